@@ -0,0 +1,215 @@
+//! Compares the locks of this crate against `std`'s `OnceCell`, `OnceLock` and `LazyLock`.
+//!
+//! Run with `cargo bench --bench compare`.
+
+use std::cell::OnceCell;
+use std::hint::black_box;
+use std::sync::{Barrier, LazyLock, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use in_place_once_cell::{InPlaceOnceCell, InPlaceOnceLock};
+
+const U32_INIT: u32 = 34;
+const U32_MUTATED: u32 = U32_INIT * U32_INIT;
+
+const THREADS: usize = 4;
+const LOCKS: usize = 1024;
+
+const fn u32_square(val: &mut u32) {
+    *val *= *val;
+}
+
+/// Reading an already mutated lock, the path every caller takes after the first.
+fn uncontended_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uncontended_read");
+
+    let cell = InPlaceOnceCell::new(U32_INIT);
+    cell.get_or_mutate(u32_square);
+    group.bench_function("InPlaceOnceCell", |b| {
+        b.iter(|| *black_box(&cell).get_or_mutate(u32_square));
+    });
+
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    lock.get_or_mutate(u32_square);
+    group.bench_function("InPlaceOnceLock", |b| {
+        b.iter(|| *black_box(&lock).get_or_mutate(u32_square));
+    });
+
+    let cell = OnceCell::new();
+    cell.get_or_init(|| U32_MUTATED);
+    group.bench_function("OnceCell", |b| {
+        b.iter(|| *black_box(&cell).get_or_init(|| U32_MUTATED));
+    });
+
+    let lock = OnceLock::new();
+    lock.get_or_init(|| U32_MUTATED);
+    group.bench_function("OnceLock", |b| {
+        b.iter(|| *black_box(&lock).get_or_init(|| U32_MUTATED));
+    });
+
+    let lazy = LazyLock::new(|| U32_MUTATED);
+    LazyLock::force(&lazy);
+    group.bench_function("LazyLock", |b| b.iter(|| **black_box(&lazy)));
+
+    group.finish();
+}
+
+/// Mutating a fresh lock, including constructing it.
+fn first_mutation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("first_mutation");
+
+    group.bench_function("InPlaceOnceCell", |b| {
+        b.iter_batched_ref(
+            || InPlaceOnceCell::new(U32_INIT),
+            |cell| *cell.get_or_mutate(u32_square),
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("InPlaceOnceLock", |b| {
+        b.iter_batched_ref(
+            || InPlaceOnceLock::new(U32_INIT),
+            |lock| *lock.get_or_mutate(u32_square),
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("OnceCell", |b| {
+        b.iter_batched_ref(
+            OnceCell::new,
+            |cell| *cell.get_or_init(|| black_box(U32_INIT) * U32_INIT),
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("OnceLock", |b| {
+        b.iter_batched_ref(
+            OnceLock::new,
+            |lock| *lock.get_or_init(|| black_box(U32_INIT) * U32_INIT),
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("LazyLock", |b| {
+        b.iter_batched_ref(
+            || LazyLock::new(|| black_box(U32_INIT) * U32_INIT),
+            |lazy| **lazy,
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
+/// Runs `race` on a fresh lock from `THREADS` threads released together, `iters` times, and
+/// returns the time spent from the release until every thread is done.
+fn time_race<L: Sync>(iters: u64, new: impl Fn() -> L, race: impl Fn(&L) + Sync) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..iters {
+        let lock = new();
+        let barrier = Barrier::new(THREADS + 1);
+        let started = thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    barrier.wait();
+                    race(&lock);
+                });
+            }
+
+            barrier.wait();
+            Instant::now()
+        });
+        total += started.elapsed();
+    }
+
+    total
+}
+
+/// Several threads racing to mutate the same fresh lock.
+fn contended_mutation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("contended_mutation");
+
+    group.bench_function("InPlaceOnceLock", |b| {
+        b.iter_custom(|iters| {
+            time_race(
+                iters,
+                || InPlaceOnceLock::new(U32_INIT),
+                |lock| {
+                    black_box(lock.get_or_mutate(u32_square));
+                },
+            )
+        });
+    });
+
+    group.bench_function("OnceLock", |b| {
+        b.iter_custom(|iters| {
+            time_race(iters, OnceLock::new, |lock| {
+                black_box(lock.get_or_init(|| black_box(U32_INIT) * U32_INIT));
+            })
+        });
+    });
+
+    group.bench_function("LazyLock", |b| {
+        b.iter_custom(|iters| {
+            time_race(
+                iters,
+                || LazyLock::new(|| black_box(U32_INIT) * U32_INIT),
+                |lazy| {
+                    black_box(**lazy);
+                },
+            )
+        });
+    });
+
+    group.finish();
+}
+
+/// Mutating then reading every lock of a large array, as in a table of lazily computed entries.
+fn lock_array(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lock_array");
+
+    group.bench_function("InPlaceOnceLock", |b| {
+        b.iter_batched_ref(
+            || -> Vec<_> { (0..LOCKS).map(|_| InPlaceOnceLock::new(U32_INIT)).collect() },
+            |locks| {
+                for lock in locks.iter() {
+                    lock.get_or_mutate(u32_square);
+                }
+                locks
+                    .iter()
+                    .map(|lock| *lock.get_or_mutate(u32_square))
+                    .sum::<u32>()
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("OnceLock", |b| {
+        b.iter_batched_ref(
+            || -> Vec<OnceLock<u32>> { (0..LOCKS).map(|_| OnceLock::new()).collect() },
+            |locks| {
+                for lock in locks.iter() {
+                    lock.get_or_init(|| black_box(U32_INIT) * U32_INIT);
+                }
+                locks
+                    .iter()
+                    .map(|lock| *lock.get_or_init(|| black_box(U32_INIT) * U32_INIT))
+                    .sum::<u32>()
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    uncontended_read,
+    first_mutation,
+    contended_mutation,
+    lock_array
+);
+criterion_main!(benches);
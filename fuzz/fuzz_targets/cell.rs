@@ -0,0 +1,10 @@
+#![no_main]
+
+use in_place_once_cell::InPlaceOnceCell;
+use in_place_once_cell_fuzz::{Op, run};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (u32, Vec<Op>)| {
+    let (init, ops) = input;
+    run::<InPlaceOnceCell<u32>>(init, &ops);
+});
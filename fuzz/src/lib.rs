@@ -0,0 +1,223 @@
+//! Drives arbitrary sequences of operations against the cells and locks of `in_place_once_cell`,
+//! checking each step against a model.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use arbitrary::Arbitrary;
+use in_place_once_cell::{InPlaceOnceCell, InPlaceOnceLock, PackedInPlaceOnceCell};
+
+/// An operation on a cell holding a `u32`. Every mutator adds to the value in place.
+#[derive(Arbitrary, Debug, Clone, Copy)]
+pub enum Op {
+    /// Reads the cell.
+    Get,
+    /// Mutates the cell with `get_or_mutate`.
+    Mutate(u32),
+    /// Mutates the cell with `get_or_try_mutate`, failing after adding if `fail` is set.
+    TryMutate { add: u32, fail: bool },
+    /// Mutates the cell with a mutator that panics after adding.
+    Panic(u32),
+    /// Takes the value and the mutated flag out of the cell, then puts them back.
+    Take,
+    /// Takes the value out of the cell, then puts it back untouched.
+    Reset,
+}
+
+/// A cell under test.
+pub trait Subject: Sized {
+    /// Whether a panicking mutator poisons the cell for good.
+    const POISONS: bool;
+
+    fn new(value: u32) -> Self;
+
+    fn get(&self) -> Option<u32>;
+
+    fn get_or_mutate(&self, f: impl FnOnce(&mut u32)) -> u32;
+
+    fn get_or_try_mutate(&self, f: impl FnOnce(&mut u32) -> Result<(), ()>) -> Result<u32, ()>;
+
+    /// Consumes the cell, returning its value and whether it was mutated.
+    fn take(self) -> (u32, bool);
+
+    /// Rebuilds a cell from the parts returned by [`Subject::take`].
+    fn put(value: u32, mutated: bool) -> Self;
+}
+
+/// What a cell should hold.
+#[derive(Debug)]
+struct Model {
+    value: u32,
+    mutated: bool,
+    poisoned: bool,
+}
+
+impl Model {
+    fn get(&self) -> Option<u32> {
+        self.mutated.then_some(self.value)
+    }
+}
+
+/// Applies `ops` to a cell starting out as `init`, asserting after each one that the cell agrees
+/// with the model.
+pub fn run<S: Subject>(init: u32, ops: &[Op]) {
+    let mut model = Model {
+        value: init,
+        mutated: false,
+        poisoned: false,
+    };
+    let mut cell = Some(S::new(init));
+
+    for &op in ops {
+        let c = cell.as_ref().unwrap();
+        match op {
+            Op::Get => {}
+            // Mutating a poisoned cell panics through the panic hook, which aborts under
+            // libFuzzer, so that is left to the tests.
+            Op::Mutate(..) | Op::TryMutate { .. } | Op::Panic(..) if model.poisoned => {}
+            Op::Mutate(add) => {
+                let res = c.get_or_mutate(|val| *val = val.wrapping_add(add));
+                if !model.mutated {
+                    model.value = model.value.wrapping_add(add);
+                    model.mutated = true;
+                }
+                assert_eq!(res, model.value);
+            }
+            Op::TryMutate { add, fail } => {
+                let res = c.get_or_try_mutate(|val| {
+                    *val = val.wrapping_add(add);
+                    if fail { Err(()) } else { Ok(()) }
+                });
+                if !model.mutated {
+                    model.value = model.value.wrapping_add(add);
+                    model.mutated = !fail;
+                }
+                let expected = if model.mutated {
+                    Ok(model.value)
+                } else {
+                    Err(())
+                };
+                assert_eq!(res, expected);
+            }
+            Op::Panic(add) => {
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    c.get_or_mutate(|val| {
+                        *val = val.wrapping_add(add);
+                        // Skips the panic hook, which aborts under libFuzzer.
+                        panic::resume_unwind(Box::new("mutator panicked"));
+                    })
+                }));
+                if model.mutated {
+                    assert_eq!(res.ok(), Some(model.value));
+                } else {
+                    assert!(res.is_err(), "the mutator did not run");
+                    model.value = model.value.wrapping_add(add);
+                    model.poisoned = S::POISONS;
+                }
+            }
+            Op::Take | Op::Reset => {
+                let (value, mutated) = cell.take().unwrap().take();
+                assert_eq!((value, mutated), (model.value, model.mutated));
+                if let Op::Reset = op {
+                    model.mutated = false;
+                }
+                model.poisoned = false;
+                cell = Some(S::put(value, model.mutated));
+            }
+        }
+
+        assert_eq!(cell.as_ref().unwrap().get(), model.get());
+    }
+}
+
+impl Subject for InPlaceOnceCell<u32> {
+    const POISONS: bool = false;
+
+    fn new(value: u32) -> Self {
+        Self::new(value)
+    }
+
+    fn get(&self) -> Option<u32> {
+        self.get().copied()
+    }
+
+    fn get_or_mutate(&self, f: impl FnOnce(&mut u32)) -> u32 {
+        *self.get_or_mutate(f)
+    }
+
+    fn get_or_try_mutate(&self, f: impl FnOnce(&mut u32) -> Result<(), ()>) -> Result<u32, ()> {
+        self.get_or_try_mutate(f).copied()
+    }
+
+    fn take(self) -> (u32, bool) {
+        self.into_raw_parts()
+    }
+
+    fn put(value: u32, mutated: bool) -> Self {
+        // SAFETY: the parts come from `into_raw_parts`, or are marked untouched.
+        unsafe { Self::from_raw_parts(value, mutated) }
+    }
+}
+
+impl Subject for InPlaceOnceLock<u32> {
+    const POISONS: bool = true;
+
+    fn new(value: u32) -> Self {
+        Self::new(value)
+    }
+
+    fn get(&self) -> Option<u32> {
+        self.get().copied()
+    }
+
+    fn get_or_mutate(&self, f: impl FnOnce(&mut u32)) -> u32 {
+        *self.get_or_mutate(f)
+    }
+
+    fn get_or_try_mutate(&self, f: impl FnOnce(&mut u32) -> Result<(), ()>) -> Result<u32, ()> {
+        self.get_or_try_mutate(f).copied()
+    }
+
+    fn take(self) -> (u32, bool) {
+        self.into_raw_parts()
+    }
+
+    fn put(value: u32, mutated: bool) -> Self {
+        // SAFETY: the parts come from `into_raw_parts`, or are marked untouched.
+        unsafe { Self::from_raw_parts(value, mutated) }
+    }
+}
+
+/// Holds the value in a `Box`, whose pointer lends its spare bit to the cell.
+impl Subject for PackedInPlaceOnceCell<Box<u32>> {
+    const POISONS: bool = false;
+
+    fn new(value: u32) -> Self {
+        Self::new(Box::new(value))
+    }
+
+    fn get(&self) -> Option<u32> {
+        self.get().map(|val| **val)
+    }
+
+    fn get_or_mutate(&self, f: impl FnOnce(&mut u32)) -> u32 {
+        **self.get_or_mutate(|val| f(val))
+    }
+
+    fn get_or_try_mutate(&self, f: impl FnOnce(&mut u32) -> Result<(), ()>) -> Result<u32, ()> {
+        self.get_or_try_mutate(|val| f(val)).map(|val| **val)
+    }
+
+    fn take(self) -> (u32, bool) {
+        let mutated = self.get().is_some();
+        (*self.into_inner(), mutated)
+    }
+
+    fn put(value: u32, mutated: bool) -> Self {
+        let cell = Self::new(Box::new(value));
+        if mutated {
+            cell.get_or_mutate(|_| {});
+        }
+
+        cell
+    }
+}
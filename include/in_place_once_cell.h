@@ -0,0 +1,87 @@
+#ifndef IN_PLACE_ONCE_CELL_H
+#define IN_PLACE_ONCE_CELL_H
+
+/* Generated with cbindgen from `src/ffi.rs`. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A type-erased [`InPlaceOnceLock`] holding `size` bytes.
+ *
+ * Only ever handled through a pointer returned by [`ipoc_new`].
+ */
+typedef struct IpocLock IpocLock;
+
+/**
+ * A mutator called with a pointer to the value and the `user_data` passed alongside it.
+ */
+typedef void (*IpocMutator)(void *value, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif  // __cplusplus
+
+/**
+ * Creates a lock holding `size` bytes, copied from `init`, or zeroed if `init` is null.
+ *
+ * The returned lock must be freed with [`ipoc_free`].
+ *
+ * # Safety
+ *
+ * `init` must be null or valid for reads of `size` bytes.
+ */
+IpocLock *ipoc_new(const void *init, size_t size);
+
+/**
+ * Returns the number of bytes held by `lock`.
+ *
+ * # Safety
+ *
+ * `lock` must have been returned by [`ipoc_new`] and not yet freed.
+ */
+size_t ipoc_size(const IpocLock *lock);
+
+/**
+ * Returns a pointer to the value of `lock`, or null if it is not mutated.
+ *
+ * The value must not be written through the returned pointer.
+ *
+ * # Safety
+ *
+ * `lock` must have been returned by [`ipoc_new`] and not yet freed. The returned pointer is
+ * valid until `lock` is freed.
+ */
+const void *ipoc_get(const IpocLock *lock);
+
+/**
+ * Returns a pointer to the value of `lock`, mutating it with `mutator` if it was never mutated.
+ *
+ * `mutator` is called at most once per lock, with the value and `user_data`. Other threads
+ * calling this concurrently block until it returns.
+ *
+ * # Safety
+ *
+ * `lock` must have been returned by [`ipoc_new`] and not yet freed, and `mutator` must be safe
+ * to call with `user_data`. The returned pointer is valid until `lock` is freed.
+ */
+const void *ipoc_get_or_mutate(const IpocLock *lock, IpocMutator mutator, void *user_data);
+
+/**
+ * Frees a lock returned by [`ipoc_new`]. Does nothing if `lock` is null.
+ *
+ * # Safety
+ *
+ * `lock` must be null, or have been returned by [`ipoc_new`] and not yet freed. No pointer
+ * returned for it may be used afterwards.
+ */
+void ipoc_free(IpocLock *lock);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* IN_PLACE_ONCE_CELL_H */
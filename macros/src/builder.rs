@@ -0,0 +1,93 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Result};
+
+use crate::once_fields::named_fields;
+
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let fields = named_fields(&input, "OnceBuilder")?;
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let builder = format_ident!("{}Builder", ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let generics = &input.generics;
+
+    let names: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let tys: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let field_vis: Vec<_> = fields.iter().map(|f| &f.vis).collect();
+
+    let doc = format!("A builder for [`{ident}`] whose setters may each be called only once.");
+    let builder_doc = format!("Creates a [`{builder}`] with no fields set.");
+    let setter_docs = names
+        .iter()
+        .map(|n| format!("Sets `{n}`.\n\nReturns `value` back if `{n}` was already set."));
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #builder #generics #where_clause {
+            #(#names: ::in_place_once_cell::InPlaceOnceCell<::core::option::Option<#tys>>,)*
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #[doc = #builder_doc]
+            #[inline]
+            #[must_use]
+            #vis fn builder() -> #builder #ty_generics {
+                #builder::new()
+            }
+        }
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            /// Creates a builder with no fields set.
+            #[inline]
+            #[must_use]
+            #vis fn new() -> Self {
+                Self {
+                    #(#names: ::in_place_once_cell::InPlaceOnceCell::new(::core::option::Option::None),)*
+                }
+            }
+
+            #(
+                #[doc = #setter_docs]
+                #field_vis fn #names(&self, value: #tys) -> ::core::result::Result<&Self, #tys> {
+                    if self.#names.get().is_some() {
+                        return ::core::result::Result::Err(value);
+                    }
+
+                    self.#names.get_or_mutate(|slot| *slot = ::core::option::Option::Some(value));
+                    ::core::result::Result::Ok(self)
+                }
+            )*
+
+            /// Returns `true` if every field is set.
+            #[inline]
+            #vis fn is_complete(&self) -> bool {
+                true #(&& self.#names.get().is_some())*
+            }
+
+            /// Consumes the builder, returning the built struct if every field is set.
+            ///
+            /// Returns `self` unchanged otherwise.
+            #vis fn build(self) -> ::core::result::Result<#ident #ty_generics, Self> {
+                if !self.is_complete() {
+                    return ::core::result::Result::Err(self);
+                }
+
+                ::core::result::Result::Ok(#ident {
+                    #(#names: match self.#names.into_inner() {
+                        ::core::option::Option::Some(value) => value,
+                        ::core::option::Option::None => ::core::unreachable!(),
+                    },)*
+                })
+            }
+        }
+
+        impl #impl_generics ::core::default::Default for #builder #ty_generics #where_clause {
+            #[inline]
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    })
+}
@@ -0,0 +1,65 @@
+//! Procedural macros for `in_place_once_cell`. Use them through the `derive` feature of that
+//! crate rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, ItemFn, parse_macro_input};
+
+mod builder;
+mod memoize;
+mod once_fields;
+
+/// Generates a sibling of a struct with named fields, where every field is an independently
+/// once-mutable `InPlaceOnceCell`.
+///
+/// For `struct Foo { bar: T }` this generates `struct FooOnceFields` with the same visibility and
+/// generics, along with:
+/// - `new(initial: Foo)` and `From<Foo>`, using `initial`'s fields as placeholders,
+/// - `get_bar(&self) -> Option<&T>` and `mutate_bar(&self, f) -> &T` for every field,
+/// - `is_complete(&self) -> bool`, which is `true` once every field is mutated,
+/// - `finish(self) -> Result<Foo, Self>`, which succeeds once every field is mutated.
+#[proc_macro_derive(InPlaceOnceFields)]
+pub fn derive_in_place_once_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    once_fields::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generates a builder for a struct with named fields, whose setters may each be called only
+/// once.
+///
+/// For `struct Foo { bar: T }` this generates `struct FooBuilder` with the same visibility and
+/// generics, where every field is an `InPlaceOnceCell`, along with:
+/// - `Foo::builder()`, `FooBuilder::new()` and `Default`, which create a builder with no fields
+///   set,
+/// - `bar(&self, value: T) -> Result<&Self, T>` for every field, which hands `value` back if the
+///   field was already set,
+/// - `is_complete(&self) -> bool`, which is `true` once every field is set,
+/// - `build(self) -> Result<Foo, Self>`, which succeeds once every field is set.
+#[proc_macro_derive(OnceBuilder)]
+pub fn derive_once_builder(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    builder::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Memoizes a function that mutates a value in place, turning it into a getter for a hidden
+/// static `InPlaceOnceLock`.
+///
+/// The function must take exactly one `&mut T` argument and return nothing. It is rewritten to
+/// take no arguments and return `&'static T`; the original body runs at most once, on the first
+/// call, mutating the value in place.
+///
+/// By default, the value starts out as `T::default()`. Use `#[memoize_in_place(init = EXPR)]` to
+/// start from a constant expression instead, which avoids the extra lazy initialization.
+#[proc_macro_attribute]
+pub fn memoize_in_place(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut parsed = memoize::Args::default();
+    let parser = syn::meta::parser(|meta| parsed.parse(meta));
+    parse_macro_input!(args with parser);
+    let item = parse_macro_input!(item as ItemFn);
+    memoize::expand(parsed, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,93 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::{Error, Expr, FnArg, ItemFn, Result, ReturnType, Type};
+
+#[derive(Default)]
+pub(crate) struct Args {
+    init: Option<Expr>,
+}
+
+impl Args {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta<'_>) -> Result<()> {
+        if meta.path.is_ident("init") {
+            self.init = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported `memoize_in_place` argument"))
+        }
+    }
+}
+
+pub(crate) fn expand(args: Args, item: ItemFn) -> Result<TokenStream> {
+    let sig = &item.sig;
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "`memoize_in_place` functions cannot be generic",
+        ));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(Error::new_spanned(
+            asyncness,
+            "`memoize_in_place` functions cannot be async",
+        ));
+    }
+    if let ReturnType::Type(_, ty) = &sig.output {
+        return Err(Error::new_spanned(
+            ty,
+            "`memoize_in_place` functions mutate their argument instead of returning a value",
+        ));
+    }
+
+    let mut inputs = sig.inputs.iter();
+    let (pat, ty) = match (inputs.next(), inputs.next()) {
+        (Some(FnArg::Typed(arg)), None) => match &*arg.ty {
+            Type::Reference(r) if r.mutability.is_some() => (&arg.pat, &r.elem),
+            ty => {
+                return Err(Error::new_spanned(
+                    ty,
+                    "the argument of a `memoize_in_place` function must be `&mut T`",
+                ));
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &sig.inputs,
+                "`memoize_in_place` functions take exactly one `&mut T` argument",
+            ));
+        }
+    };
+
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let ident = &sig.ident;
+    let body = &item.block;
+
+    // Without a constant initial value, default-construct it on first access instead.
+    let get = match &args.init {
+        Some(init) => quote! {
+            static LOCK: ::in_place_once_cell::InPlaceOnceLock<#ty> =
+                ::in_place_once_cell::InPlaceOnceLock::new(#init);
+            LOCK.get_or_mutate(mutate)
+        },
+        None => quote! {
+            static LOCK: ::std::sync::LazyLock<::in_place_once_cell::InPlaceOnceLock<#ty>> =
+                ::std::sync::LazyLock::new(|| {
+                    ::in_place_once_cell::InPlaceOnceLock::new(
+                        <#ty as ::core::default::Default>::default(),
+                    )
+                });
+            LOCK.get_or_mutate(mutate)
+        },
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis fn #ident() -> &'static #ty {
+            fn mutate(#pat: &mut #ty) #body
+
+            #get
+        }
+    })
+}
@@ -0,0 +1,116 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{Data, DeriveInput, Error, Field, Fields, Result};
+
+/// Returns the fields of a struct with named fields, or an error naming `derive`.
+pub(crate) fn named_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str,
+) -> Result<&'a Punctuated<Field, Comma>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(Error::new_spanned(
+                &input.ident,
+                format!("`{derive}` requires a struct with named fields"),
+            )),
+        },
+        _ => Err(Error::new_spanned(
+            &input.ident,
+            format!("`{derive}` can only be derived for structs"),
+        )),
+    }
+}
+
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let fields = named_fields(&input, "InPlaceOnceFields")?;
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let sibling = format_ident!("{}OnceFields", ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let generics = &input.generics;
+
+    let names: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let tys: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let field_vis: Vec<_> = fields.iter().map(|f| &f.vis).collect();
+    let getters: Vec<_> = names.iter().map(|n| format_ident!("get_{}", n)).collect();
+    let mutators: Vec<_> = names
+        .iter()
+        .map(|n| format_ident!("mutate_{}", n))
+        .collect();
+
+    let doc =
+        format!("A copy of [`{ident}`] where every field is independently mutable once, in place.");
+    let getter_docs = names
+        .iter()
+        .map(|n| format!("Gets `{n}` if it is mutated."));
+    let mutator_docs = names
+        .iter()
+        .map(|n| format!("Gets `{n}`, mutating it with `f` if it was never mutated."));
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #sibling #generics #where_clause {
+            #(#names: ::in_place_once_cell::InPlaceOnceCell<#tys>,)*
+        }
+
+        impl #impl_generics #sibling #ty_generics #where_clause {
+            /// Creates the fields from `initial`, none of which are mutated.
+            #[inline]
+            #[must_use]
+            #vis fn new(initial: #ident #ty_generics) -> Self {
+                Self {
+                    #(#names: ::in_place_once_cell::InPlaceOnceCell::new(initial.#names),)*
+                }
+            }
+
+            #(
+                #[doc = #getter_docs]
+                #[inline]
+                #field_vis fn #getters(&self) -> ::core::option::Option<&#tys> {
+                    self.#names.get()
+                }
+
+                #[doc = #mutator_docs]
+                #[inline]
+                #field_vis fn #mutators<F>(&self, f: F) -> &#tys
+                where
+                    F: ::core::ops::FnOnce(&mut #tys),
+                {
+                    self.#names.get_or_mutate(f)
+                }
+            )*
+
+            /// Returns `true` if every field is mutated.
+            #[inline]
+            #vis fn is_complete(&self) -> bool {
+                true #(&& self.#names.get().is_some())*
+            }
+
+            /// Consumes the fields, returning the original struct if every field is mutated.
+            ///
+            /// Returns `self` unchanged otherwise.
+            #vis fn finish(self) -> ::core::result::Result<#ident #ty_generics, Self> {
+                if !self.is_complete() {
+                    return ::core::result::Result::Err(self);
+                }
+
+                ::core::result::Result::Ok(#ident {
+                    #(#names: self.#names.into_inner(),)*
+                })
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#ident #ty_generics>
+            for #sibling #ty_generics #where_clause
+        {
+            #[inline]
+            fn from(initial: #ident #ty_generics) -> Self {
+                Self::new(initial)
+            }
+        }
+    })
+}
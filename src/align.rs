@@ -0,0 +1,160 @@
+//! Over-aligned cells, for DMA buffers and SIMD data.
+//!
+//! [`AlignedOnceCell<T, A>`] stores its value at an address aligned to at least the alignment of
+//! `A`, which can be any type: one of the markers in this module, or a `#[repr(align(N))]` type
+//! of the caller's own. The markers are zero-sized, so a cell is only as much larger than an
+//! [`InPlaceOnceCell<T>`] as the padding that the alignment requires.
+
+use core::fmt;
+
+use crate::InPlaceOnceCell;
+use crate::debug::{self, LockState};
+
+/// Aligns a cell's value to 16 bytes.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(align(16))]
+pub struct Align16;
+
+/// Aligns a cell's value to 32 bytes.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(align(32))]
+pub struct Align32;
+
+/// Aligns a cell's value to 64 bytes, a cache line on most targets.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(align(64))]
+pub struct Align64;
+
+/// Aligns a cell's value to 128 bytes.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(align(128))]
+pub struct Align128;
+
+/// Aligns a cell's value to 4096 bytes, a page on most targets.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(align(4096))]
+pub struct Align4096;
+
+/// A value aligned to at least the alignment of `A`. The value is at offset 0.
+#[repr(C)]
+struct Aligned<T, A> {
+    value: T,
+    _align: [A; 0],
+}
+
+const _: () = {
+    assert!(align_of::<Aligned<u8, Align64>>() == 64);
+    assert!(size_of::<Aligned<u8, Align64>>() == 64);
+    assert!(align_of::<Aligned<u64, Align16>>() == 16);
+    assert!(align_of::<Aligned<[u8; 3], Align4096>>() == 4096);
+};
+
+/// A cell that can only be mutated once, whose value is aligned to at least the alignment of
+/// `A`.
+///
+/// The value's address is guaranteed to be a multiple of both `align_of::<A>()` and
+/// `align_of::<T>()`, wherever the cell itself is stored, so references to it can be handed to
+/// hardware or SIMD loads that require it.
+///
+/// ```
+/// use in_place_once_cell::AlignedOnceCell;
+/// use in_place_once_cell::align::Align64;
+///
+/// let buf = AlignedOnceCell::<[u8; 256], Align64>::new([0; 256]);
+/// let val = buf.get_or_mutate(|buf| buf.fill(0xff));
+/// assert_eq!(val.as_ptr().addr() % 64, 0);
+/// ```
+pub struct AlignedOnceCell<T, A> {
+    cell: InPlaceOnceCell<Aligned<T, A>>,
+}
+
+impl<T, A> AlignedOnceCell<T, A> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            cell: InPlaceOnceCell::new(Aligned { value, _align: [] }),
+        }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.cell.get().map(|aligned| &aligned.value)
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.cell.get_mut().map(|aligned| &mut aligned.value)
+    }
+
+    /// Gets the contents of the cell, mutating it with `f()` if the cell was never mutated.
+    ///
+    /// See [`InPlaceOnceCell::get_or_mutate`].
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        &self
+            .cell
+            .get_or_mutate(|aligned: &mut Aligned<T, A>| f(&mut aligned.value))
+            .value
+    }
+
+    /// Gets the contents of the cell, mutating it with `f(&mut T)` if the cell was never
+    /// mutated.
+    ///
+    /// See [`InPlaceOnceCell::get_or_try_mutate`].
+    #[inline]
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        self.cell
+            .get_or_try_mutate(|aligned: &mut Aligned<T, A>| f(&mut aligned.value))
+            .map(|aligned| &aligned.value)
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.cell.into_inner().value
+    }
+}
+
+impl<T: Default, A> Default for AlignedOnceCell<T, A> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug, A> fmt::Debug for AlignedOnceCell<T, A> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (value, mutated) = self.cell.peek();
+        let state = if mutated {
+            LockState::Mutated
+        } else {
+            LockState::Untouched
+        };
+        debug::fmt_cell(f, "AlignedOnceCell", state, Some(&value.value), |_| {})
+    }
+}
+
+impl<T, A> From<T> for AlignedOnceCell<T, A> {
+    /// Creates a new `AlignedOnceCell<T, A>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::InPlaceOnceLock;
+
+/// A shared pointer to a lock that can only be mutated once.
+///
+/// Every clone points to the same allocation: whichever owner mutates it first does so in place,
+/// and every clone reads the value lock-free afterwards. It dereferences to the
+/// [`InPlaceOnceLock`], and keeps `Arc`'s own functions, such as [`ArcOnce::get_mut`] when it is
+/// the only owner, which wrapping an `Arc<InPlaceOnceLock<T>>` hides behind the lock's methods.
+///
+/// Like `Arc`, its own functions are associated functions, e.g. `ArcOnce::get_mut(&mut this)`,
+/// so that they never shadow the methods of the lock.
+pub struct ArcOnce<T> {
+    inner: Arc<InPlaceOnceLock<T>>,
+}
+
+impl<T> ArcOnce<T> {
+    /// Creates a new shared lock that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Arc::new(InPlaceOnceLock::new(value)),
+        }
+    }
+
+    /// Returns a mutable reference to the lock, if `this` is its only owner, like
+    /// [`Arc::get_mut`].
+    ///
+    /// With it, the lock can be mutated without waiting, or its value changed again once mutated.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut InPlaceOnceLock<T>> {
+        Arc::get_mut(&mut this.inner)
+    }
+
+    /// Returns the lock, if `this` is its only owner, or `this` otherwise, like
+    /// [`Arc::try_unwrap`].
+    #[inline]
+    pub fn try_unwrap(this: Self) -> Result<InPlaceOnceLock<T>, Self> {
+        Arc::try_unwrap(this.inner).map_err(|inner| Self { inner })
+    }
+
+    /// Returns the lock, if `this` is its last owner, like [`Arc::into_inner`].
+    ///
+    /// If every owner calls this, exactly one of them gets the lock.
+    #[inline]
+    pub fn into_inner(this: Self) -> Option<InPlaceOnceLock<T>> {
+        Arc::into_inner(this.inner)
+    }
+
+    /// Returns the number of owners of the lock, like [`Arc::strong_count`].
+    #[inline]
+    #[must_use]
+    pub fn strong_count(this: &Self) -> usize {
+        Arc::strong_count(&this.inner)
+    }
+
+    /// Returns whether both point to the same lock, like [`Arc::ptr_eq`].
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.inner, &other.inner)
+    }
+}
+
+impl<T> Clone for ArcOnce<T> {
+    /// Makes another owner of the same lock.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> Deref for ArcOnce<T> {
+    type Target = InPlaceOnceLock<T>;
+
+    #[inline]
+    fn deref(&self) -> &InPlaceOnceLock<T> {
+        &self.inner
+    }
+}
+
+impl<T: Default> Default for ArcOnce<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArcOnce<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.inner, f)
+    }
+}
+
+impl<T> From<T> for ArcOnce<T> {
+    /// Creates a new `ArcOnce<T>` containing `value`. This new lock is not yet mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> From<Arc<InPlaceOnceLock<T>>> for ArcOnce<T> {
+    /// Wraps a lock that is already shared.
+    #[inline]
+    fn from(inner: Arc<InPlaceOnceLock<T>>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T> From<ArcOnce<T>> for Arc<InPlaceOnceLock<T>> {
+    #[inline]
+    fn from(arc: ArcOnce<T>) -> Self {
+        arc.inner
+    }
+}
@@ -0,0 +1,368 @@
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomPinned;
+use core::mem;
+use core::pin::Pin;
+use core::ptr;
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use critical_section::CriticalSection;
+
+use crate::debug::{self, LockState};
+
+const UNTOUCHED: u8 = 0;
+const MUTATING: u8 = 1;
+const MUTATED: u8 = 2;
+
+/// A cell that can only be mutated once, with an asynchronous mutator, for embedded executors.
+///
+/// Unlike [`AsyncInPlaceOnceLock`](crate::AsyncInPlaceOnceLock), this never allocates: waiting
+/// futures register their wakers in an intrusive list stored inside the futures themselves, and
+/// every state transition happens inside a [`critical_section`]. Atomics are only ever loaded and
+/// stored, never compared-and-swapped, so this works on targets such as `thumbv6m` as well.
+///
+/// # Cancellation
+///
+/// Dropping a future that is running its mutator returns the cell to the untouched state and
+/// wakes every queued mutator to retry. As with an error, the next mutator runs on whatever the
+/// abandoned one left behind.
+pub struct AsyncInPlaceOnceCsLock<T> {
+    value: UnsafeCell<T>,
+    state: AtomicU8,
+    /// The head of the intrusive list of waiting futures. Only accessed inside a critical
+    /// section.
+    waiters: Cell<*const Waiter>,
+}
+
+/// A node of the intrusive waiter list, pinned inside a [`Wait`] future.
+struct Waiter {
+    waker: Cell<Option<Waker>>,
+    prev: Cell<*const Waiter>,
+    next: Cell<*const Waiter>,
+    linked: Cell<bool>,
+    _pinned: PhantomPinned,
+}
+
+impl<T> AsyncInPlaceOnceCsLock<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            state: AtomicU8::new(UNTOUCHED),
+            waiters: Cell::new(ptr::null()),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.load(Ordering::Acquire) == MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == MUTATED {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Waits until the cell is mutated by some other task, then returns a reference to the value.
+    pub async fn wait(&self) -> &T {
+        Wait::new(self, || {
+            (self.state.load(Ordering::Acquire) == MUTATED).then_some(())
+        })
+        .await;
+        // SAFETY: `Wait` only completes once the cell is mutated.
+        unsafe { self.get_unchecked() }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped while `f` is running, the cell stays unmutated and the
+    /// queued mutators retry.
+    pub async fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: AsyncFnOnce(&mut T),
+    {
+        match self
+            .get_or_try_mutate(async |val: &mut T| {
+                f(val).await;
+                Ok::<(), ()>(())
+            })
+            .await
+        {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated and the queued mutators retry.
+    pub async fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: AsyncFnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+
+        // Checking and claiming the cell both happen inside the same critical section.
+        let claimed = Wait::new(self, || match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => {
+                self.state.store(MUTATING, Ordering::Relaxed);
+                Some(true)
+            }
+            MUTATED => Some(false),
+            _ => None,
+        })
+        .await;
+
+        if !claimed {
+            // SAFETY: the cell is mutated.
+            return Ok(unsafe { self.get_unchecked() });
+        }
+
+        let mutation = Mutation { lock: self };
+        // SAFETY: the `MUTATING` state excludes every other reader and writer until `mutation`
+        // is dropped or published.
+        f(unsafe { &mut *self.value.get() }).await?;
+        Ok(mutation.publish())
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// Consumes the cell, returning the wrapped value and whether the cell was mutated.
+    ///
+    /// See [`InPlaceOnceCell::into_raw_parts`](crate::InPlaceOnceCell::into_raw_parts).
+    #[inline]
+    pub fn into_raw_parts(mut self) -> (T, bool) {
+        let mutated = *self.state.get_mut() == MUTATED;
+        (self.into_inner(), mutated)
+    }
+
+    /// Reassembles a cell from the parts returned by [`AsyncInPlaceOnceCsLock::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for
+    /// [`InPlaceOnceCell::from_raw_parts`](crate::InPlaceOnceCell::from_raw_parts).
+    #[inline]
+    #[must_use]
+    pub const unsafe fn from_raw_parts(value: T, mutated: bool) -> Self {
+        let mut lock = Self::new(value);
+        if mutated {
+            lock.state = AtomicU8::new(MUTATED);
+        }
+
+        lock
+    }
+
+    fn link(&self, _cs: CriticalSection<'_>, waiter: &Waiter) {
+        let head = self.waiters.get();
+        waiter.prev.set(ptr::null());
+        waiter.next.set(head);
+        if !head.is_null() {
+            // SAFETY: linked waiters are pinned and unlink themselves before being dropped.
+            unsafe { (*head).prev.set(waiter) };
+        }
+        self.waiters.set(waiter);
+        waiter.linked.set(true);
+    }
+
+    fn unlink(&self, _cs: CriticalSection<'_>, waiter: &Waiter) {
+        if !waiter.linked.replace(false) {
+            return;
+        }
+
+        let (prev, next) = (waiter.prev.get(), waiter.next.get());
+        // SAFETY: linked waiters are pinned and unlink themselves before being dropped.
+        unsafe {
+            match prev.as_ref() {
+                Some(prev) => prev.next.set(next),
+                None => self.waiters.set(next),
+            }
+            if let Some(next) = next.as_ref() {
+                next.prev.set(prev);
+            }
+        }
+    }
+
+    /// Unlinks and wakes every waiter. Woken waiters re-register if they need to keep waiting.
+    fn wake_all(&self, _cs: CriticalSection<'_>) {
+        let mut cur = self.waiters.replace(ptr::null());
+        // SAFETY: linked waiters are pinned and unlink themselves before being dropped, which
+        // cannot happen while we are inside the critical section.
+        while let Some(waiter) = unsafe { cur.as_ref() } {
+            cur = waiter.next.get();
+            waiter.linked.set(false);
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A future that completes once `check`, run inside a critical section, returns `Some`.
+struct Wait<'a, T, F> {
+    lock: &'a AsyncInPlaceOnceCsLock<T>,
+    check: F,
+    waiter: Waiter,
+}
+
+impl<'a, T, F> Wait<'a, T, F> {
+    fn new(lock: &'a AsyncInPlaceOnceCsLock<T>, check: F) -> Self {
+        Self {
+            lock,
+            check,
+            waiter: Waiter {
+                waker: Cell::new(None),
+                prev: Cell::new(ptr::null()),
+                next: Cell::new(ptr::null()),
+                linked: Cell::new(false),
+                _pinned: PhantomPinned,
+            },
+        }
+    }
+}
+
+impl<T, F, R> Future for Wait<'_, T, F>
+where
+    F: FnMut() -> Option<R>,
+{
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        // SAFETY: `waiter` is never moved out of the pinned future.
+        let this = unsafe { self.get_unchecked_mut() };
+        critical_section::with(|cs| {
+            if let Some(res) = (this.check)() {
+                this.lock.unlink(cs, &this.waiter);
+                return Poll::Ready(res);
+            }
+
+            let waker = match this.waiter.waker.take() {
+                Some(waker) if waker.will_wake(cx.waker()) => waker,
+                _ => cx.waker().clone(),
+            };
+            this.waiter.waker.set(Some(waker));
+            if !this.waiter.linked.get() {
+                this.lock.link(cs, &this.waiter);
+            }
+
+            Poll::Pending
+        })
+    }
+}
+
+impl<T, F> Drop for Wait<'_, T, F> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| self.lock.unlink(cs, &self.waiter));
+    }
+}
+
+// SAFETY: the waiter list is only touched inside a critical section.
+unsafe impl<T: Sync, F: Send> Send for Wait<'_, T, F> {}
+
+/// Exclusive access to an unmutated cell.
+///
+/// Dropping this without calling [`Mutation::publish`] returns the cell to its untouched state.
+struct Mutation<'a, T> {
+    lock: &'a AsyncInPlaceOnceCsLock<T>,
+}
+
+impl<'a, T> Mutation<'a, T> {
+    fn publish(self) -> &'a T {
+        let lock = self.lock;
+        mem::forget(self);
+
+        critical_section::with(|cs| {
+            lock.state.store(MUTATED, Ordering::Release);
+            lock.wake_all(cs);
+        });
+        // SAFETY: the cell was just mutated.
+        unsafe { lock.get_unchecked() }
+    }
+}
+
+impl<T> Drop for Mutation<'_, T> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| {
+            self.lock.state.store(UNTOUCHED, Ordering::Release);
+            self.lock.wake_all(cs);
+        });
+    }
+}
+
+// SAFETY: the waiter list is only touched inside a critical section.
+unsafe impl<T: Send> Send for AsyncInPlaceOnceCsLock<T> {}
+// SAFETY: a shared reference hands out `&mut T` to exactly one mutator and `&T` afterwards, and
+// the waiter list is only touched inside a critical section.
+unsafe impl<T: Send + Sync> Sync for AsyncInPlaceOnceCsLock<T> {}
+
+impl<T: Default> Default for AsyncInPlaceOnceCsLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AsyncInPlaceOnceCsLock<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is untouched, being mutated, or
+    /// mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            MUTATING => LockState::Mutating,
+            _ => LockState::Mutated,
+        };
+        debug::fmt_cell(f, "AsyncInPlaceOnceCsLock", state, self.get(), |_| {})
+    }
+}
+
+impl<T> From<T> for AsyncInPlaceOnceCsLock<T> {
+    /// Creates a new `AsyncInPlaceOnceCsLock<T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
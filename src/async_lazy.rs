@@ -0,0 +1,94 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use alloc::boxed::Box;
+
+use crate::AsyncInPlaceOnceLock;
+
+/// The future returned by the default mutator type of [`AsyncInPlaceLazyLock`].
+pub type LazyMutatorFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// A value that is mutated in place by an async mutator on first access.
+///
+/// The default mutator type is a function pointer, so non-capturing closures can be used to
+/// declare a `static`:
+///
+/// ```
+/// use in_place_once_cell::AsyncInPlaceLazyLock;
+///
+/// static PORTS: AsyncInPlaceLazyLock<Vec<u16>> =
+///     AsyncInPlaceLazyLock::new(Vec::new(), |ports| Box::pin(async { ports.push(8080) }));
+///
+/// # futures::executor::block_on(async {
+/// assert_eq!(PORTS.get().await, &[8080]);
+/// # });
+/// ```
+///
+/// # Cancellation
+///
+/// The mutator is kept for as long as the lock lives, so that it can be run again when a
+/// mutation is cancelled. See [`AsyncInPlaceOnceLock`] for details.
+pub struct AsyncInPlaceLazyLock<T, F = fn(&mut T) -> LazyMutatorFuture<'_>> {
+    lock: AsyncInPlaceOnceLock<T>,
+    mutator: F,
+}
+
+impl<T, F> AsyncInPlaceLazyLock<T, F>
+where
+    F: AsyncFn(&mut T),
+{
+    /// Creates a new lazy lock that mutates `value` with `mutator` on first access.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T, mutator: F) -> Self {
+        Self {
+            lock: AsyncInPlaceOnceLock::new(value),
+            mutator,
+        }
+    }
+
+    /// Forces the mutation of this lazy lock and returns a reference to the value.
+    ///
+    /// This is equivalent to [`AsyncInPlaceLazyLock::get`].
+    pub async fn force(this: &Self) -> &T {
+        this.lock
+            .get_or_mutate(async |val| (this.mutator)(val).await)
+            .await
+    }
+
+    /// Gets the contents of the lock, mutating it first if that has not happened yet.
+    #[inline]
+    pub async fn get(&self) -> &T {
+        Self::force(self).await
+    }
+}
+
+impl<T, F> AsyncInPlaceLazyLock<T, F> {
+    /// Gets the contents of the lock without waiting.
+    ///
+    /// Returns `None` if the lock is not mutated.
+    #[inline]
+    pub fn try_get(&self) -> Option<&T> {
+        self.lock.get()
+    }
+
+    /// Consumes the lock, returning the wrapped value. Note that this occurs even when the lock
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.lock.into_inner()
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for AsyncInPlaceLazyLock<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_tuple("AsyncInPlaceLazyLock");
+        match self.try_get() {
+            Some(v) => d.field(v),
+            None => d.field(&format_args!("<untouched>")),
+        };
+
+        d.finish()
+    }
+}
@@ -0,0 +1,334 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::mem;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use event_listener::{Event, EventListener};
+
+use crate::debug::{self, LockState};
+use crate::errors::TimeoutError;
+#[cfg(feature = "test-util")]
+use crate::test_util::{self, HookPoint};
+use crate::trace::{self, Label, MutationTrace};
+#[cfg(feature = "debug-tracking")]
+use crate::tracking::Holder;
+
+const UNTOUCHED: u8 = 0;
+const MUTATING: u8 = 1;
+const MUTATED: u8 = 2;
+
+/// A cell that can only be mutated once, with an asynchronous mutator.
+///
+/// This only depends on `event-listener`, so it works with any executor. Runtime-specific helpers
+/// live in the `tokio` and `smol` modules.
+///
+/// # Cancellation
+///
+/// Every future returned by this type is cancellation safe. Dropping a future that is running its
+/// mutator returns the cell to the untouched state and wakes exactly one queued mutator, which
+/// then retries with its own mutator. Dropping a future that is merely waiting has no effect on
+/// the cell; if it had already been chosen to retry, the wakeup is passed on to the next queued
+/// mutator.
+///
+/// Note that an abandoned mutator may have partially modified the value, and the next mutator
+/// runs on whatever was left behind, just like after an error.
+pub struct AsyncInPlaceOnceLock<T> {
+    value: UnsafeCell<T>,
+    state: AtomicU8,
+    /// Notified when the cell gets mutated.
+    mutated: Event,
+    /// Notified when a mutator gives up, or when the cell gets mutated.
+    released: Event,
+    label: Label,
+    /// The thread running the mutator, reported when a deadline passes.
+    #[cfg(feature = "debug-tracking")]
+    holder: Holder,
+}
+
+impl<T> AsyncInPlaceOnceLock<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            state: AtomicU8::new(UNTOUCHED),
+            mutated: Event::new(),
+            released: Event::new(),
+            label: Label::NONE,
+            #[cfg(feature = "debug-tracking")]
+            holder: Holder::new(),
+        }
+    }
+
+    /// Attaches `label` to the tracing events of this lock, to tell locks holding the same type
+    /// apart. Does nothing without the `tracing` feature.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(not(feature = "tracing"), allow(unused_mut))]
+    pub const fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Label::new(label);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.load(Ordering::Acquire) == MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == MUTATED {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Waits until the cell is mutated by some other task, then returns a reference to the value.
+    pub async fn wait(&self) -> &T {
+        loop {
+            if let Some(val) = self.get() {
+                return val;
+            }
+
+            // Register before re-checking so a mutation finishing in between is not missed.
+            let listener = self.listen_mutated();
+            if let Some(val) = self.get() {
+                return val;
+            }
+
+            #[cfg(feature = "test-util")]
+            test_util::hook(HookPoint::BeforePark);
+            listener.await;
+        }
+    }
+
+    /// Returns a listener that is notified once the cell gets mutated.
+    pub(crate) fn listen_mutated(&self) -> EventListener {
+        self.mutated.listen()
+    }
+
+    /// Returns the error for a deadline that just passed, naming the thread holding the mutation
+    /// in progress with the `debug-tracking` feature.
+    pub(crate) fn timed_out(&self) -> TimeoutError {
+        TimeoutError {
+            #[cfg(feature = "debug-tracking")]
+            holder: self.holder.get(),
+            #[cfg(not(feature = "debug-tracking"))]
+            holder: None,
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated to the caller, the cell stays unmutated, and the
+    /// next queued mutator retries.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped while `f` is running, the cell stays unmutated and the
+    /// next queued mutator retries.
+    pub async fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: AsyncFnOnce(&mut T),
+    {
+        match self
+            .get_or_try_mutate(async |val: &mut T| {
+                f(val).await;
+                Ok::<(), ()>(())
+            })
+            .await
+        {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated and the next queued mutator retries.
+    pub async fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: AsyncFnOnce(&mut T) -> Result<(), E>,
+    {
+        let Some(mut mutation) = self.begin_mutation().await else {
+            // SAFETY: `begin_mutation` only fails once the cell is mutated.
+            return Ok(unsafe { self.get_unchecked() });
+        };
+
+        let trace = MutationTrace::start::<T>("AsyncInPlaceOnceLock", self.label, false);
+        let res = trace.instrument(f(mutation.value())).await;
+        trace.finish(&res);
+        res?;
+        Ok(mutation.publish())
+    }
+
+    /// Waits until this caller may mutate the cell, returning `None` if the cell gets mutated in
+    /// the meantime.
+    pub(crate) async fn begin_mutation(&self) -> Option<Mutation<'_, T>> {
+        loop {
+            #[cfg(feature = "test-util")]
+            test_util::hook(HookPoint::BeforeCas);
+            match self.state.compare_exchange(
+                UNTOUCHED,
+                MUTATING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(..) => {
+                    #[cfg(feature = "debug-tracking")]
+                    self.holder.begin();
+                    return Some(Mutation { lock: self });
+                }
+                Err(MUTATED) => return None,
+                Err(..) => {}
+            }
+
+            // Register before re-checking so a release in between is not missed.
+            let listener = self.released.listen();
+            if self.state.load(Ordering::Acquire) == MUTATING {
+                trace::contended::<T>("AsyncInPlaceOnceLock", self.label);
+                #[cfg(feature = "test-util")]
+                test_util::hook(HookPoint::BeforePark);
+                listener.await;
+            }
+        }
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// Consumes the cell, returning the wrapped value and whether the cell was mutated.
+    ///
+    /// See [`InPlaceOnceCell::into_raw_parts`](crate::InPlaceOnceCell::into_raw_parts).
+    #[inline]
+    pub fn into_raw_parts(mut self) -> (T, bool) {
+        let mutated = *self.state.get_mut() == MUTATED;
+        (self.into_inner(), mutated)
+    }
+
+    /// Reassembles a cell from the parts returned by [`AsyncInPlaceOnceLock::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for
+    /// [`InPlaceOnceCell::from_raw_parts`](crate::InPlaceOnceCell::from_raw_parts).
+    #[inline]
+    #[must_use]
+    pub const unsafe fn from_raw_parts(value: T, mutated: bool) -> Self {
+        let mut lock = Self::new(value);
+        if mutated {
+            lock.state = AtomicU8::new(MUTATED);
+        }
+
+        lock
+    }
+}
+
+/// Exclusive access to an unmutated cell.
+///
+/// Dropping this without calling [`Mutation::publish`] returns the cell to its untouched state.
+/// Since it is dropped on error, panic, and cancellation alike, this is the only place the
+/// `MUTATING -> UNTOUCHED` transition happens.
+pub(crate) struct Mutation<'a, T> {
+    lock: &'a AsyncInPlaceOnceLock<T>,
+}
+
+impl<'a, T> Mutation<'a, T> {
+    pub(crate) fn value(&mut self) -> &mut T {
+        // SAFETY: the `MUTATING` state excludes every other reader and writer until `self` is
+        // dropped or published.
+        unsafe { &mut *self.lock.value.get() }
+    }
+
+    pub(crate) fn publish(self) -> &'a T {
+        let lock = self.lock;
+        mem::forget(self);
+
+        #[cfg(feature = "test-util")]
+        test_util::hook(HookPoint::BeforePublish);
+        #[cfg(feature = "debug-tracking")]
+        lock.holder.end();
+        lock.state.store(MUTATED, Ordering::Release);
+        lock.mutated.notify(usize::MAX);
+        lock.released.notify(usize::MAX);
+        // SAFETY: the cell was just mutated.
+        unsafe { lock.get_unchecked() }
+    }
+}
+
+impl<T> Drop for Mutation<'_, T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "debug-tracking")]
+        self.lock.holder.end();
+        self.lock.state.store(UNTOUCHED, Ordering::Release);
+        // Only one queued mutator can win the retry. If it gets cancelled before retrying, its
+        // listener hands the notification over to the next one.
+        self.lock.released.notify(1);
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to exactly one mutator and `&T` afterwards.
+unsafe impl<T: Send + Sync> Sync for AsyncInPlaceOnceLock<T> {}
+
+impl<T: Default> Default for AsyncInPlaceOnceLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AsyncInPlaceOnceLock<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is untouched, being mutated, or
+    /// mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            MUTATING => LockState::Mutating,
+            _ => LockState::Mutated,
+        };
+        debug::fmt_cell(f, "AsyncInPlaceOnceLock", state, self.get(), |_| {})
+    }
+}
+
+impl<T> From<T> for AsyncInPlaceOnceLock<T> {
+    /// Creates a new `AsyncInPlaceOnceLock<T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,184 @@
+use alloc::boxed::Box;
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+#[cfg(feature = "nightly")]
+use core::marker::Unsize;
+#[cfg(feature = "nightly")]
+use core::ops::{CoerceUnsized, DispatchFromDyn, Receiver};
+
+use crate::debug::{self, LockState};
+
+/// The flag and the value, allocated together so that the box is a single pointer.
+struct Inner<T: ?Sized> {
+    is_mutated: Cell<bool>,
+    /// Whether a mutator is running, to catch a mutator that mutates the cell itself.
+    is_mutating: Cell<bool>,
+    value: UnsafeCell<T>,
+}
+
+/// A cell that can only be mutated once, whose flag and value live on the heap.
+///
+/// The value may be unsized. With the `nightly` feature, `InPlaceOnceBox<T>` coerces to
+/// `InPlaceOnceBox<dyn Trait>` like a `Box` does, so a registry of trait objects that are each
+/// mutated once, e.g. a `Vec<InPlaceOnceBox<dyn Plugin>>`, needs no second box. Trait methods may
+/// then also take `self: InPlaceOnceBox<Self>`.
+pub struct InPlaceOnceBox<T: ?Sized> {
+    inner: Box<Inner<T>>,
+}
+
+impl<T> InPlaceOnceBox<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Box::new(Inner {
+                is_mutated: Cell::new(false),
+                is_mutating: Cell::new(false),
+                value: UnsafeCell::new(value),
+            }),
+        }
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> InPlaceOnceBox<T> {
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.inner.is_mutated.get()
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: a mutated cell is never written through a shared reference again.
+            Some(unsafe { &*self.inner.value.get() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if self.is_mutated() {
+            Some(self.inner.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f()` if the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f()` mutates the cell itself.
+    ///
+    /// If `f()` panics, the panic is propagated to the caller, and the cell is never fully
+    /// mutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f(&mut T)` if the cell was never
+    /// mutated.
+    ///
+    /// Returns an error if the cell was uninitialized and `f()` returns an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f()` mutates the cell itself.
+    #[inline]
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+
+        let is_mutating = &self.inner.is_mutating;
+        assert!(
+            !is_mutating.replace(true),
+            "reentrant mutation of an `InPlaceOnceBox`"
+        );
+        let _mutating = ClearOnDrop(is_mutating);
+        // SAFETY: the cell is not mutated, and it is not `Sync`, so only a reentrant call could be
+        // reading or writing the value, which `is_mutating` rules out.
+        f(unsafe { &mut *self.inner.value.get() })?;
+        self.inner.is_mutated.set(true);
+        // SAFETY: the cell was just mutated.
+        Ok(unsafe { &*self.inner.value.get() })
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<InPlaceOnceBox<U>> for InPlaceOnceBox<T> {}
+
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<InPlaceOnceBox<U>> for InPlaceOnceBox<T> {}
+
+/// Lets trait methods take `self: InPlaceOnceBox<Self>`, which `DispatchFromDyn` makes callable on
+/// `InPlaceOnceBox<dyn Trait>`.
+#[cfg(feature = "nightly")]
+impl<T: ?Sized> Receiver for InPlaceOnceBox<T> {
+    type Target = T;
+}
+
+impl<T: Default> Default for InPlaceOnceBox<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for InPlaceOnceBox<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.is_mutated() {
+            LockState::Mutated
+        } else {
+            LockState::Untouched
+        };
+        debug::fmt_cell(f, "InPlaceOnceBox", state, self.get(), |_| {})
+    }
+}
+
+/// Clears the mutating flag once the mutator returns or unwinds.
+struct ClearOnDrop<'a>(&'a Cell<bool>);
+
+impl Drop for ClearOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+impl<T> From<T> for InPlaceOnceBox<T> {
+    /// Creates a new `InPlaceOnceBox<T>` containing `value`. This new cell is not yet mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,289 @@
+use core::borrow::Borrow;
+use core::cell::{Cell, RefCell, UnsafeCell};
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Bound, RangeBounds};
+use core::ptr::NonNull;
+
+use alloc::alloc::Layout;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+
+use crate::debug::Value;
+use crate::errors::AllocError;
+
+const UNTOUCHED: u8 = 0;
+const MUTATING: u8 = 1;
+const MUTATED: u8 = 2;
+
+/// A sorted map, where the value of each key is mutated in place exactly once.
+///
+/// This is the ordered counterpart of [`OnceMemo`](crate::OnceMemo), without a capacity: each key
+/// gets its own allocation, so that its value never moves, and mutated entries can be iterated
+/// over in key order, or by range, e.g. for caches keyed by timestamp.
+pub struct BTreeOnceMap<K, V> {
+    placeholder: V,
+    entries: RefCell<BTreeSet<Slot<K, V>>>,
+}
+
+struct Entry<K, V> {
+    key: K,
+    state: Cell<u8>,
+    value: UnsafeCell<V>,
+}
+
+/// An entry, ordered by its key, which lets the set be searched by key.
+///
+/// The entry is leaked from a `Box`, and only freed when the slot is dropped, so that references
+/// to it stay valid as the set moves its slots around.
+struct Slot<K, V>(NonNull<Entry<K, V>>);
+
+impl<K, V> BTreeOnceMap<K, V> {
+    /// Creates an empty map, whose keys each start out with a clone of `placeholder`.
+    #[inline]
+    #[must_use]
+    pub const fn new(placeholder: V) -> Self {
+        Self {
+            placeholder,
+            entries: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Returns the number of mutated keys in the map.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries
+            .borrow()
+            .iter()
+            // SAFETY: the entry lives as long as the slot.
+            .filter(|slot| unsafe { slot.0.as_ref() }.state.get() == MUTATED)
+            .count()
+    }
+
+    /// Returns whether the map holds no mutated keys.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Ord, V> BTreeOnceMap<K, V> {
+    /// Gets the value of `key`.
+    ///
+    /// Returns `None` if the key is not mutated.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let entry = self.entries.borrow().get(key)?.0;
+        // SAFETY: entries are never freed while the map is borrowed.
+        unsafe { entry.as_ref() }.get()
+    }
+
+    /// Gets the value of `key`, mutating a clone of the placeholder with `f` if the key is not
+    /// mutated yet.
+    ///
+    /// `f` may look up and mutate other keys of the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` mutates `key` itself, which it sees as unmutated. If `f` panics, the panic is
+    /// propagated to the caller, and the key stays unmutated; a later mutator of the same key runs
+    /// on whatever `f` left behind.
+    pub fn get_or_mutate<F>(&self, key: K, f: F) -> &V
+    where
+        V: Clone,
+        F: FnOnce(&mut V),
+    {
+        match self.get_or_mutate_in(key, f, |entry| {
+            Ok(NonNull::from(Box::leak(Box::new(entry))))
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Like [`BTreeOnceMap::get_or_mutate`], but returns an error rather than aborting if the
+    /// entry of a new key cannot be allocated, in which case the map is unchanged and `f` is not
+    /// run.
+    ///
+    /// Only the entry is allocated fallibly: the nodes of the underlying `BTreeSet` are not, as
+    /// it has no fallible insertion on stable Rust. They are small, and shared between keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` mutates `key` itself, as [`BTreeOnceMap::get_or_mutate`] does.
+    pub fn try_get_or_mutate<F>(&self, key: K, f: F) -> Result<&V, AllocError>
+    where
+        V: Clone,
+        F: FnOnce(&mut V),
+    {
+        self.get_or_mutate_in(key, f, try_box)
+    }
+
+    /// Implements the two `get_or_mutate`s, moving the entry of a new key into the allocation
+    /// returned by `alloc`.
+    fn get_or_mutate_in<F, A>(&self, key: K, f: F, alloc: A) -> Result<&V, AllocError>
+    where
+        V: Clone,
+        F: FnOnce(&mut V),
+        A: FnOnce(Entry<K, V>) -> Result<NonNull<Entry<K, V>>, AllocError>,
+    {
+        let entry = {
+            let mut entries = self.entries.borrow_mut();
+            match entries.get(&key) {
+                Some(slot) => slot.0,
+                None => {
+                    let entry = alloc(Entry {
+                        key,
+                        state: Cell::new(UNTOUCHED),
+                        value: UnsafeCell::new(self.placeholder.clone()),
+                    })?;
+                    entries.insert(Slot(entry));
+                    entry
+                }
+            }
+        };
+        // SAFETY: entries are never freed while the map is borrowed. The set is no longer
+        // borrowed, so that `f` may use the map.
+        let entry = unsafe { entry.as_ref() };
+
+        match entry.state.get() {
+            MUTATED => {}
+            UNTOUCHED => {
+                entry.state.set(MUTATING);
+                let reset = ResetOnDrop(&entry.state);
+                // SAFETY: the `MUTATING` state keeps every other lookup away from the value.
+                f(unsafe { &mut *entry.value.get() });
+                core::mem::forget(reset);
+                entry.state.set(MUTATED);
+            }
+            _ => panic!("reentrant mutation of a `BTreeOnceMap` entry"),
+        }
+
+        // SAFETY: the entry is mutated.
+        Ok(unsafe { &*entry.value.get() })
+    }
+
+    /// Returns an iterator over the mutated keys in `range` and their values, in key order.
+    ///
+    /// The map is not borrowed between items, so keys may be mutated while iterating; those
+    /// past the last key yielded are yielded too.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> impl Iterator<Item = (&K, &V)> {
+        let mut last: Option<&K> = None;
+        core::iter::from_fn(move || {
+            loop {
+                let start = match last {
+                    Some(key) => Bound::Excluded(key),
+                    None => range.start_bound(),
+                };
+                let entry = self
+                    .entries
+                    .borrow()
+                    .range::<K, _>((start, range.end_bound()))
+                    .next()?
+                    .0;
+                // SAFETY: entries are never freed while the map is borrowed.
+                let entry = unsafe { entry.as_ref() };
+                last = Some(&entry.key);
+                if let Some(val) = entry.get() {
+                    return Some((&entry.key, val));
+                }
+            }
+        })
+    }
+
+    /// Returns an iterator over the mutated keys in the map and their values, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.range(..)
+    }
+}
+
+impl<K, V> Entry<K, V> {
+    #[inline]
+    fn get(&self) -> Option<&V> {
+        if self.state.get() == MUTATED {
+            // SAFETY: the entry is mutated, so it is never mutated again.
+            Some(unsafe { &*self.value.get() })
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns an entry to the untouched state if its mutator unwinds.
+struct ResetOnDrop<'a>(&'a Cell<u8>);
+
+impl Drop for ResetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.set(UNTOUCHED);
+    }
+}
+
+impl<K, V> Slot<K, V> {
+    #[inline]
+    fn key(&self) -> &K {
+        // SAFETY: the entry lives as long as the slot.
+        unsafe { &self.0.as_ref().key }
+    }
+}
+
+/// Moves `entry` into a new allocation, like `Box::new`, but returns an error rather than
+/// aborting if the allocator fails. The allocation may be freed with `Box::from_raw`.
+fn try_box<K, V>(entry: Entry<K, V>) -> Result<NonNull<Entry<K, V>>, AllocError> {
+    let layout = Layout::new::<Entry<K, V>>();
+    // SAFETY: an entry is never zero-sized, as it holds its state.
+    let ptr = NonNull::new(unsafe { alloc::alloc::alloc(layout) })
+        .ok_or(AllocError)?
+        .cast::<Entry<K, V>>();
+    // SAFETY: the allocation fits an entry, which `Box::from_raw` may then free with the same
+    // layout.
+    unsafe { ptr.write(entry) };
+    Ok(ptr)
+}
+
+impl<K, V> Drop for Slot<K, V> {
+    fn drop(&mut self) {
+        // SAFETY: the entry was leaked from a `Box`, and this is the last use of it.
+        drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+    }
+}
+
+impl<K, V> Borrow<K> for Slot<K, V> {
+    #[inline]
+    fn borrow(&self) -> &K {
+        self.key()
+    }
+}
+
+impl<K: Ord, V> Ord for Slot<K, V> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(other.key())
+    }
+}
+
+impl<K: Ord, V> PartialOrd for Slot<K, V> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, V> PartialEq for Slot<K, V> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<K: Ord, V> Eq for Slot<K, V> {}
+
+// SAFETY: the map owns its keys and values, which are dropped by whichever thread drops it.
+unsafe impl<K: Send, V: Send> Send for BTreeOnceMap<K, V> {}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for BTreeOnceMap<K, V> {
+    /// Prints the mutated entries as a map.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(key, val)| (key, Value(val))))
+            .finish()
+    }
+}
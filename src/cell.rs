@@ -1,8 +1,103 @@
-use std::cell::{Cell, UnsafeCell};
-use std::fmt;
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+#[cfg(feature = "nightly")]
+use core::marker::Destruct;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::debug::{self, LockState};
 
 // TODO: Add more documentation
 
+/// Defines the mutating getters of [`InPlaceOnceCell`]. With the `nightly` feature they are
+/// `const fn`s taking `[const]` closures, so constant tables can be built with the same calls as
+/// runtime code; a non-`const` closure still works outside of const contexts.
+macro_rules! mutators {
+    () => {
+        #[cfg(not(feature = "nightly"))]
+        mutators!(@define [] [] [] []);
+        #[cfg(feature = "nightly")]
+        mutators!(@define [const] [[const]] [+ [const] Destruct] [[const] Destruct]);
+    };
+    (@define
+        [$($const:tt)?] [$($maybe_const:tt)?] [$($f_destruct:tt)*] [$($e_destruct:tt)*]
+    ) => {
+        /// Gets the contents of the cell, mutating it with `f()` if the cell was never mutated.
+        ///
+        /// # Panics
+        ///
+        /// If `f()` panics, the panic is propagated to the caller, and the cell is never fully
+        /// mutated.
+        ///
+        /// `f()` is not guaranteed to be reentrant, so calling the function after a panic also may
+        /// result in a panic.
+        #[inline]
+        pub $($const)? fn get_or_mutate<F>(&self, f: F) -> &T
+        where
+            F: $($maybe_const)? FnOnce(&mut T) $($f_destruct)*,
+        {
+            match self.get_or_try_mutate($($const)? move |val: &mut T| {
+                f(val);
+                Ok::<(), ()>(())
+            }) {
+                Ok(..) => {}
+                Err(..) => unreachable!(),
+            }
+
+            // SAFETY: the previous code is guaranteed to mutate the cell
+            unsafe { self.get_unchecked() }
+        }
+
+        /// Gets the contents of the cell, mutating it with `f(&mut T)` if the cell was never
+        /// mutated.
+        ///
+        /// Returns an error if the cell was uninitialized and `f()` returns an error.
+        #[inline]
+        pub $($const)? fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+        where
+            F: $($maybe_const)? FnOnce(&mut T) -> Result<(), E> $($f_destruct)*,
+            E: $($e_destruct)*,
+        {
+            if let Some(val) = self.get() {
+                return Ok(val);
+            }
+
+            // `?` is not `const` yet.
+            #[allow(clippy::question_mark)]
+            if let Err(err) = self.try_mutate(f) {
+                return Err(err);
+            }
+            // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
+            Ok(unsafe { self.get_unchecked() })
+        }
+
+        // It's most likely that the value is already initialized.
+        #[cold]
+        #[inline(never)]
+        $($const)? fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
+        where
+            F: $($maybe_const)? FnOnce(&mut T) -> Result<(), E> $($f_destruct)*,
+            E: $($e_destruct)*,
+        {
+            // SAFETY: `try_init` is only called in `get_*_or_try_mutate`, meaning `self.inner` will
+            // always be non-null and not mutated.
+            let inner_mut_ref = unsafe { &mut *self.value.get() };
+            #[allow(clippy::question_mark)]
+            if let Err(err) = f(inner_mut_ref) {
+                return Err(err);
+            }
+
+            // `Cell::set` is not `const` yet, and the old flag needs no drop.
+            self.is_mutated.replace(true);
+
+            Ok(())
+        }
+    };
+}
+
 /// A cell that can only be mutated once.
 pub struct InPlaceOnceCell<T> {
     is_mutated: Cell<bool>,
@@ -20,9 +115,32 @@ impl<T> InPlaceOnceCell<T> {
         }
     }
 
+    /// Creates an array of `N` cells that have not been mutated, each containing a copy of
+    /// `value`.
+    ///
+    /// Unlike `[InPlaceOnceCell::new(value); N]`, this does not require the cell to be `Copy`, and
+    /// can be used to build constant tables. See also
+    /// [`in_place_once_array!`](crate::in_place_once_array).
+    #[must_use]
+    pub const fn new_array<const N: usize>(value: T) -> [Self; N]
+    where
+        T: Copy,
+    {
+        let mut arr = [const { MaybeUninit::<Self>::uninit() }; N];
+        let mut i = 0;
+        while i < N {
+            arr[i] = MaybeUninit::new(Self::new(value));
+            i += 1;
+        }
+
+        // SAFETY: every element was just initialized, and `MaybeUninit<Self>` has the same layout
+        // as `Self`.
+        unsafe { ptr::read((&raw const arr).cast::<[Self; N]>()) }
+    }
+
     #[inline]
     #[must_use]
-    fn is_mutated(&self) -> bool {
+    const fn is_mutated(&self) -> bool {
         self.is_mutated.get()
     }
 
@@ -30,7 +148,7 @@ impl<T> InPlaceOnceCell<T> {
     ///
     /// The cell must be mutated.
     #[inline]
-    unsafe fn get_unchecked(&self) -> &T {
+    pub(crate) const unsafe fn get_unchecked(&self) -> &T {
         debug_assert!(self.is_mutated());
         unsafe { &*self.value.get() }
     }
@@ -48,7 +166,7 @@ impl<T> InPlaceOnceCell<T> {
     ///
     /// Returns `None` if the cell is not mutated.
     #[inline]
-    pub fn get(&self) -> Option<&T> {
+    pub const fn get(&self) -> Option<&T> {
         if self.is_mutated() {
             // SAFETY: `self.is_initialized() == true`, so always safe.
             Some(unsafe { self.get_unchecked() })
@@ -57,6 +175,31 @@ impl<T> InPlaceOnceCell<T> {
         }
     }
 
+    /// Gets the reference to the underlying value, which must be mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics with `msg` if the cell is not mutated, like [`Option::expect`].
+    #[inline]
+    #[track_caller]
+    pub fn expect_mutated(&self, msg: &str) -> &T {
+        match self.get() {
+            Some(val) => val,
+            None => expect_failed(msg),
+        }
+    }
+
+    /// Gets the reference to the underlying value, which must be mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is not mutated.
+    #[inline]
+    #[track_caller]
+    pub fn unwrap_mutated(&self) -> &T {
+        self.expect_mutated("called `InPlaceOnceCell::unwrap_mutated()` on an untouched cell")
+    }
+
     /// Gets a mutable reference to the underlying value.
     ///
     /// Returns `None` if the cell is not mutated.
@@ -68,29 +211,129 @@ impl<T> InPlaceOnceCell<T> {
         }
     }
 
-    /// Gets the contents of the cell, mutating it with `f()` if the cell was never mutated.
+    /// Gets the contents of the cell, replacing the placeholder with `T::default()` if the cell
+    /// was never mutated.
     ///
-    /// # Panics
+    /// This suits placeholders that are only sentinels, whose real initial state is the default.
+    #[inline]
+    pub fn mutate_with_default(&self) -> &T
+    where
+        T: Default,
+    {
+        self.get_or_mutate(|val: &mut T| *val = T::default())
+    }
+
+    /// Mutates the cell by replacing the placeholder with `value`, if it was never mutated.
     ///
-    /// If `f()` panics, the panic is propagated to the caller, and the cell is never fully mutated.
+    /// Returns the mutated value on success. Otherwise, hands `value` back along with the value
+    /// that is already there, like `OnceCell::try_insert`, so losing a race costs no clone.
+    #[inline]
+    pub fn try_mutate_with_value(&self, value: T) -> Result<&T, (T, &T)> {
+        let mut value = Some(value);
+        let val = self.get_or_mutate(|val: &mut T| *val = value.take().unwrap());
+        match value {
+            None => Ok(val),
+            Some(value) => Err((value, val)),
+        }
+    }
+
+    /// Mutates `other` from the value of this cell with `f`, once this cell is mutated, e.g. to
+    /// derive one configuration from another.
     ///
-    /// `f()` is not guaranteed to be reentrant, so calling the function after a panic also may
-    /// result in a panic.
+    /// Call this wherever `other` is read, instead of `other.get()`. It returns `None` until this
+    /// cell is mutated, then mutates `other` on the first call and returns its value from then on,
+    /// so that no access site depends on the order in which the two are mutated. If `other` was
+    /// mutated some other way, its value is returned as is.
     #[inline]
-    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    pub fn propagate_to<'a, U, F>(&self, other: &'a InPlaceOnceCell<U>, f: F) -> Option<&'a U>
     where
+        F: FnOnce(&T, &mut U),
+    {
+        if let Some(val) = other.get() {
+            return Some(val);
+        }
+
+        let val = self.get()?;
+        Some(other.get_or_mutate(|other: &mut U| f(val, other)))
+    }
+
+    /// Like [`InPlaceOnceCell::get_or_mutate`], but also returns a clone of the placeholder
+    /// taken right before `f` ran, e.g. to diff the value before and after a one-time upgrade.
+    ///
+    /// The snapshot is `None` if this call did not run `f`, because the cell was already
+    /// mutated. No snapshot of the placeholder survives once it is mutated.
+    #[inline]
+    pub fn fetch_mutate<F>(&self, f: F) -> (Option<T>, &T)
+    where
+        T: Clone,
         F: FnOnce(&mut T),
     {
-        match self.get_or_try_mutate(|val: &mut T| {
+        let mut before = None;
+        let val = self.get_or_mutate(|val: &mut T| {
+            before = Some(val.clone());
             f(val);
-            Ok::<(), ()>(())
-        }) {
-            Ok(..) => {}
-            Err(..) => unreachable!(),
-        }
+        });
+        (before, val)
+    }
 
-        // SAFETY: the previous code is guaranteed to mutate the cell
-        unsafe { self.get_unchecked() }
+    /// Like [`InPlaceOnceCell::get_or_mutate`], but also returns whether this call ran `f`.
+    #[inline]
+    pub fn get_or_mutate_status<F>(&self, f: F) -> (&T, bool)
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut mutated = false;
+        let val = self.get_or_mutate(|val: &mut T| {
+            f(val);
+            mutated = true;
+        });
+        (val, mutated)
+    }
+
+    /// Like [`InPlaceOnceCell::get_or_mutate`], but `f` also returns a value, e.g. diagnostics or a
+    /// handle derived while mutating, which is passed back to the caller.
+    ///
+    /// The returned value is `None` if this call did not run `f`, because the cell was already
+    /// mutated.
+    #[inline]
+    pub fn get_or_mutate_map<F, R>(&self, f: F) -> (Option<R>, &T)
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut ret = None;
+        let val = self.get_or_mutate(|val: &mut T| ret = Some(f(val)));
+        (ret, val)
+    }
+
+    /// Gets the contents of the cell, writing `args` into it if the cell was never mutated, e.g.
+    /// with `format_args!`.
+    ///
+    /// The output is appended to the placeholder, which may already hold a prefix or reserve
+    /// capacity, so nothing is formatted into a temporary buffer first. For a `String`, the
+    /// result derefs to `&str`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the writer returns an error, as `format!` does. Writing to a `String` never
+    /// does; use [`InPlaceOnceCell::get_or_try_mutate_fmt`] for writers with a fixed capacity.
+    #[inline]
+    #[track_caller]
+    pub fn get_or_mutate_fmt(&self, args: fmt::Arguments<'_>) -> &T
+    where
+        T: fmt::Write,
+    {
+        self.get_or_try_mutate_fmt(args)
+            .expect("a formatting trait implementation returned an error")
+    }
+
+    /// Like [`InPlaceOnceCell::get_or_mutate_fmt`], but returns an error if the writer does, e.g.
+    /// because a fixed-capacity buffer is full, in which case the cell stays unmutated.
+    #[inline]
+    pub fn get_or_try_mutate_fmt(&self, args: fmt::Arguments<'_>) -> Result<&T, fmt::Error>
+    where
+        T: fmt::Write,
+    {
+        self.get_or_try_mutate(|val: &mut T| val.write_fmt(args))
     }
 
     #[inline]
@@ -110,26 +353,11 @@ impl<T> InPlaceOnceCell<T> {
         unsafe { self.get_mut_unchecked() }
     }
 
-    /// Gets the contents of the cell, mutating it with `f(&mut T)` if the cell was never mutated.
-    ///
-    /// Returns an error if the cell was uninitialized and `f()` returns an error.
-    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
-    where
-        F: FnOnce(&mut T) -> Result<(), E>,
-    {
-        if let Some(val) = self.get() {
-            return Ok(val);
-        }
-
-        self.try_mutate(f)?;
-        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
-        Ok(unsafe { self.get_unchecked() })
-    }
-
     /// Gets the mutable contents of the cell, mutating it with `f(&mut T)` if the cell was never
     /// mutated.
     ///
     /// Returns an error if the cell was uninitialized and `f()` returns an error.
+    #[inline]
     pub fn get_mut_or_try_mutate<F, E>(&mut self, f: F) -> Result<&mut T, E>
     where
         F: FnOnce(&mut T) -> Result<(), E>,
@@ -145,28 +373,127 @@ impl<T> InPlaceOnceCell<T> {
         Ok(unsafe { self.get_mut_unchecked() })
     }
 
-    // It's most likely that the value is already initialized.
-    #[cold]
-    fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
-    where
-        F: FnOnce(&mut T) -> Result<(), E>,
-    {
-        // SAFETY: `try_init` is only called in `get_*_or_try_mutate`, meaning `self.inner` will
-        // always be non-null and not mutated.
-        let inner_mut_ref = unsafe { &mut *self.value.get() };
-        f(inner_mut_ref)?;
+    mutators!();
 
-        self.is_mutated.set(true);
+    /// Returns the cell to the untouched state, returning its value to restore the placeholder.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub(crate) fn reset(&mut self) -> &mut T {
+        *self.is_mutated.get_mut() = false;
+        self.value.get_mut()
+    }
 
-        Ok(())
+    /// Returns the value, even if the cell was never mutated, along with whether it was.
+    pub(crate) fn peek(&self) -> (&T, bool) {
+        // SAFETY: the value is always valid, even before the cell is mutated, as for `Clone`.
+        (unsafe { &*self.value.get() }, self.is_mutated())
+    }
+
+    /// Returns the value, even if the cell was never mutated, along with its flag.
+    ///
+    /// # Safety
+    ///
+    /// Setting the flag is subject to the same conditions as
+    /// [`InPlaceOnceCell::from_raw_parts`].
+    #[cfg(feature = "serde")]
+    pub(crate) unsafe fn peek_mut(&mut self) -> (&mut T, &mut bool) {
+        (self.value.get_mut(), self.is_mutated.get_mut())
     }
 
     /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
     /// was never mutated.
     #[inline]
-    pub fn into_inner(self) -> T {
-        // TODO: Make this a `pub const fn`.
-        self.value.into_inner()
+    pub const fn into_inner(self) -> T {
+        // SAFETY: `self` is forgotten right after, so the value is only moved out once, and the
+        // flag needs no drop. `const fn`s cannot drop `self` after moving a field out of it.
+        let value = unsafe { ptr::read(&self.value) };
+        mem::forget(self);
+        value.into_inner()
+    }
+
+    /// Consumes the cell, returning the wrapped value if the cell was mutated, or the cell itself
+    /// otherwise.
+    ///
+    /// This stands in for `TryFrom<InPlaceOnceCell<T>> for T`, which the orphan rules forbid.
+    #[inline]
+    pub const fn try_into_inner(self) -> Result<T, Self> {
+        if self.is_mutated() {
+            Ok(self.into_inner())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Consumes the cell, returning the wrapped value and whether the cell was mutated.
+    ///
+    /// The parts can be stored elsewhere, e.g. in an arena or an intrusive data structure, and
+    /// reassembled with [`InPlaceOnceCell::from_raw_parts`].
+    #[inline]
+    pub const fn into_raw_parts(self) -> (T, bool) {
+        let mutated = self.is_mutated();
+        (self.into_inner(), mutated)
+    }
+
+    /// Reassembles a cell from the parts returned by [`InPlaceOnceCell::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// If `mutated` is `true`, `value` must have been mutated by a mutator of the cell it came
+    /// from, or otherwise be a value that the cell's users may observe as mutated. Code relying
+    /// on a mutator having established an invariant before the value became visible, such as a
+    /// validated buffer read by `unsafe` code, would otherwise be unsound.
+    #[inline]
+    #[must_use]
+    pub const unsafe fn from_raw_parts(value: T, mutated: bool) -> Self {
+        Self {
+            is_mutated: Cell::new(mutated),
+            value: UnsafeCell::new(value),
+        }
+    }
+}
+
+/// Panics with `msg`, for `expect_mutated` on every cell, kept out of line like the panics of
+/// [`Option::expect`].
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub(crate) fn expect_failed(msg: &str) -> ! {
+    panic!("{msg}")
+}
+
+/// Unwraps a whole collection of [`InPlaceOnceCell`]s at once, e.g. to finalize a batch of cells
+/// mutated one by one.
+pub trait TryUnwrapAll: Sized {
+    /// The values of the cells.
+    type Output;
+
+    /// Consumes the cells, returning their values if every cell was mutated, or the cells
+    /// themselves otherwise.
+    fn try_unwrap_all(self) -> Result<Self::Output, Self>;
+}
+
+impl<T, const N: usize> TryUnwrapAll for [InPlaceOnceCell<T>; N] {
+    type Output = [T; N];
+
+    fn try_unwrap_all(self) -> Result<[T; N], Self> {
+        if self.iter().all(InPlaceOnceCell::is_mutated) {
+            Ok(self.map(InPlaceOnceCell::into_inner))
+        } else {
+            Err(self)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> TryUnwrapAll for Vec<InPlaceOnceCell<T>> {
+    type Output = Vec<T>;
+
+    fn try_unwrap_all(self) -> Result<Vec<T>, Self> {
+        if self.iter().all(InPlaceOnceCell::is_mutated) {
+            Ok(self.into_iter().map(InPlaceOnceCell::into_inner).collect())
+        } else {
+            Err(self)
+        }
     }
 }
 
@@ -178,19 +505,17 @@ impl<T: Default> Default for InPlaceOnceCell<T> {
 }
 
 impl<T: fmt::Debug> fmt::Debug for InPlaceOnceCell<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is mutated.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut d = f.debug_tuple("InPlaceOnceCell");
-        match self.get() {
-            Some(v) => d.field(v),
-            // SAFETY: `get_unchecked` always returns a valid memory location, it's just marked as
-            // `unsafe` because it is not initialized. Still, we may want to see its uninitialized
-            // value.
-            None => d.field(&format_args!("{:?} <untouched>", unsafe {
-                self.get_unchecked()
-            })),
+        let state = if self.is_mutated() {
+            LockState::Mutated
+        } else {
+            LockState::Untouched
         };
-
-        d.finish()
+        // SAFETY: the value is always valid, even before the cell is mutated. Still, we may want
+        // to see its uninitialized value.
+        let value = unsafe { &*self.value.get() };
+        debug::fmt_cell(f, "InPlaceOnceCell", state, Some(value), |_| {})
     }
 }
 
@@ -222,3 +547,27 @@ impl<T> From<T> for InPlaceOnceCell<T> {
         Self::new(value)
     }
 }
+
+/// Creates an array of untouched cells at compile time.
+///
+/// `in_place_once_array![value; N]` creates `[InPlaceOnceCell<_>; N]`, and
+/// `in_place_once_array![Cell => value; N]` creates an array of any cell type with a `const fn
+/// new`, such as `InPlaceOnceLock`. `value` must be a constant expression, but does not need to be
+/// `Copy`.
+///
+/// ```
+/// use in_place_once_cell::{InPlaceOnceCell, in_place_once_array};
+///
+/// let table: [InPlaceOnceCell<Vec<u8>>; 4] = in_place_once_array![Vec::new(); 4];
+/// assert_eq!(table[1].get_or_mutate(|v| v.push(1)), &[1]);
+/// assert_eq!(table[2].get(), None);
+/// ```
+#[macro_export]
+macro_rules! in_place_once_array {
+    ($cell:ty => $value:expr; $n:expr) => {
+        [const { <$cell>::new($value) }; $n]
+    };
+    ($value:expr; $n:expr) => {
+        [const { $crate::InPlaceOnceCell::new($value) }; $n]
+    };
+}
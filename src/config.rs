@@ -0,0 +1,56 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::{env, fs};
+
+use crate::InPlaceOnceLock;
+use crate::errors::ConfigError;
+
+impl<T> InPlaceOnceLock<T> {
+    /// Gets the contents of the lock, mutating it with `parser` and the value of the environment
+    /// variable `var` if the lock was never mutated.
+    ///
+    /// The variable is only read if the lock is mutated by this call. Returns an error, and
+    /// leaves the lock unmutated, if the variable is not set or `parser` fails.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate_from_env<F, E>(
+        &self,
+        var: impl AsRef<OsStr>,
+        parser: F,
+    ) -> Result<&T, ConfigError<E>>
+    where
+        F: FnOnce(&mut T, &str) -> Result<(), E>,
+    {
+        self.get_or_try_mutate(|val: &mut T| {
+            let var = var.as_ref();
+            let text = env::var(var).map_err(|source| ConfigError::Env {
+                var: var.to_os_string(),
+                source,
+            })?;
+            parser(val, &text).map_err(ConfigError::Parse)
+        })
+    }
+
+    /// Gets the contents of the lock, mutating it with `parser` and the contents of the file at
+    /// `path` if the lock was never mutated.
+    ///
+    /// The file is only read if the lock is mutated by this call. Returns an error, and leaves the
+    /// lock unmutated, if the file cannot be read as UTF-8 or `parser` fails.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate_from_file<F, E>(
+        &self,
+        path: impl AsRef<Path>,
+        parser: F,
+    ) -> Result<&T, ConfigError<E>>
+    where
+        F: FnOnce(&mut T, &str) -> Result<(), E>,
+    {
+        self.get_or_try_mutate(|val: &mut T| {
+            let path = path.as_ref();
+            let text = fs::read_to_string(path).map_err(|source| ConfigError::File {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            parser(val, &text).map_err(ConfigError::Parse)
+        })
+    }
+}
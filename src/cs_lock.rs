@@ -0,0 +1,223 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::debug::{self, LockState};
+
+const UNTOUCHED: u8 = 0;
+const MUTATING: u8 = 1;
+const MUTATED: u8 = 2;
+
+/// A cell that can only be mutated once, whose mutator runs inside a [`critical_section`].
+///
+/// On single-core microcontrollers a critical section masks interrupts, so the mutator can never
+/// be preempted by another caller. This makes `get_or_mutate` safe to call from both thread mode
+/// and interrupt handlers: there is nothing to wait on, so nothing can deadlock.
+///
+/// Atomics are only ever loaded and stored, never compared-and-swapped, so this works on targets
+/// such as `thumbv6m` as well.
+pub struct InPlaceOnceCsLock<T> {
+    value: UnsafeCell<T>,
+    state: AtomicU8,
+}
+
+impl<T> InPlaceOnceCsLock<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            state: AtomicU8::new(UNTOUCHED),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.load(Ordering::Acquire) == MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == MUTATED {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// `f` runs inside a critical section, so keep it short.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` calls back into this cell's mutation methods.
+    ///
+    /// If `f` panics, the panic is propagated to the caller and the cell stays unmutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` calls back into this cell's mutation methods.
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+
+        self.try_mutate(f)?;
+        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
+        Ok(unsafe { self.get_unchecked() })
+    }
+
+    #[cold]
+    fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        critical_section::with(|_| {
+            match self.state.load(Ordering::Acquire) {
+                UNTOUCHED => {}
+                MUTATED => return Ok(()),
+                _ => panic!("reentrant mutation of an `InPlaceOnceCsLock`"),
+            }
+
+            self.state.store(MUTATING, Ordering::Relaxed);
+            let reset = ResetOnDrop(&self.state);
+            // SAFETY: the `MUTATING` state, set inside the critical section, excludes every other
+            // reader and writer.
+            f(unsafe { &mut *self.value.get() })?;
+            core::mem::forget(reset);
+            self.state.store(MUTATED, Ordering::Release);
+
+            Ok(())
+        })
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// Consumes the cell, returning the wrapped value and whether the cell was mutated.
+    ///
+    /// See [`InPlaceOnceCell::into_raw_parts`](crate::InPlaceOnceCell::into_raw_parts).
+    #[inline]
+    pub fn into_raw_parts(mut self) -> (T, bool) {
+        let mutated = *self.state.get_mut() == MUTATED;
+        (self.into_inner(), mutated)
+    }
+
+    /// Reassembles a cell from the parts returned by [`InPlaceOnceCsLock::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for
+    /// [`InPlaceOnceCell::from_raw_parts`](crate::InPlaceOnceCell::from_raw_parts).
+    #[inline]
+    #[must_use]
+    pub const unsafe fn from_raw_parts(value: T, mutated: bool) -> Self {
+        let mut lock = Self::new(value);
+        if mutated {
+            lock.state = AtomicU8::new(MUTATED);
+        }
+
+        lock
+    }
+}
+
+/// Returns the cell to the untouched state if the mutator fails or unwinds.
+struct ResetOnDrop<'a>(&'a AtomicU8);
+
+impl Drop for ResetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(UNTOUCHED, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to exactly one mutator and `&T` afterwards.
+unsafe impl<T: Send + Sync> Sync for InPlaceOnceCsLock<T> {}
+
+impl<T: Default> Default for InPlaceOnceCsLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for InPlaceOnceCsLock<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is untouched, being mutated, or
+    /// mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            MUTATING => LockState::Mutating,
+            _ => LockState::Mutated,
+        };
+        debug::fmt_cell(f, "InPlaceOnceCsLock", state, self.get(), |_| {})
+    }
+}
+
+impl<T: PartialEq> PartialEq for InPlaceOnceCsLock<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq> Eq for InPlaceOnceCsLock<T> {}
+
+impl<T> From<T> for InPlaceOnceCsLock<T> {
+    /// Creates a new `InPlaceOnceCsLock<T>` containing `value`. This new cell is not yet mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,90 @@
+//! `Debug` formatting shared by every cell, and the hook that redacts sensitive values from it.
+
+use core::fmt;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The hook set by [`set_redaction_hook`], or null.
+static REDACTION_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Sets a hook that decides, by type name, which values `Debug` output prints as `<redacted>`.
+///
+/// The hook receives [`core::any::type_name`] of the value and applies to every cell in the
+/// crate, in both the plain and the alternate (`{:#?}`) form. Use it to keep secrets such as keys
+/// or tokens out of logs.
+pub fn set_redaction_hook(hook: fn(type_name: &'static str) -> bool) {
+    REDACTION_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Removes the hook set by [`set_redaction_hook`], so every value is printed again.
+pub fn clear_redaction_hook() {
+    REDACTION_HOOK.store(core::ptr::null_mut(), Ordering::Release);
+}
+
+fn is_redacted<T: ?Sized>() -> bool {
+    let hook = REDACTION_HOOK.load(Ordering::Acquire);
+    if hook.is_null() {
+        return false;
+    }
+
+    // SAFETY: the only non-null values ever stored are `fn(&'static str) -> bool` pointers.
+    let hook = unsafe { core::mem::transmute::<*mut (), fn(&'static str) -> bool>(hook) };
+    hook(core::any::type_name::<T>())
+}
+
+/// The state of a cell, as shown by its alternate `Debug` form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockState {
+    /// The cell has not been mutated, and nobody is mutating it.
+    Untouched,
+    /// A mutator is running.
+    Mutating,
+    /// The cell is mutated.
+    Mutated,
+    /// A mutator panicked and the cell can never be mutated.
+    Poisoned,
+}
+
+/// A value that may be redacted.
+pub(crate) struct Value<'a, T: ?Sized>(pub(crate) &'a T);
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Value<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_redacted::<T>() {
+            f.write_str("<redacted>")
+        } else {
+            self.0.fmt(f)
+        }
+    }
+}
+
+/// Formats a cell named `name`.
+///
+/// The plain form prints the value if the cell is mutated, and `<untouched>` otherwise, along with
+/// `untouched` if the value can be read before mutation. The alternate form prints the full
+/// `state`, the value if it can be read, and whatever `extra` adds.
+pub(crate) fn fmt_cell<T: fmt::Debug + ?Sized>(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    state: LockState,
+    value: Option<&T>,
+    extra: impl FnOnce(&mut fmt::DebugStruct<'_, '_>),
+) -> fmt::Result {
+    if f.alternate() {
+        let mut d = f.debug_struct(name);
+        d.field("state", &state);
+        if let Some(v) = value {
+            d.field("value", &Value(v));
+        }
+        extra(&mut d);
+        return d.finish();
+    }
+
+    let mut d = f.debug_tuple(name);
+    match (state, value) {
+        (LockState::Mutated, Some(v)) => d.field(&Value(v)),
+        (_, Some(v)) => d.field(&format_args!("{:?} <untouched>", Value(v))),
+        (_, None) => d.field(&format_args!("<untouched>")),
+    };
+
+    d.finish()
+}
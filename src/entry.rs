@@ -0,0 +1,129 @@
+use core::fmt;
+
+use crate::InPlaceOnceCell;
+#[cfg(feature = "std")]
+use crate::InPlaceOnceLock;
+
+/// A view into a cell that is either untouched or mutated, created by
+/// [`InPlaceOnceCell::entry`] and [`InPlaceOnceLock::entry`].
+///
+/// This lets initialization logic match on the state of a cell, the way `HashMap::entry` does
+/// for the keys of a map.
+pub enum Entry<'a, T, C = InPlaceOnceCell<T>> {
+    /// The cell was not mutated yet.
+    Untouched(UntouchedEntry<'a, C>),
+    /// The cell was mutated, and holds this value.
+    Mutated(&'a T),
+}
+
+/// An untouched cell, from [`Entry::Untouched`].
+///
+/// The cell may still be mutated through another reference before this entry is used. Mutating
+/// through the entry then does nothing, and returns the value that is already there, like
+/// [`InPlaceOnceCell::get_or_mutate`] would.
+pub struct UntouchedEntry<'a, C>(&'a C);
+
+impl<T> InPlaceOnceCell<T> {
+    /// Returns whether the cell is untouched or mutated, as an [`Entry`] to match on.
+    #[inline]
+    pub fn entry(&self) -> Entry<'_, T> {
+        match self.get() {
+            Some(val) => Entry::Mutated(val),
+            None => Entry::Untouched(UntouchedEntry(self)),
+        }
+    }
+}
+
+impl<'a, T> UntouchedEntry<'a, InPlaceOnceCell<T>> {
+    /// Mutates the cell with `f`, returning the mutated value.
+    ///
+    /// See [`InPlaceOnceCell::get_or_mutate`].
+    #[inline]
+    pub fn mutate<F>(self, f: F) -> &'a T
+    where
+        F: FnOnce(&mut T),
+    {
+        self.0.get_or_mutate(f)
+    }
+
+    /// Mutates the cell with `f`, returning the mutated value, or the error of `f`.
+    ///
+    /// See [`InPlaceOnceCell::get_or_try_mutate`].
+    #[inline]
+    pub fn try_mutate<F, E>(self, f: F) -> Result<&'a T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        self.0.get_or_try_mutate(f)
+    }
+
+    /// Mutates the cell by replacing the placeholder with `value`, returning the mutated value.
+    #[inline]
+    pub fn mutate_with(self, value: T) -> &'a T {
+        self.mutate(|val: &mut T| *val = value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> InPlaceOnceLock<T> {
+    /// Returns whether the lock is untouched or mutated, as an [`Entry`] to match on.
+    ///
+    /// The entry does not hold the lock, so another thread may mutate it before the entry is
+    /// used; see [`UntouchedEntry`].
+    #[inline]
+    pub fn entry(&self) -> Entry<'_, T, Self> {
+        match self.get() {
+            Some(val) => Entry::Mutated(val),
+            None => Entry::Untouched(UntouchedEntry(self)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, T> UntouchedEntry<'a, InPlaceOnceLock<T>> {
+    /// Mutates the lock with `f`, returning the mutated value.
+    ///
+    /// See [`InPlaceOnceLock::get_or_mutate`].
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn mutate<F>(self, f: F) -> &'a T
+    where
+        F: FnOnce(&mut T),
+    {
+        self.0.get_or_mutate(f)
+    }
+
+    /// Mutates the lock with `f`, returning the mutated value, or the error of `f`.
+    ///
+    /// See [`InPlaceOnceLock::get_or_try_mutate`].
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn try_mutate<F, E>(self, f: F) -> Result<&'a T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        self.0.get_or_try_mutate(f)
+    }
+
+    /// Mutates the lock by replacing the placeholder with `value`, returning the mutated value.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn mutate_with(self, value: T) -> &'a T {
+        self.mutate(|val: &mut T| *val = value)
+    }
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for Entry<'_, T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Untouched(entry) => f.debug_tuple("Untouched").field(entry).finish(),
+            Self::Mutated(val) => f.debug_tuple("Mutated").field(val).finish(),
+        }
+    }
+}
+
+impl<C> fmt::Debug for UntouchedEntry<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UntouchedEntry").finish_non_exhaustive()
+    }
+}
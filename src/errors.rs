@@ -0,0 +1,230 @@
+//! The errors returned by the cells in this crate.
+//!
+//! Every error implements [`Display`](fmt::Display) and [`Error`], so it can be propagated with
+//! `?` into `Box<dyn Error>` or `anyhow::Error`. They are all `#[non_exhaustive]`, so new causes
+//! can be added without breaking matches.
+
+use core::error::Error;
+use core::fmt;
+
+/// The error returned by
+/// [`InPlaceOnceLock::compare_and_mutate`](crate::InPlaceOnceLock::compare_and_mutate) when the
+/// lock does not hold the expected placeholder.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CasError<T> {
+    /// The contents of the lock: its placeholder, or its value if it was already mutated.
+    pub current: T,
+    /// The value that was not stored.
+    pub new: T,
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Display for CasError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the lock did not hold the expected placeholder")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> Error for CasError<T> {}
+
+/// The error returned when a deadline passes before an
+/// [`AsyncInPlaceOnceLock`](crate::AsyncInPlaceOnceLock) is mutated.
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TimeoutError {
+    /// The thread that was mutating the cell when the deadline passed, so that hangs can be
+    /// diagnosed from logs alone.
+    ///
+    /// Always `None` without the `debug-tracking` feature, and when no mutation was in progress.
+    pub holder: Option<MutationHolder>,
+}
+
+#[cfg(feature = "async")]
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("deadline elapsed before the cell was mutated")?;
+        if let Some(holder) = &self.holder {
+            write!(f, "; {holder}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl Error for TimeoutError {}
+
+#[cfg(feature = "async")]
+impl From<TimeoutError> for std::io::Error {
+    /// Converts to an error of kind [`TimedOut`](std::io::ErrorKind::TimedOut).
+    fn from(err: TimeoutError) -> Self {
+        Self::new(std::io::ErrorKind::TimedOut, err)
+    }
+}
+
+/// The mutation of an [`AsyncInPlaceOnceLock`](crate::AsyncInPlaceOnceLock) that was in progress
+/// when a [`TimeoutError`] occurred.
+///
+/// The thread is the one that started the mutation. An async mutator may since have been polled
+/// on other threads.
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MutationHolder {
+    /// The ID of the thread that started the mutation.
+    pub thread: std::thread::ThreadId,
+    /// The name of that thread, if it has one.
+    pub thread_name: Option<std::string::String>,
+    /// How long ago the mutation started.
+    pub elapsed: std::time::Duration,
+}
+
+#[cfg(feature = "async")]
+impl fmt::Display for MutationHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("thread ")?;
+        if let Some(name) = &self.thread_name {
+            write!(f, "`{name}` ")?;
+        }
+        write!(
+            f,
+            "({:?}) has been mutating it for {:?}",
+            self.thread, self.elapsed
+        )
+    }
+}
+
+/// The error returned when a [`PersistentOnceCell`](crate::PersistentOnceCell) fails to mutate.
+#[cfg(feature = "nvm")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PersistError<E, S> {
+    /// The mutator returned an error.
+    Mutator(E),
+    /// The storage failed to write the mutated value.
+    Storage(S),
+}
+
+#[cfg(feature = "nvm")]
+impl<E: fmt::Display, S: fmt::Display> fmt::Display for PersistError<E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mutator(e) => write!(f, "mutator failed: {e}"),
+            Self::Storage(e) => write!(f, "failed to persist the mutated value: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "nvm")]
+impl<E: Error + 'static, S: Error + 'static> Error for PersistError<E, S> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Mutator(e) => Some(e),
+            Self::Storage(e) => Some(e),
+        }
+    }
+}
+
+/// The error returned when an [`InPlaceOnceLock`](crate::InPlaceOnceLock) fails to mutate from
+/// its configuration source.
+#[cfg(feature = "config")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError<E> {
+    /// The environment variable is not set, or is not valid Unicode.
+    Env {
+        /// The name of the variable.
+        var: std::ffi::OsString,
+        /// Why it could not be read.
+        source: std::env::VarError,
+    },
+    /// The file could not be read, or is not valid UTF-8.
+    File {
+        /// The path of the file.
+        path: std::path::PathBuf,
+        /// Why it could not be read.
+        source: std::io::Error,
+    },
+    /// The parser returned an error.
+    Parse(E),
+}
+
+#[cfg(feature = "config")]
+impl<E: fmt::Display> fmt::Display for ConfigError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env { var, source } => {
+                write!(f, "failed to read environment variable {var:?}: {source}")
+            }
+            Self::File { path, source } => {
+                write!(f, "failed to read {}: {source}", path.display())
+            }
+            Self::Parse(e) => write!(f, "failed to parse the configuration: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl<E: Error + 'static> Error for ConfigError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Env { source, .. } => Some(source),
+            Self::File { source, .. } => Some(source),
+            Self::Parse(e) => Some(e),
+        }
+    }
+}
+
+/// The error returned by [`OnceGraph::resolve`](crate::OnceGraph::resolve) when the nodes it
+/// would mutate depend on each other in a cycle.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CycleError {
+    /// The nodes on the cycle, each depending on the next, ending with the node reached twice.
+    pub cycle: alloc::vec::Vec<crate::NodeId>,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("dependency cycle:")?;
+        for (i, node) in self.cycle.iter().enumerate() {
+            let sep = if i == 0 { " " } else { " -> " };
+            write!(f, "{sep}{}", node.index())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Error for CycleError {}
+
+/// The error returned by the fallible counterparts of the functions of
+/// [`OnceGraph`](crate::OnceGraph) and [`BTreeOnceMap`](crate::BTreeOnceMap) that allocate,
+/// when the allocator fails, or the capacity would overflow, instead of aborting.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AllocError;
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Error for AllocError {}
+
+#[cfg(feature = "alloc")]
+impl From<alloc::collections::TryReserveError> for AllocError {
+    #[inline]
+    fn from(_: alloc::collections::TryReserveError) -> Self {
+        Self
+    }
+}
@@ -0,0 +1,120 @@
+//! A C API over a type-erased [`InPlaceOnceLock`], for sharing once-mutated state with C and C++.
+//!
+//! The value is an opaque, zero-initialized or copied byte buffer aligned to 16 bytes, and the
+//! mutator is a C callback. `include/in_place_once_cell.h` declares these functions and can be
+//! regenerated with `cbindgen --config cbindgen.toml --output include/in_place_once_cell.h`.
+//!
+//! Callbacks must not unwind into Rust, and must not call back into the same lock.
+
+use core::ffi::c_void;
+use core::ptr;
+
+use alloc::boxed::Box;
+use alloc::vec;
+
+use crate::InPlaceOnceLock;
+
+/// A chunk of the value buffer, aligned like C's `max_align_t` on common targets.
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct Chunk([u8; 16]);
+
+/// A type-erased [`InPlaceOnceLock`] holding `size` bytes.
+///
+/// Only ever handled through a pointer returned by [`ipoc_new`].
+pub struct IpocLock {
+    lock: InPlaceOnceLock<Box<[Chunk]>>,
+    size: usize,
+}
+
+/// A mutator called with a pointer to the value and the `user_data` passed alongside it.
+pub type IpocMutator = unsafe extern "C" fn(value: *mut c_void, user_data: *mut c_void);
+
+/// Creates a lock holding `size` bytes, copied from `init`, or zeroed if `init` is null.
+///
+/// The returned lock must be freed with [`ipoc_free`].
+///
+/// # Safety
+///
+/// `init` must be null or valid for reads of `size` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ipoc_new(init: *const c_void, size: usize) -> *mut IpocLock {
+    let mut buf = vec![Chunk([0; 16]); size.div_ceil(16)].into_boxed_slice();
+    if !init.is_null() {
+        // SAFETY: the caller guarantees `init` is valid for `size` bytes, and `buf` holds at
+        // least `size` bytes.
+        unsafe { ptr::copy_nonoverlapping(init.cast::<u8>(), buf.as_mut_ptr().cast(), size) };
+    }
+
+    Box::into_raw(Box::new(IpocLock {
+        lock: InPlaceOnceLock::new(buf),
+        size,
+    }))
+}
+
+/// Returns the number of bytes held by `lock`.
+///
+/// # Safety
+///
+/// `lock` must have been returned by [`ipoc_new`] and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ipoc_size(lock: *const IpocLock) -> usize {
+    // SAFETY: guaranteed by the caller.
+    unsafe { (*lock).size }
+}
+
+/// Returns a pointer to the value of `lock`, or null if it is not mutated.
+///
+/// The value must not be written through the returned pointer.
+///
+/// # Safety
+///
+/// `lock` must have been returned by [`ipoc_new`] and not yet freed. The returned pointer is
+/// valid until `lock` is freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ipoc_get(lock: *const IpocLock) -> *const c_void {
+    // SAFETY: guaranteed by the caller.
+    match unsafe { (*lock).lock.get() } {
+        Some(buf) => buf.as_ptr().cast(),
+        None => ptr::null(),
+    }
+}
+
+/// Returns a pointer to the value of `lock`, mutating it with `mutator` if it was never mutated.
+///
+/// `mutator` is called at most once per lock, with the value and `user_data`. Other threads
+/// calling this concurrently block until it returns.
+///
+/// # Safety
+///
+/// `lock` must have been returned by [`ipoc_new`] and not yet freed, and `mutator` must be safe
+/// to call with `user_data`. The returned pointer is valid until `lock` is freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ipoc_get_or_mutate(
+    lock: *const IpocLock,
+    mutator: IpocMutator,
+    user_data: *mut c_void,
+) -> *const c_void {
+    // SAFETY: guaranteed by the caller.
+    let lock = unsafe { &(*lock).lock };
+    lock.get_or_mutate(|buf| {
+        // SAFETY: guaranteed by the caller.
+        unsafe { mutator(buf.as_mut_ptr().cast(), user_data) }
+    })
+    .as_ptr()
+    .cast()
+}
+
+/// Frees a lock returned by [`ipoc_new`]. Does nothing if `lock` is null.
+///
+/// # Safety
+///
+/// `lock` must be null, or have been returned by [`ipoc_new`] and not yet freed. No pointer
+/// returned for it may be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ipoc_free(lock: *mut IpocLock) {
+    if !lock.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { Box::from_raw(lock) });
+    }
+}
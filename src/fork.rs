@@ -0,0 +1,75 @@
+//! `pthread_atfork` handlers that keep registered locks usable in a forked child.
+//!
+//! Only the forking thread survives a fork, so a lock that another thread was mutating would
+//! stay `MUTATING` in the child forever, and one whose park lock another thread held would stay
+//! locked. The handlers take every registered park lock before forking, so none is held by a
+//! thread that does not survive, then return every lock that such a thread was mutating to its
+//! untouched state in the child.
+
+use std::any::Any;
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::ptr;
+use std::sync::{Mutex, MutexGuard, Once, PoisonError};
+use std::vec::Vec;
+
+/// A lock that takes part in the fork handlers.
+pub(crate) trait AtFork: Sync {
+    /// Takes the lock's park lock, returning its guard.
+    fn lock_parking(&'static self) -> Box<dyn Any>;
+
+    /// Returns the lock to its untouched state if it was being mutated by a thread that did not
+    /// survive the fork.
+    fn reset_in_child(&self);
+}
+
+type Locks = Vec<&'static dyn AtFork>;
+
+static LOCKS: Mutex<Locks> = Mutex::new(Vec::new());
+
+/// The guards taken before forking: the list of locks, and the park lock of each of them.
+struct Held(MutexGuard<'static, Locks>, Vec<Box<dyn Any>>);
+
+std::thread_local! {
+    /// Set by `prepare`, and taken by `parent` or `child`, which run on the same thread.
+    static HELD: RefCell<Option<Held>> = const { RefCell::new(None) };
+}
+
+/// Registers `lock` with the fork handlers, installing them on first use. Registering a lock
+/// again does nothing.
+pub(crate) fn register(lock: &'static dyn AtFork) {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        // SAFETY: the handlers are plain `extern "C"` functions that live forever.
+        let res = unsafe { libc::pthread_atfork(Some(prepare), Some(parent), Some(child)) };
+        assert_eq!(res, 0, "failed to install the fork handlers");
+    });
+
+    let mut locks = LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+    // Taking the same park lock twice before forking would deadlock.
+    if !locks
+        .iter()
+        .any(|registered| ptr::addr_eq(*registered, lock))
+    {
+        locks.push(lock);
+    }
+}
+
+extern "C" fn prepare() {
+    let locks = LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+    let guards = locks.iter().map(|lock| lock.lock_parking()).collect();
+    HELD.with(|held| *held.borrow_mut() = Some(Held(locks, guards)));
+}
+
+extern "C" fn parent() {
+    HELD.with(|held| held.borrow_mut().take());
+}
+
+extern "C" fn child() {
+    if let Some(Held(locks, guards)) = HELD.with(|held| held.borrow_mut().take()) {
+        for lock in locks.iter() {
+            lock.reset_in_child();
+        }
+        drop(guards);
+    }
+}
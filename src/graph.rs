@@ -0,0 +1,313 @@
+use core::cell::Cell;
+use core::fmt;
+use core::ops::Index;
+
+use alloc::vec::Vec;
+
+use crate::InPlaceOnceCell;
+use crate::errors::{AllocError, CycleError};
+
+/// A directed graph of cells, each mutated once, after every cell it depends on.
+///
+/// Nodes are added with a placeholder, and dependencies declared between them in any order.
+/// [`OnceGraph::resolve`] then mutates a node and, first, everything it transitively depends
+/// on, so the order of mutations follows the graph rather than the call chain. A cycle among the
+/// nodes being resolved is reported as a [`CycleError`], before any node on it is mutated.
+pub struct OnceGraph<T> {
+    nodes: Vec<Node<T>>,
+}
+
+/// The index of a node in a [`OnceGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+struct Node<T> {
+    cell: InPlaceOnceCell<T>,
+    deps: Vec<NodeId>,
+    /// Whether the node is on the path being resolved.
+    visiting: Cell<bool>,
+}
+
+impl NodeId {
+    /// Returns the position of the node in the order nodes were added.
+    #[inline]
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl<T> OnceGraph<T> {
+    /// Creates an empty graph.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Returns the number of nodes.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether the graph has no nodes.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds a node containing `placeholder`, which has not been mutated, and depends on nothing.
+    pub fn add_node(&mut self, placeholder: T) -> NodeId {
+        self.nodes.push(Node {
+            cell: InPlaceOnceCell::new(placeholder),
+            deps: Vec::new(),
+            visiting: Cell::new(false),
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Reserves capacity for at least `additional` more nodes.
+    ///
+    /// Returns an error if the allocation fails, in which case the graph is unchanged.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        Ok(self.nodes.try_reserve(additional)?)
+    }
+
+    /// Like [`OnceGraph::add_node`], but returns an error if the allocation fails, in which case
+    /// `placeholder` is dropped and the graph is unchanged.
+    pub fn try_add_node(&mut self, placeholder: T) -> Result<NodeId, AllocError> {
+        self.nodes.try_reserve(1)?;
+        Ok(self.add_node(placeholder))
+    }
+
+    /// Declares that `node` depends on `dep`, which is then mutated first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either node is not in the graph.
+    #[track_caller]
+    pub fn add_dependency(&mut self, node: NodeId, dep: NodeId) {
+        assert!(
+            node.0 < self.nodes.len() && dep.0 < self.nodes.len(),
+            "node not in graph"
+        );
+        self.nodes[node.0].deps.push(dep);
+    }
+
+    /// Like [`OnceGraph::add_dependency`], but returns an error if the allocation fails, in which
+    /// case the graph is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either node is not in the graph.
+    #[track_caller]
+    pub fn try_add_dependency(&mut self, node: NodeId, dep: NodeId) -> Result<(), AllocError> {
+        assert!(
+            node.0 < self.nodes.len() && dep.0 < self.nodes.len(),
+            "node not in graph"
+        );
+        let deps = &mut self.nodes[node.0].deps;
+        deps.try_reserve(1)?;
+        deps.push(dep);
+        Ok(())
+    }
+
+    /// Returns the nodes that `node` depends on directly, in the order they were declared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is not in the graph.
+    #[inline]
+    #[must_use]
+    pub fn dependencies(&self, node: NodeId) -> &[NodeId] {
+        &self.nodes[node.0].deps
+    }
+
+    /// Gets the reference to the value of `node`.
+    ///
+    /// Returns `None` if the node is not mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is not in the graph.
+    #[inline]
+    pub fn get(&self, node: NodeId) -> Option<&T> {
+        self.nodes[node.0].cell.get()
+    }
+
+    /// Gets the value of `node`, mutating it and every node it transitively depends on that is
+    /// not mutated yet, dependencies first.
+    ///
+    /// Each node is mutated with `f(id, &mut T, deps)`, where `deps` holds the values of its
+    /// direct dependencies, all mutated by then.
+    ///
+    /// Returns an error if the nodes left to mutate contain a cycle. The nodes on the cycle, and
+    /// those depending on them, are not mutated, but the dependencies resolved before the cycle was
+    /// reached stay mutated. Calling `resolve` from `f` on a node that is being resolved counts as
+    /// a cycle too.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is not in the graph.
+    ///
+    /// If `f` panics, the panic is propagated to the caller, and the node it was mutating stays
+    /// unmutated; the nodes mutated before it stay mutated.
+    pub fn resolve<F>(&self, node: NodeId, mut f: F) -> Result<&T, CycleError>
+    where
+        F: FnMut(NodeId, &mut T, Deps<'_, T>),
+    {
+        let cell = &self.nodes[node.0].cell;
+        if let Some(val) = cell.get() {
+            return Ok(val);
+        }
+
+        let mut path = Path {
+            graph: self,
+            stack: Vec::new(),
+        };
+        path.push(node)?;
+        while let Some(&mut (id, ref mut next)) = path.stack.last_mut() {
+            let deps = &self.nodes[id.0].deps;
+            if let Some(&dep) = deps.get(*next) {
+                *next += 1;
+                if self.nodes[dep.0].cell.get().is_none() {
+                    path.push(dep)?;
+                }
+                continue;
+            }
+
+            let deps = Deps { graph: self, deps };
+            self.nodes[id.0]
+                .cell
+                .get_or_mutate(|val: &mut T| f(id, val, deps));
+            path.pop();
+        }
+
+        Ok(cell.get().unwrap_or_else(|| unreachable!()))
+    }
+}
+
+/// The nodes being resolved, each with the position of the next dependency to visit.
+///
+/// Nodes are marked as visiting while they are on the path, and unmarked when they leave it,
+/// including when resolving fails or unwinds.
+struct Path<'a, T> {
+    graph: &'a OnceGraph<T>,
+    stack: Vec<(NodeId, usize)>,
+}
+
+impl<T> Path<'_, T> {
+    fn push(&mut self, node: NodeId) -> Result<(), CycleError> {
+        let visiting = &self.graph.nodes[node.0].visiting;
+        if visiting.get() {
+            // The node is on this path, unless `f` is resolving it from an outer call.
+            let start = self
+                .stack
+                .iter()
+                .position(|&(id, _)| id == node)
+                .unwrap_or(0);
+            let mut cycle: Vec<NodeId> = self.stack[start..].iter().map(|&(id, _)| id).collect();
+            cycle.push(node);
+            return Err(CycleError { cycle });
+        }
+
+        visiting.set(true);
+        self.stack.push((node, 0));
+        Ok(())
+    }
+
+    fn pop(&mut self) {
+        if let Some((node, _)) = self.stack.pop() {
+            self.graph.nodes[node.0].visiting.set(false);
+        }
+    }
+}
+
+impl<T> Drop for Path<'_, T> {
+    fn drop(&mut self) {
+        while !self.stack.is_empty() {
+            self.pop();
+        }
+    }
+}
+
+/// The values of the direct dependencies of a node, passed to the mutator of
+/// [`OnceGraph::resolve`], in the order they were declared.
+pub struct Deps<'a, T> {
+    graph: &'a OnceGraph<T>,
+    deps: &'a [NodeId],
+}
+
+impl<'a, T> Deps<'a, T> {
+    /// Returns the number of dependencies.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.deps.len()
+    }
+
+    /// Returns whether the node has no dependencies.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.deps.is_empty()
+    }
+
+    /// Returns the ids of the dependencies.
+    #[inline]
+    #[must_use]
+    pub fn ids(&self) -> &'a [NodeId] {
+        self.deps
+    }
+
+    /// Returns an iterator over the values of the dependencies.
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + use<'a, T> {
+        let graph = self.graph;
+        self.deps.iter().map(move |&dep| value(graph, dep))
+    }
+}
+
+impl<T> Index<usize> for Deps<'_, T> {
+    type Output = T;
+
+    /// Returns the value of the `index`th dependency.
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: usize) -> &T {
+        value(self.graph, self.deps[index])
+    }
+}
+
+/// Returns the value of a dependency, which is mutated before its dependents.
+#[inline]
+fn value<T>(graph: &OnceGraph<T>, dep: NodeId) -> &T {
+    graph.nodes[dep.0]
+        .cell
+        .get()
+        .unwrap_or_else(|| unreachable!())
+}
+
+impl<T> Default for OnceGraph<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceGraph<T> {
+    /// Prints each node's cell and dependencies, as a list in the order nodes were added.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.nodes.iter().map(|node| (&node.cell, &node.deps)))
+            .finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Deps<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
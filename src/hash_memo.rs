@@ -0,0 +1,154 @@
+use std::cell::Cell;
+use std::fmt;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash, Hasher};
+
+use crate::InPlaceOnceCell;
+use crate::debug::{self, LockState};
+
+/// A cell that can only be mutated once, and hashes its value once, when it is mutated.
+///
+/// Its [`Hash`] implementation only feeds the stored 64-bit hash to the hasher, so a large value
+/// used as a `HashMap` key, e.g. for interning, is not hashed again on every lookup. Equality
+/// compares the stored hashes before the values. Untouched cells all hash and compare equal,
+/// and stop doing so once mutated, so only mutated cells should be used as keys.
+///
+/// The hash is computed with `S`, which must build the same hasher every time, so that equal
+/// values get equal hashes. The default, `DefaultHasher::new()`, does; `RandomState` does not.
+pub struct HashMemoCell<T, S = BuildHasherDefault<DefaultHasher>> {
+    cell: InPlaceOnceCell<T>,
+    hash: Cell<u64>,
+    hasher: S,
+}
+
+impl<T> HashMemoCell<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self::with_hasher(value, BuildHasherDefault::new())
+    }
+}
+
+impl<T, S> HashMemoCell<T, S> {
+    /// Creates a new cell that has not been mutated, which hashes its value with `hasher`.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(value: T, hasher: S) -> Self {
+        Self {
+            cell: InPlaceOnceCell::new(value),
+            hash: Cell::new(0),
+            hasher,
+        }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    /// Returns the hash computed when the cell was mutated.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    #[must_use]
+    pub fn precomputed_hash(&self) -> Option<u64> {
+        self.cell.get().map(|_| self.hash.get())
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.cell.into_inner()
+    }
+}
+
+impl<T: Hash, S: BuildHasher> HashMemoCell<T, S> {
+    /// Gets the contents of the cell, mutating it with `f()` and hashing the result if the cell
+    /// was never mutated.
+    ///
+    /// See [`InPlaceOnceCell::get_or_mutate`].
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f(&mut T)` and hashing the result if the
+    /// cell was never mutated.
+    ///
+    /// See [`InPlaceOnceCell::get_or_try_mutate`]. Nothing is hashed if `f` fails.
+    #[inline]
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        self.cell.get_or_try_mutate(|val: &mut T| {
+            f(val)?;
+            self.hash.set(self.hasher.hash_one(&*val));
+            Ok(())
+        })
+    }
+}
+
+impl<T, S> Hash for HashMemoCell<T, S> {
+    /// Feeds the precomputed hash, or `None` if the cell is not mutated, to `state`.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.precomputed_hash().hash(state);
+    }
+}
+
+impl<T: PartialEq, S> PartialEq for HashMemoCell<T, S> {
+    /// Two cells are equal if neither is mutated, or if both are mutated to equal values. The
+    /// stored hashes are compared first.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.precomputed_hash() == other.precomputed_hash() && self.cell == other.cell
+    }
+}
+
+impl<T: Eq, S> Eq for HashMemoCell<T, S> {}
+
+impl<T: Default, S: Default> Default for HashMemoCell<T, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(T::default(), S::default())
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for HashMemoCell<T, S> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is mutated, and its hash.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (value, mutated) = self.cell.peek();
+        let state = if mutated {
+            LockState::Mutated
+        } else {
+            LockState::Untouched
+        };
+        debug::fmt_cell(f, "HashMemoCell", state, Some(value), |d| {
+            if let Some(hash) = self.precomputed_hash() {
+                d.field("hash", &format_args!("{hash:#018x}"));
+            }
+        })
+    }
+}
+
+impl<T> From<T> for HashMemoCell<T> {
+    /// Creates a new `HashMemoCell<T>` containing `value`. This new cell is not yet mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
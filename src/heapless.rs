@@ -0,0 +1,104 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use crate::InPlaceOnceCell;
+
+/// A vector of up to `N` cells that can each be mutated once, stored inline in a
+/// [`heapless::Vec`](::heapless::Vec).
+///
+/// Like [`OnceMemo`](crate::OnceMemo), this never allocates, so `no_std` code without an
+/// allocator can grow a bounded collection of cells, e.g. one per attached peripheral, and mutate
+/// each of them in place later. It dereferences to a slice of [`InPlaceOnceCell`]s.
+pub struct InPlaceOnceVec<T, const N: usize> {
+    cells: ::heapless::Vec<InPlaceOnceCell<T>, N>,
+}
+
+impl<T, const N: usize> InPlaceOnceVec<T, N> {
+    /// Creates an empty vector.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            cells: ::heapless::Vec::new(),
+        }
+    }
+
+    /// Returns the number of cells the vector can hold.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Appends a cell containing `placeholder`, which has not been mutated.
+    ///
+    /// Returns `placeholder` back if the vector is full.
+    #[inline]
+    pub fn push(&mut self, placeholder: T) -> Result<(), T> {
+        self.cells
+            .push(InPlaceOnceCell::new(placeholder))
+            .map_err(InPlaceOnceCell::into_inner)
+    }
+
+    /// Removes the last cell, returning it, or `None` if the vector is empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<InPlaceOnceCell<T>> {
+        self.cells.pop()
+    }
+
+    /// Consumes the vector, returning the wrapped values, whether or not their cells were mutated.
+    pub fn into_inner(self) -> ::heapless::Vec<T, N> {
+        self.cells
+            .into_iter()
+            .map(InPlaceOnceCell::into_inner)
+            .collect()
+    }
+
+    /// Consumes the vector, returning the wrapped values if every cell was mutated, or the vector
+    /// itself otherwise.
+    pub fn try_unwrap_all(self) -> Result<::heapless::Vec<T, N>, Self> {
+        if self.iter().all(|cell| cell.get().is_some()) {
+            Ok(self.into_inner())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for InPlaceOnceVec<T, N> {
+    type Target = [InPlaceOnceCell<T>];
+
+    #[inline]
+    fn deref(&self) -> &[InPlaceOnceCell<T>] {
+        &self.cells
+    }
+}
+
+impl<T, const N: usize> DerefMut for InPlaceOnceVec<T, N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [InPlaceOnceCell<T>] {
+        &mut self.cells
+    }
+}
+
+impl<T, const N: usize> Default for InPlaceOnceVec<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for InPlaceOnceVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const N: usize> From<::heapless::Vec<T, N>> for InPlaceOnceVec<T, N> {
+    /// Creates a vector of cells that have not been mutated, one for each of `values`.
+    fn from(values: ::heapless::Vec<T, N>) -> Self {
+        Self {
+            cells: values.into_iter().map(InPlaceOnceCell::new).collect(),
+        }
+    }
+}
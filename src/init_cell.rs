@@ -0,0 +1,227 @@
+//! A cell that only the initialization context may mutate, for RTIC-style applications.
+//!
+//! RTIC runs `#[init]` with interrupts disabled before any task, then `#[idle]` at the lowest
+//! priority. State that `init` computes and every task then reads is commonly kept in a
+//! `static mut MaybeUninit<T>`, which every reader has to trust was written. An
+//! [`InPlaceOnceInitCell`] in a plain `static` replaces it: mutating takes an [`InitToken`],
+//! which only the initialization context holds, so no two mutators ever race and no lock or
+//! critical section is needed, while tasks of any priority read it with a single atomic load.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::cell::expect_failed;
+use crate::debug::{self, LockState};
+
+const UNTOUCHED: u8 = 0;
+const MUTATING: u8 = 1;
+const MUTATED: u8 = 2;
+
+/// Permission to mutate [`InPlaceOnceInitCell`]s, held by the one execution context that
+/// initializes them, e.g. RTIC's `init` and, after it returns, `idle`.
+///
+/// The token is neither `Send` nor `Sync`, so it cannot leave the context that created it.
+pub struct InitToken(PhantomData<*mut ()>);
+
+impl InitToken {
+    /// Creates a token.
+    ///
+    /// # Safety
+    ///
+    /// Tokens may only exist in one execution context at a time: no task or interrupt handler
+    /// that can preempt the context holding a token may create one, or otherwise mutate the cells
+    /// that this token mutates.
+    #[inline]
+    #[must_use]
+    pub const unsafe fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl fmt::Debug for InitToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InitToken")
+    }
+}
+
+/// A cell that can only be mutated once, by the holder of an [`InitToken`].
+///
+/// Reading is lock-free, so tasks of any priority may read the cell once it is mutated, including
+/// ones that preempt a mutation in `idle`: they see it untouched until it is published. Reading
+/// from another context takes `T: Sync`, as for any shared reference.
+///
+/// Atomics are only ever loaded and stored, never compared-and-swapped, so this works on targets
+/// such as `thumbv6m` as well.
+pub struct InPlaceOnceInitCell<T> {
+    value: UnsafeCell<T>,
+    state: AtomicU8,
+}
+
+impl<T> InPlaceOnceInitCell<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            state: AtomicU8::new(UNTOUCHED),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.load(Ordering::Acquire) == MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the reference to the underlying value, which must be mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics with `msg` if the cell is not mutated, like [`Option::expect`].
+    #[inline]
+    #[track_caller]
+    pub fn expect_mutated(&self, msg: &str) -> &T {
+        match self.get() {
+            Some(val) => val,
+            None => expect_failed(msg),
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == MUTATED {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` calls back into this cell's mutation methods.
+    ///
+    /// If `f` panics, the panic is propagated to the caller and the cell stays unmutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, token: &InitToken, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(token, |val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` calls back into this cell's mutation methods.
+    pub fn get_or_try_mutate<F, E>(&self, token: &InitToken, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        let _ = token;
+        match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => {}
+            // SAFETY: the cell is mutated.
+            MUTATED => return Ok(unsafe { self.get_unchecked() }),
+            _ => panic!("reentrant mutation of an `InPlaceOnceInitCell`"),
+        }
+
+        self.state.store(MUTATING, Ordering::Relaxed);
+        let reset = ResetOnDrop(&self.state);
+        // SAFETY: holding a token, this is the only context that mutates the cell, and the
+        // `MUTATING` state keeps it from mutating it twice at once, and readers away.
+        f(unsafe { &mut *self.value.get() })?;
+        core::mem::forget(reset);
+        self.state.store(MUTATED, Ordering::Release);
+
+        // SAFETY: the cell was just mutated.
+        Ok(unsafe { self.get_unchecked() })
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+/// Returns the cell to the untouched state if the mutator fails or unwinds.
+struct ResetOnDrop<'a>(&'a AtomicU8);
+
+impl Drop for ResetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(UNTOUCHED, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to exactly one mutator, in the context holding
+// the token, and `&T` afterwards.
+unsafe impl<T: Send + Sync> Sync for InPlaceOnceInitCell<T> {}
+
+impl<T: Default> Default for InPlaceOnceInitCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for InPlaceOnceInitCell<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is untouched, being mutated, or
+    /// mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            MUTATING => LockState::Mutating,
+            _ => LockState::Mutated,
+        };
+        debug::fmt_cell(f, "InPlaceOnceInitCell", state, self.get(), |_| {})
+    }
+}
+
+impl<T> From<T> for InPlaceOnceInitCell<T> {
+    /// Creates a new `InPlaceOnceInitCell<T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
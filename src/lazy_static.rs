@@ -0,0 +1,111 @@
+/// Declares statics that are mutated in place the first time they are dereferenced.
+///
+/// Each declaration expands to a unit-like type that dereferences to the value, backed by a
+/// hidden static [`InPlaceOnceLock`](crate::InPlaceOnceLock). The initial value must be a
+/// constant expression; the `mutate` closure runs on it in place on first access.
+///
+/// With the `registry` feature, every declared static is reported by
+/// [`registry::dump_states`](crate::registry::dump_states). With the `stable_deref_trait`
+/// feature, every declared type implements `StableDeref`, as the value never moves.
+///
+/// ```
+/// use in_place_once_cell::in_place_lazy_static;
+///
+/// struct Config {
+///     retries: u32,
+/// }
+///
+/// impl Config {
+///     const DEFAULT: Self = Self { retries: 1 };
+///
+///     fn load(&mut self) {
+///         self.retries = 3;
+///     }
+/// }
+///
+/// in_place_lazy_static! {
+///     static ref CONFIG: Config = Config::DEFAULT; mutate |c| c.load();
+///     pub static ref COUNT: u32 = 0; mutate |n| *n += CONFIG.retries;
+/// }
+///
+/// assert_eq!(CONFIG.retries, 3);
+/// assert_eq!(*COUNT, 3);
+/// ```
+#[macro_export]
+macro_rules! in_place_lazy_static {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis static ref $name:ident : $ty:ty = $init:expr ; mutate |$val:pat_param| $body:expr ;
+    )*) => {$(
+        $(#[$attr])*
+        #[allow(non_camel_case_types)]
+        $vis struct $name {
+            __private: (),
+        }
+
+        $(#[$attr])*
+        $vis static $name: $name = $name { __private: () };
+
+        impl $name {
+            #[doc(hidden)]
+            #[inline]
+            fn __lock() -> &'static $crate::InPlaceOnceLock<$ty> {
+                static LOCK: $crate::InPlaceOnceLock<$ty> = $crate::InPlaceOnceLock::new($init);
+                &LOCK
+            }
+        }
+
+        impl ::core::ops::Deref for $name {
+            type Target = $ty;
+
+            #[inline]
+            fn deref(&self) -> &$ty {
+                $name::__lock().get_or_mutate(|$val: &mut $ty| {
+                    $body;
+                })
+            }
+        }
+
+        $crate::__register_lazy_static!($name);
+        $crate::__stable_deref_lazy_static!($name);
+    )*};
+}
+
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_lazy_static {
+    ($name:ident) => {
+        $crate::registry::__submit! {
+            $crate::registry::Registration::new(
+                ::core::stringify!($name),
+                ::core::module_path!(),
+                || $name::__lock().state(),
+            )
+        }
+    };
+}
+
+#[cfg(not(feature = "registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_lazy_static {
+    ($name:ident) => {};
+}
+
+#[cfg(feature = "stable_deref_trait")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __stable_deref_lazy_static {
+    ($name:ident) => {
+        // SAFETY: the value lives in a static, so it never moves.
+        unsafe impl $crate::__stable_deref_trait::StableDeref for $name {}
+    };
+}
+
+#[cfg(not(feature = "stable_deref_trait"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __stable_deref_lazy_static {
+    ($name:ident) => {};
+}
@@ -1,5 +1,201 @@
+#![no_std]
+#![cfg_attr(
+    feature = "nightly",
+    feature(
+        arbitrary_self_types,
+        coerce_unsized,
+        const_closures,
+        const_destruct,
+        const_trait_impl,
+        dispatch_from_dyn,
+        unsize
+    )
+)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod align;
+#[cfg(feature = "std")]
+mod arc;
+#[cfg(feature = "critical-section")]
+mod async_cs_lock;
+#[cfg(feature = "async")]
+mod async_lazy;
+#[cfg(feature = "async")]
+mod async_lock;
+#[cfg(feature = "alloc")]
+mod boxed;
+#[cfg(feature = "alloc")]
+mod btree;
 mod cell;
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "critical-section")]
+mod cs_lock;
+mod debug;
+mod entry;
+#[cfg(any(feature = "alloc", feature = "nvm"))]
+pub mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(all(feature = "fork", unix, not(loom)))]
+mod fork;
+#[cfg(feature = "alloc")]
+mod graph;
+#[cfg(feature = "std")]
+mod hash_memo;
+#[cfg(feature = "heapless")]
+mod heapless;
+mod init_cell;
+#[cfg(feature = "std")]
+mod lazy_static;
+#[cfg(feature = "std")]
+mod list;
+#[cfg(feature = "std")]
 mod lock;
+#[cfg(feature = "std")]
+mod lock_array;
+#[cfg(feature = "std")]
+mod map;
+mod memo;
+mod must_mutate;
+mod mutate_async;
+#[cfg(feature = "async")]
+mod mutations;
+mod packed;
+#[cfg(feature = "nvm")]
+mod persistent;
+#[cfg(feature = "alloc")]
+mod pool;
+mod proof;
+mod race;
+pub mod raw;
+#[cfg(feature = "embassy-sync")]
+mod raw_mutex_lock;
+#[cfg(feature = "alloc")]
+mod rc;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod reduce;
+#[cfg(feature = "registry")]
+pub mod registry;
+mod repr_c;
+mod retaining;
+#[cfg(feature = "schemars")]
+mod schemars;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod shared;
+#[cfg(feature = "smol")]
+mod smol;
+#[cfg(feature = "alloc")]
+mod soa;
+#[cfg(feature = "std")]
+mod split;
+#[cfg(feature = "stats")]
+mod stats;
+mod take;
+#[cfg(feature = "std")]
+mod tcell;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "async")]
+mod timeout;
+#[cfg(feature = "timestamps")]
+mod timestamp;
+#[cfg(feature = "tokio")]
+mod tokio;
+#[cfg(feature = "std")]
+mod trace;
+#[cfg(feature = "debug-tracking")]
+mod tracking;
+pub mod unchecked;
+#[cfg(kani)]
+mod verification;
 
-pub use cell::InPlaceOnceCell;
-pub use lock::InPlaceOnceLock;
+pub use align::AlignedOnceCell;
+#[cfg(feature = "std")]
+pub use arc::ArcOnce;
+#[cfg(feature = "critical-section")]
+pub use async_cs_lock::AsyncInPlaceOnceCsLock;
+#[cfg(feature = "async")]
+pub use async_lazy::{AsyncInPlaceLazyLock, LazyMutatorFuture};
+#[cfg(feature = "async")]
+pub use async_lock::AsyncInPlaceOnceLock;
+#[cfg(feature = "alloc")]
+pub use boxed::InPlaceOnceBox;
+#[cfg(feature = "alloc")]
+pub use btree::BTreeOnceMap;
+pub use cell::{InPlaceOnceCell, TryUnwrapAll};
+#[cfg(feature = "critical-section")]
+pub use cs_lock::InPlaceOnceCsLock;
+pub use debug::{LockState, clear_redaction_hook, set_redaction_hook};
+pub use entry::{Entry, UntouchedEntry};
+#[cfg(feature = "std")]
+pub use errors::CasError;
+#[cfg(feature = "config")]
+pub use errors::ConfigError;
+#[cfg(feature = "nvm")]
+pub use errors::PersistError;
+#[cfg(feature = "alloc")]
+pub use errors::{AllocError, CycleError};
+#[cfg(feature = "async")]
+pub use errors::{MutationHolder, TimeoutError};
+#[cfg(feature = "alloc")]
+pub use graph::{Deps, NodeId, OnceGraph};
+#[cfg(feature = "std")]
+pub use hash_memo::HashMemoCell;
+#[cfg(feature = "heapless")]
+pub use heapless::InPlaceOnceVec;
+#[cfg(all(feature = "derive", feature = "std"))]
+pub use in_place_once_cell_macros::memoize_in_place;
+#[cfg(feature = "derive")]
+pub use in_place_once_cell_macros::{InPlaceOnceFields, OnceBuilder};
+pub use init_cell::{InPlaceOnceInitCell, InitToken};
+#[cfg(feature = "std")]
+pub use list::OnceList;
+#[cfg(feature = "std")]
+pub use lock::{InPlaceOnceLock, mutate_both};
+#[cfg(feature = "std")]
+pub use lock_array::InPlaceOnceLockArray;
+#[cfg(feature = "std")]
+pub use map::InPlaceOnceMap;
+pub use memo::OnceMemo;
+pub use must_mutate::MustMutate;
+pub use mutate_async::{OnceMutateAsync, OnceMutateAsyncWith};
+#[cfg(feature = "async")]
+pub use mutations::Mutations;
+pub use packed::{HasSpareFlagBit, PackedInPlaceOnceCell};
+#[cfg(feature = "nvm")]
+pub use persistent::{NvmStorage, NvmValue, PersistentOnceCell};
+#[cfg(feature = "alloc")]
+pub use pool::OncePool;
+pub use proof::{Branded, MutationProof};
+pub use race::RacyInPlaceOnceLock;
+#[cfg(feature = "embassy-sync")]
+pub use raw_mutex_lock::InPlaceOnceRawMutexLock;
+#[cfg(feature = "alloc")]
+pub use rc::RcOnce;
+#[cfg(feature = "std")]
+pub use reduce::ReduceOnceLock;
+pub use repr_c::ReprCOnceCell;
+pub use retaining::RetainingOnceCell;
+pub use shared::SharedInPlaceOnceLock;
+#[cfg(feature = "alloc")]
+pub use soa::{BoxedSoaInPlaceOnceCells, IterMutated, SoaChunkMut, SoaInPlaceOnceCells};
+#[cfg(feature = "std")]
+pub use split::{ReadHandle, WriteHandle};
+#[cfg(feature = "stable_deref_trait")]
+#[doc(hidden)]
+pub use stable_deref_trait as __stable_deref_trait;
+#[cfg(feature = "stats")]
+pub use stats::LockStats;
+pub use take::TakeOnceCell;
+#[cfg(feature = "std")]
+pub use tcell::{InPlaceOnceTCell, InPlaceOnceTCellOwner};
+#[cfg(feature = "timestamps")]
+pub use timestamp::MutationTime;
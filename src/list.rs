@@ -0,0 +1,118 @@
+use std::boxed::Box;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::InPlaceOnceLock;
+
+/// A concurrent, append-only list of locks that can each be mutated once.
+///
+/// Pushing a placeholder is lock-free, and hands back the lock holding it, to be mutated in place
+/// later, e.g. by the thread that pushed it once its record is final. Iterating is wait-free: it
+/// follows links that never change once published, and skips locks that are not mutated yet
+/// without waiting for them.
+///
+/// Nodes are never removed before the list is dropped, so each push allocates once and no node is
+/// ever reused.
+pub struct OnceList<T> {
+    head: AtomicPtr<Node<T>>,
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+struct Node<T> {
+    lock: InPlaceOnceLock<T>,
+    next: *mut Node<T>,
+}
+
+impl<T> OnceList<T> {
+    /// Creates an empty list.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns whether the list holds no locks.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    /// Appends a lock containing `placeholder`, which has not been mutated, returning it.
+    pub fn push(&self, placeholder: T) -> &InPlaceOnceLock<T> {
+        let node = Box::into_raw(Box::new(Node {
+            lock: InPlaceOnceLock::new(placeholder),
+            next: self.head.load(Ordering::Relaxed),
+        }));
+
+        loop {
+            // SAFETY: the node is not published yet, so nothing else accesses it.
+            let next = unsafe { (*node).next };
+            match self
+                .head
+                .compare_exchange_weak(next, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(..) => break,
+                // SAFETY: as above.
+                Err(head) => unsafe { (*node).next = head },
+            }
+        }
+
+        // SAFETY: the node lives as long as the list, and its lock is never moved.
+        unsafe { &(*node).lock }
+    }
+
+    /// Returns an iterator over every lock in the list, newest first, whether or not it is
+    /// mutated.
+    pub fn iter(&self) -> impl Iterator<Item = &InPlaceOnceLock<T>> {
+        let mut node = self.head.load(Ordering::Acquire);
+        core::iter::from_fn(move || {
+            // SAFETY: published nodes live as long as the list, and their links never change.
+            let current = unsafe { node.as_ref() }?;
+            node = current.next;
+            Some(&current.lock)
+        })
+    }
+
+    /// Returns an iterator over the values of the mutated locks in the list, newest first.
+    ///
+    /// Locks that are untouched or being mutated are skipped, not waited for.
+    pub fn iter_mutated(&self) -> impl Iterator<Item = &T> {
+        self.iter().filter_map(InPlaceOnceLock::get)
+    }
+}
+
+impl<T> Drop for OnceList<T> {
+    fn drop(&mut self) {
+        let mut node = *self.head.get_mut();
+        while !node.is_null() {
+            // SAFETY: every node was allocated by `push`, and this is the last use of it.
+            let current = unsafe { Box::from_raw(node) };
+            node = current.next;
+        }
+    }
+}
+
+// SAFETY: the list owns its values, which are dropped by whichever thread drops the list.
+unsafe impl<T: Send> Send for OnceList<T> {}
+
+// SAFETY: a shared list moves placeholders in from any thread, and shares each lock between them.
+unsafe impl<T: Send + Sync> Sync for OnceList<T> {}
+
+impl<T> Default for OnceList<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
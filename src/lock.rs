@@ -1,34 +1,194 @@
+use std::any::Any;
+use std::boxed::Box;
 use std::cell::UnsafeCell;
 use std::fmt;
-use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::sync::Once;
+use std::panic::{self, AssertUnwindSafe, RefUnwindSafe, UnwindSafe};
+use std::ptr;
+use std::string::{String, ToString};
+use std::sync::PoisonError;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use crate::cell::expect_failed;
+use crate::debug::{self, LockState};
+use crate::errors::CasError;
+use crate::split::{self, ReadHandle, WriteHandle};
+#[cfg(feature = "stats")]
+use crate::stats::{Counters, LockStats};
+#[cfg(feature = "test-util")]
+use crate::test_util::{self, HookPoint};
+#[cfg(feature = "timestamps")]
+use crate::timestamp::{MutationTime, Timestamp};
+use crate::trace::{self, Label, MutationTrace};
+#[cfg(feature = "debug-tracking")]
+use crate::tracking::Tracking;
+#[cfg(feature = "async")]
+use event_listener::Event;
+#[cfg(loom)]
+use loom::sync::{Condvar, Mutex, atomic::AtomicU8};
+#[cfg(any(debug_assertions, all(feature = "fork", unix, not(loom))))]
+use std::sync::atomic::AtomicUsize;
+#[cfg(feature = "async")]
+use std::sync::atomic::{self, AtomicBool};
+#[cfg(not(loom))]
+use std::sync::{Condvar, Mutex, atomic::AtomicU8};
+#[cfg(feature = "stats")]
+use std::time::Instant;
 
 // TODO: Add more documentation
 
 // TODO: Once `#![feature(never_type)]` is stabilized, remove this
 enum Never {}
 
+const UNTOUCHED: u8 = 0;
+const MUTATING: u8 = 1;
+const POISONED: u8 = 2;
+const MUTATED: u8 = 3;
+/// `MUTATED` for a strict lock, so that the fast path of `get_or_mutate` still only compares the
+/// state with `MUTATED`, and leaves rejecting the mutator to the slow path.
+///
+/// Both mutated states are above the others, so that `is_mutated` is a single comparison too.
+const MUTATED_STRICT: u8 = 4;
+
+/// Builds an untouched `InPlaceOnceLock` holding `$value`, for both versions of `new`.
+macro_rules! new_lock {
+    ($value:expr) => {
+        Self {
+            value: UnsafeCell::new($value),
+            state: AtomicU8::new(UNTOUCHED),
+            park_lock: Mutex::new(None),
+            parked: Condvar::new(),
+            label: Label::NONE,
+            retry_after_panic: false,
+            strict: false,
+            #[cfg(feature = "async")]
+            event: Event::new(),
+            #[cfg(feature = "async")]
+            has_listeners: AtomicBool::new(false),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "timestamps")]
+            mutated_when: Timestamp::new(),
+            #[cfg(feature = "debug-tracking")]
+            tracking: Tracking::new(),
+            #[cfg(feature = "debug-tracking")]
+            snapshot: Mutex::new(None),
+            #[cfg(any(debug_assertions, all(feature = "fork", unix, not(loom))))]
+            mutator: AtomicUsize::new(0),
+        }
+    };
+}
+
 /// A cell that can only be mutated once.
 pub struct InPlaceOnceLock<T> {
     value: UnsafeCell<T>,
-    once: Once,
+    /// The state of the lock.
+    ///
+    /// The value is published by storing `MUTATED` with `Release` once the mutator returns, and
+    /// read after loading `MUTATED` with `Acquire`, so reading a mutated lock is that single load.
+    /// Strict locks store `MUTATED_STRICT` instead.
+    /// Claiming the lock moves it from `UNTOUCHED` to `MUTATING` with `Acquire`, which also sees
+    /// whatever a failed mutator left behind, as it stores `UNTOUCHED` with `Release`.
+    state: AtomicU8,
+    /// Waiters block on `parked` while the state is `MUTATING`, holding `park_lock` while they
+    /// check it. The mutator takes `park_lock` after changing the state and before notifying, so
+    /// the notification cannot slip in between a waiter's check and its wait.
+    ///
+    /// Once the lock is poisoned, `park_lock` also holds what the mutator panicked with, to
+    /// re-panic with later. It is boxed so that locks that never get poisoned stay small.
+    park_lock: Mutex<Option<Box<Poison>>>,
+    parked: Condvar,
+    label: Label,
+    /// Whether a panicking mutator returns the lock to `UNTOUCHED` rather than poisoning it.
+    retry_after_panic: bool,
+    /// Whether a mutator that is ignored, because the lock is already mutated, panics.
+    strict: bool,
+    #[cfg(feature = "async")]
+    event: Event,
+    /// Whether `event` was ever listened to, so mutating never allocates otherwise.
+    #[cfg(feature = "async")]
+    has_listeners: AtomicBool,
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    #[cfg(feature = "timestamps")]
+    mutated_when: Timestamp,
+    #[cfg(feature = "debug-tracking")]
+    tracking: Tracking,
+    /// The placeholder that [`InPlaceOnceLock::get_or_mutate_checked`] mutated, to replay the
+    /// mutators that lost the race on. Cleared once the value may be changed through `&mut self`.
+    #[cfg(feature = "debug-tracking")]
+    snapshot: Mutex<Option<T>>,
+    /// The [`thread_token`] of the thread running the mutator, or 0. Used to catch reentrant
+    /// mutations, and to tell whether the mutator survives a fork.
+    #[cfg(any(debug_assertions, all(feature = "fork", unix, not(loom))))]
+    mutator: AtomicUsize,
 }
 
 impl<T> InPlaceOnceLock<T> {
     /// Creates a new cell that has not been mutated.
+    #[cfg(not(loom))]
     #[inline]
     #[must_use]
     pub const fn new(value: T) -> Self {
-        Self {
-            value: UnsafeCell::new(value),
-            once: Once::new(),
-        }
+        new_lock!(value)
+    }
+
+    /// Creates a new cell that has not been mutated.
+    ///
+    /// Not `const` under loom, whose primitives cannot be created in constants.
+    #[cfg(loom)]
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        new_lock!(value)
+    }
+
+    /// Attaches `label` to the tracing events of this lock, to tell locks holding the same type
+    /// apart. Does nothing without the `tracing` feature.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(not(feature = "tracing"), allow(unused_mut))]
+    pub const fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Label::new(label);
+        self
+    }
+
+    /// Makes a panicking mutator return the lock to its untouched state, like a failing one, so
+    /// the next caller retries the mutation instead of finding the lock poisoned for good.
+    ///
+    /// The next mutator runs on whatever the panicking one left behind. Mutators of such a lock
+    /// should either tolerate a partly mutated value, or only write to it once nothing can panic.
+    #[inline]
+    #[must_use]
+    pub const fn with_retry_after_panic(mut self) -> Self {
+        self.retry_after_panic = true;
+        self
+    }
+
+    /// Makes the lock panic whenever a mutator is passed to it after it was mutated, instead of
+    /// ignoring the mutator, to catch two parts of a program that both believe they initialize
+    /// it.
+    ///
+    /// This includes the losers of a race to mutate the lock. Code that only needs the value
+    /// should read it with [`InPlaceOnceLock::get`], or `wait_async` with the `async` feature.
+    /// With the `debug-tracking` feature, the panic message says where the lock was mutated.
+    #[inline]
+    #[must_use]
+    pub const fn with_strict(mut self) -> Self {
+        self.strict = true;
+        // A lock assembled from mutated parts is already published as `MUTATED`.
+        let state = std::mem::replace(&mut self.state, AtomicU8::new(UNTOUCHED)).into_inner();
+        self.state = AtomicU8::new(if state == MUTATED {
+            MUTATED_STRICT
+        } else {
+            state
+        });
+        self
     }
 
     #[inline]
     #[must_use]
     fn is_mutated(&self) -> bool {
-        self.once.is_completed()
+        self.state.load(Ordering::Acquire) >= MUTATED
     }
 
     /// # Safety
@@ -46,6 +206,11 @@ impl<T> InPlaceOnceLock<T> {
     #[inline]
     unsafe fn get_mut_unchecked(&mut self) -> &mut T {
         debug_assert!(self.is_mutated());
+        #[cfg(feature = "debug-tracking")]
+        self.snapshot
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
         self.value.get_mut()
     }
 
@@ -62,6 +227,44 @@ impl<T> InPlaceOnceLock<T> {
         }
     }
 
+    /// Gets the reference to the underlying value, like [`InPlaceOnceLock::get`], from a signal
+    /// handler.
+    ///
+    /// This is guaranteed to be async-signal-safe, with every feature: it is a single lock-free
+    /// atomic load, and never allocates, takes a lock, or makes a syscall. A handler interrupting
+    /// a mutation on the same thread gets `None`, like any other reader.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get_signal_safe(&self) -> Option<&T> {
+        self.get()
+    }
+
+    /// Gets the reference to the underlying value, which must be mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics with `msg` if the cell is not mutated, like [`Option::expect`].
+    #[inline]
+    #[track_caller]
+    pub fn expect_mutated(&self, msg: &str) -> &T {
+        match self.get() {
+            Some(val) => val,
+            None => expect_failed(msg),
+        }
+    }
+
+    /// Gets the reference to the underlying value, which must be mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is not mutated.
+    #[inline]
+    #[track_caller]
+    pub fn unwrap_mutated(&self) -> &T {
+        self.expect_mutated("called `InPlaceOnceLock::unwrap_mutated()` on an untouched cell")
+    }
+
     /// Gets a mutable reference to the underlying value.
     ///
     /// Returns `None` if the cell is not mutated.
@@ -81,7 +284,11 @@ impl<T> InPlaceOnceLock<T> {
     ///
     /// `f` is not guaranteed to be reentrant, so calling the function after a panic also may
     /// result in a panic.
+    ///
+    /// In debug builds, panics if `f` calls back into this lock's mutation methods, which would
+    /// otherwise deadlock.
     #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
     pub fn get_or_mutate<F>(&self, f: F) -> &T
     where
         F: FnOnce(&mut T),
@@ -97,7 +304,250 @@ impl<T> InPlaceOnceLock<T> {
         unsafe { self.get_unchecked() }
     }
 
+    /// Gets the contents of the cell, replacing the placeholder with `T::default()` if the cell
+    /// was never mutated.
+    ///
+    /// This suits placeholders that are only sentinels, whose real initial state is the default.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn mutate_with_default(&self) -> &T
+    where
+        T: Default,
+    {
+        self.get_or_mutate(|val: &mut T| *val = T::default())
+    }
+
+    /// Mutates the lock by replacing the placeholder with `value`, if it was never mutated.
+    ///
+    /// Returns the mutated value on success. Otherwise, hands `value` back along with the value
+    /// that is already there, like `OnceCell::try_insert`, so losing a race costs no clone.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn try_mutate_with_value(&self, value: T) -> Result<&T, (T, &T)> {
+        let mut value = Some(value);
+        let val = self.get_or_mutate(|val: &mut T| *val = value.take().unwrap());
+        match value {
+            None => Ok(val),
+            Some(value) => Err((value, val)),
+        }
+    }
+
+    /// Mutates the lock by storing `new`, but only if its placeholder still equals `expected`,
+    /// e.g. for optimistic initializers that validated against a snapshot of the placeholder.
+    ///
+    /// Returns the mutated value on success. Otherwise, returns the current contents, which are
+    /// the mutated value if the lock was already mutated, along with `new`.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn compare_and_mutate(&self, expected: &T, new: T) -> Result<&T, CasError<T>>
+    where
+        T: PartialEq + Clone,
+    {
+        let mut new = Some(new);
+        let mut current = None;
+        let res = self.get_or_try_mutate(|val: &mut T| {
+            if val == expected {
+                *val = new.take().unwrap();
+                Ok(())
+            } else {
+                current = Some(val.clone());
+                Err(())
+            }
+        });
+
+        match (res, new) {
+            (Ok(val), None) => Ok(val),
+            (Ok(val), Some(new)) => Err(CasError {
+                current: val.clone(),
+                new,
+            }),
+            (Err(()), new) => Err(CasError {
+                current: current.unwrap(),
+                new: new.unwrap(),
+            }),
+        }
+    }
+
+    /// Mutates `other` from the value of this lock with `f`, once this lock is mutated, e.g. to
+    /// derive one configuration from another.
+    ///
+    /// Call this wherever `other` is read, instead of `other.get()`. It returns `None` until this
+    /// lock is mutated, then mutates `other` on the first call and returns its value from then on,
+    /// so that no access site depends on the order in which the two are mutated. If `other` was
+    /// mutated some other way, its value is returned as is.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn propagate_to<'a, U, F>(&self, other: &'a InPlaceOnceLock<U>, f: F) -> Option<&'a U>
+    where
+        F: FnOnce(&T, &mut U),
+    {
+        if let Some(val) = other.get() {
+            return Some(val);
+        }
+
+        let val = self.get()?;
+        Some(other.get_or_mutate(|other: &mut U| f(val, other)))
+    }
+
+    /// Like [`InPlaceOnceLock::get_or_mutate`], but also returns a clone of the placeholder
+    /// taken right before `f` ran, e.g. to diff the value before and after a one-time upgrade.
+    ///
+    /// The snapshot is `None` if this call did not run `f`, because the lock was already
+    /// mutated. No snapshot of the placeholder survives once it is mutated.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn fetch_mutate<F>(&self, f: F) -> (Option<T>, &T)
+    where
+        T: Clone,
+        F: FnOnce(&mut T),
+    {
+        let mut before = None;
+        let val = self.get_or_mutate(|val: &mut T| {
+            before = Some(val.clone());
+            f(val);
+        });
+        (before, val)
+    }
+
+    /// Like [`InPlaceOnceLock::get_or_mutate`], but also returns whether this call ran `f` and
+    /// published the mutation, e.g. to log which caller did the work.
+    ///
+    /// Exactly one caller sees `true`, even when many race to mutate the lock.
     #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate_status<F>(&self, f: F) -> (&T, bool)
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut mutated = false;
+        let val = self.get_or_mutate(|val| {
+            f(val);
+            mutated = true;
+        });
+        (val, mutated)
+    }
+
+    /// Like [`InPlaceOnceLock::get_or_mutate`], but `f` also returns a value, e.g. diagnostics or a
+    /// handle derived while mutating, which is passed back to the caller.
+    ///
+    /// The returned value is `None` if this call did not run `f`, because the lock was already
+    /// mutated.
+    ///
+    /// Only the one caller that published the mutation gets `Some`, even when many race to
+    /// mutate the lock.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate_map<F, R>(&self, f: F) -> (Option<R>, &T)
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut ret = None;
+        let val = self.get_or_mutate(|val: &mut T| ret = Some(f(val)));
+        (ret, val)
+    }
+
+    /// Like [`InPlaceOnceLock::get_or_mutate`], but with the `debug-tracking` feature, also
+    /// checks that the mutation is deterministic, to catch initialization races that are
+    /// invisible in release builds.
+    ///
+    /// If the lock was already mutated by this method, `f` is run on a clone of the placeholder
+    /// the first mutator started from, and the result must equal the published value. Without
+    /// the feature, nothing is cloned or checked.
+    ///
+    /// # Panics
+    ///
+    /// With `debug-tracking`, panics if `f` mutates the placeholder into something else than the
+    /// mutator that was published did.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate_checked<F>(&self, f: F) -> &T
+    where
+        T: Clone + PartialEq + Send,
+        F: FnOnce(&mut T),
+    {
+        #[cfg(feature = "debug-tracking")]
+        {
+            let mut f = Some(f);
+            let val = self.get_or_mutate(|val: &mut T| {
+                let placeholder = val.clone();
+                if let Some(f) = f.take() {
+                    f(val);
+                }
+                *self.snapshot.lock().unwrap_or_else(PoisonError::into_inner) = Some(placeholder);
+            });
+            if let Some(f) = f {
+                self.check_deterministic(val, f);
+            }
+            val
+        }
+        #[cfg(not(feature = "debug-tracking"))]
+        self.get_or_mutate(f)
+    }
+
+    /// Replays `f`, which lost the race to mutate the lock, on a clone of the placeholder, and
+    /// panics if it does not produce `val`.
+    #[cfg(feature = "debug-tracking")]
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn check_deterministic<F>(&self, val: &T, f: F)
+    where
+        T: Clone + PartialEq,
+        F: FnOnce(&mut T),
+    {
+        // Cloned first, so `f` runs without holding the mutex.
+        let snapshot = self
+            .snapshot
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let Some(mut scratch) = snapshot else {
+            return;
+        };
+        f(&mut scratch);
+        if scratch != *val {
+            match self.tracking.location() {
+                Some(location) => panic!(
+                    "a mutator of an `InPlaceOnceLock` diverged from the one that won at {location}"
+                ),
+                None => panic!("a mutator of an `InPlaceOnceLock` diverged from the one that won"),
+            }
+        }
+    }
+
+    /// Gets the contents of the cell, writing `args` into it if the cell was never mutated, e.g.
+    /// with `format_args!`.
+    ///
+    /// The output is appended to the placeholder, which may already hold a prefix or reserve
+    /// capacity, so nothing is formatted into a temporary buffer first. For a `String`, the
+    /// result derefs to `&str`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the writer returns an error, as `format!` does. Writing to a `String` never
+    /// does; use [`InPlaceOnceLock::get_or_try_mutate_fmt`] for writers with a fixed capacity.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate_fmt(&self, args: fmt::Arguments<'_>) -> &T
+    where
+        T: fmt::Write,
+    {
+        self.get_or_try_mutate_fmt(args)
+            .expect("a formatting trait implementation returned an error")
+    }
+
+    /// Like [`InPlaceOnceLock::get_or_mutate_fmt`], but returns an error if the writer does, e.g.
+    /// because a fixed-capacity buffer is full, in which case the cell stays unmutated.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_try_mutate_fmt(&self, args: fmt::Arguments<'_>) -> Result<&T, fmt::Error>
+    where
+        T: fmt::Write,
+    {
+        self.get_or_try_mutate(|val: &mut T| val.write_fmt(args))
+    }
+
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
     pub fn get_mut_or_mutate<F>(&mut self, f: F) -> &mut T
     where
         F: FnOnce(&mut T),
@@ -116,12 +566,16 @@ impl<T> InPlaceOnceLock<T> {
     /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
     ///
     /// Returns an error if `f` returns an error during mutation.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
     pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
     where
         F: FnOnce(&mut T) -> Result<(), E>,
     {
-        if let Some(val) = self.get() {
-            return Ok(val);
+        // A strict lock is never `MUTATED`, so it rejects `f` in `try_mutate`.
+        if self.state.load(Ordering::Acquire) == MUTATED {
+            // SAFETY: the lock is mutated.
+            return Ok(unsafe { self.get_unchecked() });
         }
 
         self.try_mutate(f)?;
@@ -132,12 +586,17 @@ impl<T> InPlaceOnceLock<T> {
     /// Gets the mutable contents of the cell, mutating it with `f` if the cell was never mutated.
     ///
     /// Returns an error if `f` returns an error during mutation.
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
     pub fn get_mut_or_try_mutate<F, E>(&mut self, f: F) -> Result<&mut T, E>
     where
         F: FnOnce(&mut T) -> Result<(), E>,
     {
         // Stupid but we can't borrow `self`.
         if self.is_mutated() {
+            if self.strict {
+                self.reject_mutator();
+            }
             // SAFETY: `is_mutated` ensures that `get_mut_unchecked` is safe.
             return Ok(unsafe { self.get_mut_unchecked() });
         }
@@ -147,23 +606,275 @@ impl<T> InPlaceOnceLock<T> {
         Ok(unsafe { self.get_mut_unchecked() })
     }
 
+    // Kept out of line so callers only pay for a load and a branch once the lock is mutated.
+    #[cold]
+    #[inline(never)]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
     fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
     where
         F: FnOnce(&mut T) -> Result<(), E>,
     {
-        let mut res: Result<(), E> = Ok(());
-        // TODO: Poison on failure of `f` in order to enforce no reentrancy
-        self.once.call_once(
-            #[cold]
-            || {
-                // SAFETY: `try_init` is only called in `get_*_or_try_mutate`, meaning `self.inner` will
-                // always be non-null and not mutated.
-                let inner_mut_ref = unsafe { &mut *self.value.get() };
-                res = f(inner_mut_ref);
-            },
-        );
+        if self.state.load(Ordering::Acquire) == MUTATED_STRICT {
+            self.reject_mutator();
+        }
+        #[cfg(feature = "stats")]
+        let started = Instant::now();
+        #[cfg(feature = "debug-tracking")]
+        let location = std::panic::Location::caller();
+        if !self.claim() {
+            trace::contended::<T>("InPlaceOnceLock", self.label);
+            #[cfg(feature = "stats")]
+            self.stats.record_wait(started);
+            if self.strict {
+                self.reject_mutator();
+            }
+            return Ok(());
+        }
 
-        res
+        let claim = Claim::new(self);
+        let trace = MutationTrace::start::<T>("InPlaceOnceLock", self.label, true);
+        #[cfg(feature = "stats")]
+        let started = Instant::now();
+        #[cfg(feature = "debug-tracking")]
+        self.tracking.begin(location);
+        // SAFETY: the `MUTATING` state excludes every other reader and writer until `claim` is
+        // dropped or published.
+        let inner_mut_ref = unsafe { &mut *self.value.get() };
+        let mutate = AssertUnwindSafe(|| trace.in_scope(|| f(inner_mut_ref)));
+        let res = match panic::catch_unwind(mutate) {
+            Ok(res) => res,
+            Err(payload) => {
+                claim.abandon(Poison {
+                    message: panic_message(&*payload),
+                    #[cfg(feature = "debug-tracking")]
+                    location,
+                });
+                panic::resume_unwind(payload)
+            }
+        };
+        #[cfg(feature = "stats")]
+        self.stats.record_mutation(started);
+        trace.finish(&res);
+        res?;
+        claim.publish();
+
+        Ok(())
+    }
+
+    /// Panics because a mutator was passed to a strict lock that is already mutated.
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn reject_mutator(&self) -> ! {
+        #[cfg(feature = "debug-tracking")]
+        if let Some(location) = self.tracking.location() {
+            panic!(
+                "a mutator was passed to a strict `InPlaceOnceLock` already mutated at {location}"
+            );
+        }
+        panic!("a mutator was passed to a strict `InPlaceOnceLock` that is already mutated")
+    }
+
+    /// Waits until the lock is not being mutated, then claims it if it is untouched.
+    ///
+    /// Returns `false` if the lock is mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned, with what the mutator that poisoned it panicked with.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    fn claim(&self) -> bool {
+        loop {
+            #[cfg(feature = "test-util")]
+            test_util::hook(HookPoint::BeforeCas);
+            match self.state.compare_exchange(
+                UNTOUCHED,
+                MUTATING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(MUTATED | MUTATED_STRICT) => return false,
+                Err(POISONED) => self.poisoned(),
+                Err(_) => {}
+            }
+
+            // Waiting on our own mutation would never end, so catch that instead.
+            #[cfg(debug_assertions)]
+            if self.mutator.load(Ordering::Relaxed) == thread_token() {
+                panic!("reentrant mutation of an `InPlaceOnceLock`");
+            }
+
+            #[cfg(feature = "test-util")]
+            test_util::hook(HookPoint::BeforePark);
+            let mut guard = self
+                .park_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            while self.state.load(Ordering::Acquire) == MUTATING {
+                guard = self
+                    .parked
+                    .wait(guard)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+    }
+
+    #[cold]
+    #[track_caller]
+    fn poisoned(&self) -> ! {
+        let poison = self
+            .park_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        panic!("`InPlaceOnceLock` instance has previously been poisoned{poison}")
+    }
+
+    /// Gets the value like [`InPlaceOnceLock::get`], but panics if the lock is poisoned.
+    #[cfg(feature = "async")]
+    fn get_or_poisoned(&self) -> Option<&T> {
+        match self.state.load(Ordering::Acquire) {
+            // SAFETY: the lock is mutated.
+            MUTATED | MUTATED_STRICT => Some(unsafe { self.get_unchecked() }),
+            POISONED => self.poisoned(),
+            _ => None,
+        }
+    }
+
+    /// Ends a mutation by storing `state`, and wakes every waiter, including async ones.
+    fn release(&self, state: u8) {
+        #[cfg(any(debug_assertions, all(feature = "fork", unix, not(loom))))]
+        self.mutator.store(0, Ordering::Relaxed);
+        self.state.store(state, Ordering::Release);
+        drop(self.park_lock.lock());
+        self.parked.notify_all();
+
+        #[cfg(feature = "async")]
+        {
+            // Pairs with the fence in `wait_async`: either we see `has_listeners` along with the
+            // registered listener, or the listener sees the new state.
+            atomic::fence(Ordering::SeqCst);
+            if self.has_listeners.load(Ordering::Relaxed) {
+                self.event.notify(usize::MAX);
+            }
+        }
+    }
+
+    /// Waits until the cell is mutated by some other thread or task, then returns a reference to
+    /// the value.
+    ///
+    /// The returned future never mutates the cell itself and does not depend on any particular
+    /// executor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock is poisoned, as the blocking mutators do, including while waiting.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(&self) -> &T {
+        loop {
+            if let Some(val) = self.get_or_poisoned() {
+                return val;
+            }
+
+            // Register before re-checking so a mutation finishing in between is not missed.
+            self.has_listeners.store(true, Ordering::Relaxed);
+            let listener = self.event.listen();
+            atomic::fence(Ordering::SeqCst);
+            if let Some(val) = self.get_or_poisoned() {
+                return val;
+            }
+
+            #[cfg(feature = "test-util")]
+            test_util::hook(HookPoint::BeforePark);
+            listener.await;
+        }
+    }
+
+    /// Registers the lock with `pthread_atfork` handlers, so that a child forked while another
+    /// thread mutates it can still use it.
+    ///
+    /// Only the forking thread survives in the child. If another thread was mutating the lock, the
+    /// child finds it untouched, and its next mutator runs on whatever that thread left behind,
+    /// as after a failed mutator. If the forking thread itself was mutating the lock, the
+    /// mutation carries on in the child as in the parent. Without this, the child would find the
+    /// lock stuck mutating forever.
+    ///
+    /// The lock stays registered for the rest of the process.
+    #[cfg(all(feature = "fork", unix, not(loom)))]
+    pub fn reset_on_fork(&'static self)
+    where
+        T: Sync,
+    {
+        crate::fork::register(self);
+    }
+
+    /// Returns the state of the lock.
+    pub fn state(&self) -> LockState {
+        match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            MUTATING => LockState::Mutating,
+            MUTATED | MUTATED_STRICT => LockState::Mutated,
+            _ => LockState::Poisoned,
+        }
+    }
+
+    /// Returns how many callers raced to mutate the lock, how long they waited, and how long the
+    /// mutator took.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+
+    /// Resets the counters returned by [`InPlaceOnceLock::stats`].
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    /// Returns when the mutator of the lock returned.
+    ///
+    /// Returns `None` if the lock is not mutated, or was created mutated with
+    /// [`InPlaceOnceLock::from_raw_parts`].
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn mutated_when(&self) -> Option<MutationTime> {
+        if self.is_mutated() {
+            self.mutated_when.get()
+        } else {
+            None
+        }
+    }
+
+    /// Calls `f` with the value, even if the lock was never mutated, along with whether it was.
+    /// Waits for an in-flight mutation to finish first, and keeps the lock from being mutated
+    /// while `f` runs.
+    ///
+    /// Returns `None` if the lock is poisoned.
+    #[cfg(feature = "serde")]
+    pub(crate) fn peek<R>(&self, f: impl FnOnce(&T, bool) -> R) -> Option<R> {
+        if self.state.load(Ordering::Acquire) == POISONED {
+            return None;
+        }
+        if !self.claim() {
+            // SAFETY: `claim` only fails once the lock is mutated.
+            return Some(f(unsafe { self.get_unchecked() }, true));
+        }
+
+        let _claim = Peek(self);
+        // SAFETY: the `MUTATING` state excludes every writer until `_claim` is dropped.
+        Some(f(unsafe { &*self.value.get() }, false))
+    }
+
+    /// Consumes the lock, splitting it into the one handle that may mutate it, and a handle that
+    /// may only read it, which can be cloned for every other component.
+    ///
+    /// This lets the type system, rather than convention, decide who mutates the lock.
+    #[must_use]
+    pub fn split(self) -> (WriteHandle<T>, ReadHandle<T>) {
+        split::split(self)
     }
 
     /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
@@ -173,6 +884,266 @@ impl<T> InPlaceOnceLock<T> {
         // TODO: Make this a `pub const fn`.
         self.value.into_inner()
     }
+
+    /// Consumes the cell, returning the wrapped value if the cell was mutated, or the cell itself
+    /// otherwise.
+    #[inline]
+    pub fn try_into_inner(self) -> Result<T, Self> {
+        if self.is_mutated() {
+            Ok(self.value.into_inner())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Consumes the cell, returning the wrapped value and whether the cell was mutated.
+    ///
+    /// See [`InPlaceOnceCell::into_raw_parts`](crate::InPlaceOnceCell::into_raw_parts).
+    #[inline]
+    pub fn into_raw_parts(self) -> (T, bool) {
+        let mutated = self.is_mutated();
+        (self.value.into_inner(), mutated)
+    }
+
+    /// Reassembles a cell from the parts returned by [`InPlaceOnceLock::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for
+    /// [`InPlaceOnceCell::from_raw_parts`](crate::InPlaceOnceCell::from_raw_parts).
+    #[must_use]
+    pub unsafe fn from_raw_parts(value: T, mutated: bool) -> Self {
+        let mut lock = Self::new(value);
+        if mutated {
+            lock.state = AtomicU8::new(MUTATED);
+        }
+
+        lock
+    }
+}
+
+/// Mutates two locks together with `f`, if neither was mutated, e.g. for two subsystems whose
+/// initializers need each other's placeholders.
+///
+/// The locks are claimed in the order of their addresses, so two threads mutating the same pair
+/// of locks, in either argument order, cannot deadlock.
+///
+/// Returns both values if both locks are mutated, by this call or earlier ones. Returns `None` if
+/// only one of them was mutated, in which case `f` is not run and neither lock is changed.
+///
+/// # Panics
+///
+/// Panics if `a` and `b` are the same lock, or if either is poisoned. If `f` panics, the panic
+/// is propagated to the caller, and both locks are poisoned, unless they retry after panics.
+#[cfg_attr(feature = "debug-tracking", track_caller)]
+pub fn mutate_both<'a, 'b, A, B, F>(
+    a: &'a InPlaceOnceLock<A>,
+    b: &'b InPlaceOnceLock<B>,
+    f: F,
+) -> Option<(&'a A, &'b B)>
+where
+    F: FnOnce(&mut A, &mut B),
+{
+    assert!(
+        !ptr::addr_eq(a, b),
+        "`mutate_both` called with the same lock twice"
+    );
+    #[cfg(feature = "debug-tracking")]
+    let location = std::panic::Location::caller();
+
+    // Nothing is written until both are claimed, so claiming the second lock may panic without
+    // poisoning the first.
+    let (peek_a, peek_b) = if ptr::from_ref(a).addr() < ptr::from_ref(b).addr() {
+        let peek_a = a.claim().then(|| Peek(a));
+        (peek_a, b.claim().then(|| Peek(b)))
+    } else {
+        let peek_b = b.claim().then(|| Peek(b));
+        (a.claim().then(|| Peek(a)), peek_b)
+    };
+    let (claim_a, claim_b) = match (peek_a, peek_b) {
+        (Some(peek_a), Some(peek_b)) => (peek_a.into_claim(), peek_b.into_claim()),
+        (None, None) => {
+            if a.strict {
+                a.reject_mutator();
+            }
+            if b.strict {
+                b.reject_mutator();
+            }
+            // SAFETY: `claim` only fails once the lock is mutated.
+            return Some(unsafe { (a.get_unchecked(), b.get_unchecked()) });
+        }
+        _ => return None,
+    };
+
+    #[cfg(feature = "debug-tracking")]
+    {
+        a.tracking.begin(location);
+        b.tracking.begin(location);
+    }
+    // SAFETY: the `MUTATING` state of each lock excludes every other reader and writer until its
+    // claim is dropped or published.
+    let (val_a, val_b) = unsafe { (&mut *a.value.get(), &mut *b.value.get()) };
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| f(val_a, val_b))) {
+        let poison = || Poison {
+            message: panic_message(&*payload),
+            #[cfg(feature = "debug-tracking")]
+            location,
+        };
+        claim_a.abandon(poison());
+        claim_b.abandon(poison());
+        panic::resume_unwind(payload)
+    }
+
+    claim_a.publish();
+    claim_b.publish();
+    // SAFETY: both locks were just published.
+    Some(unsafe { (a.get_unchecked(), b.get_unchecked()) })
+}
+
+/// Exclusive access to an untouched lock.
+///
+/// Dropping this without calling [`Claim::publish`] returns the lock to its untouched state, or
+/// poisons it if the mutator panicked.
+struct Claim<'a, T>(&'a InPlaceOnceLock<T>);
+
+impl<'a, T> Claim<'a, T> {
+    /// Starts mutating a lock this thread claimed, recording the thread as its mutator until the
+    /// lock is released.
+    fn new(lock: &'a InPlaceOnceLock<T>) -> Self {
+        // Only this thread ever stores its own token, so `Relaxed` is enough.
+        #[cfg(any(debug_assertions, all(feature = "fork", unix, not(loom))))]
+        lock.mutator.store(thread_token(), Ordering::Relaxed);
+        Claim(lock)
+    }
+
+    /// Ends the mutation, publishing the value to every waiter.
+    fn publish(self) {
+        let lock = self.0;
+        #[cfg(feature = "timestamps")]
+        lock.mutated_when.record();
+        std::mem::forget(self);
+        #[cfg(feature = "test-util")]
+        test_util::hook(HookPoint::BeforePublish);
+        lock.release(if lock.strict { MUTATED_STRICT } else { MUTATED });
+    }
+
+    /// Ends a mutation whose mutator panicked, before the panic resumes unwinding.
+    ///
+    /// Poisons the lock, keeping `poison` to re-panic with, unless the lock retries after panics.
+    fn abandon(self, poison: Poison) {
+        let lock = self.0;
+        if lock.retry_after_panic {
+            // Not unwinding yet, so this returns the lock to its untouched state.
+            drop(self);
+            return;
+        }
+
+        // Recorded before the lock is poisoned, so it is there for whoever sees that.
+        *lock
+            .park_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(poison));
+        std::mem::forget(self);
+        lock.release(POISONED);
+    }
+}
+
+impl<T> Drop for Claim<'_, T> {
+    fn drop(&mut self) {
+        let state = if thread::panicking() {
+            POISONED
+        } else {
+            UNTOUCHED
+        };
+        self.0.release(state);
+    }
+}
+
+/// What a mutator that poisoned a lock panicked with.
+struct Poison {
+    /// The panic message, if the payload was a string.
+    message: Option<Box<str>>,
+    /// Where the mutation was called from.
+    #[cfg(feature = "debug-tracking")]
+    location: &'static std::panic::Location<'static>,
+}
+
+impl fmt::Display for Poison {
+    /// Follows "`InPlaceOnceLock` instance has previously been poisoned".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(" by a mutator")?;
+        #[cfg(feature = "debug-tracking")]
+        write!(f, " called at {}", self.location)?;
+        match &self.message {
+            Some(message) => write!(f, " that panicked with: {message}"),
+            None => f.write_str(" that panicked"),
+        }
+    }
+}
+
+/// Returns the message of a panic payload, if it is one of the strings `panic!` makes.
+fn panic_message(payload: &(dyn Any + Send)) -> Option<Box<str>> {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        Some((*message).into())
+    } else {
+        payload
+            .downcast_ref::<String>()
+            .map(|message| message.as_str().into())
+    }
+}
+
+/// A claim on an untouched lock that only reads it, and returns it to its untouched state when
+/// dropped, even on unwind.
+struct Peek<'a, T>(&'a InPlaceOnceLock<T>);
+
+impl<'a, T> Peek<'a, T> {
+    /// Turns this into a claim that may mutate the lock.
+    fn into_claim(self) -> Claim<'a, T> {
+        let lock = self.0;
+        std::mem::forget(self);
+        Claim::new(lock)
+    }
+}
+
+impl<T> Drop for Peek<'_, T> {
+    fn drop(&mut self) {
+        self.0.release(UNTOUCHED);
+    }
+}
+
+#[cfg(all(feature = "fork", unix, not(loom)))]
+impl<T: Sync> crate::fork::AtFork for InPlaceOnceLock<T> {
+    fn lock_parking(&'static self) -> Box<dyn Any> {
+        Box::new(
+            self.park_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    fn reset_in_child(&self) {
+        if self.state.load(Ordering::Relaxed) == MUTATING
+            && self.mutator.load(Ordering::Relaxed) != thread_token()
+        {
+            self.mutator.store(0, Ordering::Relaxed);
+            self.state.store(UNTOUCHED, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Returns a nonzero token unique to the current thread among all live threads.
+#[cfg(any(debug_assertions, all(feature = "fork", unix, not(loom))))]
+fn thread_token() -> usize {
+    #[cfg(not(loom))]
+    std::thread_local! {
+        static TOKEN: u8 = const { 0 };
+    }
+    #[cfg(loom)]
+    loom::thread_local! {
+        static TOKEN: u8 = 0;
+    }
+
+    TOKEN.with(|token| core::ptr::from_ref(token).addr())
 }
 
 unsafe impl<T: Sync> Sync for InPlaceOnceLock<T> {}
@@ -188,14 +1159,13 @@ impl<T: Default> Default for InPlaceOnceLock<T> {
 }
 
 impl<T: fmt::Debug> fmt::Debug for InPlaceOnceLock<T> {
+    /// The alternate form (`{:#?}`) also prints the state of the lock. With the
+    /// `debug-tracking` feature, it also prints where and by which thread it was mutated.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut d = f.debug_tuple("InPlaceOnceLock");
-        match self.get() {
-            Some(v) => d.field(v),
-            None => d.field(&format_args!("<untouched>")),
-        };
-
-        d.finish()
+        debug::fmt_cell(f, "InPlaceOnceLock", self.state(), self.get(), |_d| {
+            #[cfg(feature = "debug-tracking")]
+            self.tracking.fmt_origin(_d);
+        })
     }
 }
 
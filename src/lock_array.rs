@@ -0,0 +1,385 @@
+use std::boxed::Box;
+#[cfg(debug_assertions)]
+use std::cell::RefCell;
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::vec::Vec;
+
+use crate::debug::Value;
+
+const BITS: usize = usize::BITS as usize;
+
+/// A fixed-size array of locks that can each be mutated in place once, sharing their flags.
+///
+/// Each cell behaves like an [`InPlaceOnceLock`](crate::InPlaceOnceLock): threads racing to
+/// mutate it wait for the first mutator, and readers see the value once it is published. The
+/// flags live in bitset words of 64 cells rather than next to each value, so
+/// [`InPlaceOnceLockArray::mutate_batch`] can claim and publish every cell it mutates in a word
+/// with one atomic operation each, instead of one per cell, which is what warming up a large
+/// table at startup is dominated by.
+///
+/// A cell whose mutator fails or panics returns to its untouched state, as with
+/// [`InPlaceOnceLock::with_retry_after_panic`](crate::InPlaceOnceLock::with_retry_after_panic):
+/// the next mutator runs on whatever the failed one left behind.
+pub struct InPlaceOnceLockArray<T> {
+    values: Box<[UnsafeCell<T>]>,
+    /// The cells whose mutation is published, set with `Release` once their mutators return, so
+    /// that loading a bit with `Acquire` makes the value readable.
+    mutated: Box<[AtomicUsize]>,
+    /// The cells that are mutated or being mutated. A mutator claims its cells by setting their
+    /// bits, and clears those of the cells it fails to mutate with `Release`, so the next mutator
+    /// sees what it left behind.
+    claimed: Box<[AtomicUsize]>,
+    /// Threads waiting for the mutator of a cell park here. Mutators take `park_lock` after
+    /// publishing or releasing cells and before notifying, so that no wakeup is lost.
+    park_lock: Mutex<()>,
+    parked: Condvar,
+}
+
+impl<T> InPlaceOnceLockArray<T> {
+    /// Creates an array of locks that have not been mutated, one for each of `values`.
+    #[must_use]
+    pub fn new(values: impl Into<Vec<T>>) -> Self {
+        let values: Box<[UnsafeCell<T>]> = values.into().into_iter().map(UnsafeCell::new).collect();
+        let words = || {
+            (0..values.len().div_ceil(BITS))
+                .map(|_| AtomicUsize::new(0))
+                .collect()
+        };
+        Self {
+            mutated: words(),
+            claimed: words(),
+            values,
+            park_lock: Mutex::new(()),
+            parked: Condvar::new(),
+        }
+    }
+
+    /// Creates an array of `len` locks that have not been mutated, the one at `i` containing
+    /// `f(i)`.
+    #[must_use]
+    pub fn from_fn(len: usize, f: impl FnMut(usize) -> T) -> Self {
+        Self::new((0..len).map(f).collect::<Vec<_>>())
+    }
+
+    /// Returns the number of locks.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether there are no locks.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns whether the lock at `index` was mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn is_mutated(&self, index: usize) -> bool {
+        assert!(index < self.len(), "index out of bounds");
+        self.mutated[index / BITS].load(Ordering::Acquire) & (1 << (index % BITS)) != 0
+    }
+
+    /// Returns the number of mutated locks.
+    #[must_use]
+    pub fn count_mutated(&self) -> usize {
+        self.mutated
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed).count_ones() as usize)
+            .sum()
+    }
+
+    /// # Safety
+    ///
+    /// The lock at `index` must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self, index: usize) -> &T {
+        debug_assert!(self.is_mutated(index));
+        unsafe { &*self.values[index].get() }
+    }
+
+    /// Gets the reference to the value of the lock at `index`.
+    ///
+    /// Returns `None` if the lock is not mutated or `index` is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len() && self.is_mutated(index) {
+            // SAFETY: the lock is mutated.
+            Some(unsafe { self.get_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the lock at `index`, mutating it with `f(&mut T)` if the lock was
+    /// never mutated. If another thread is mutating it, waits for that mutation instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds. In debug builds, also panics if `f()` mutates the same
+    /// lock itself, which would otherwise wait forever.
+    ///
+    /// If `f()` panics, the panic is propagated to the caller, and the lock stays untouched.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, index: usize, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(index, |val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the lock at `index`, mutating it with `f(&mut T)` if the lock was
+    /// never mutated. If another thread is mutating it, waits for that mutation instead.
+    ///
+    /// Returns an error if `f()` returns an error, in which case the lock stays untouched.
+    ///
+    /// # Panics
+    ///
+    /// The same as for [`InPlaceOnceLockArray::get_or_mutate`].
+    #[inline]
+    pub fn get_or_try_mutate<F, E>(&self, index: usize, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if self.is_mutated(index) {
+            // SAFETY: the lock is mutated.
+            return Ok(unsafe { self.get_unchecked(index) });
+        }
+
+        self.try_mutate(index, f)?;
+        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the lock mutated.
+        Ok(unsafe { self.get_unchecked(index) })
+    }
+
+    // It's most likely that the value is already initialized.
+    #[cold]
+    #[inline(never)]
+    fn try_mutate<F, E>(&self, index: usize, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if !self.claim(index) {
+            return Ok(());
+        }
+
+        let bit = 1 << (index % BITS);
+        let mut claim = Claim::new(self, index / BITS, bit);
+        // SAFETY: the claimed bit excludes every other mutator, and readers until it is published.
+        f(unsafe { &mut *self.values[index].get() })?;
+        claim.done = bit;
+
+        Ok(())
+    }
+
+    /// Claims the lock at `index`, first waiting for the mutator of another thread if there is
+    /// one. Returns `false` if the lock is mutated instead.
+    fn claim(&self, index: usize) -> bool {
+        let word = index / BITS;
+        let bit = 1 << (index % BITS);
+        loop {
+            if self.claimed[word].fetch_or(bit, Ordering::Acquire) & bit == 0 {
+                return true;
+            }
+
+            // Waiting on our own mutation would never end, so catch that instead.
+            #[cfg(debug_assertions)]
+            assert!(
+                !Claim::held(self, word, bit),
+                "reentrant mutation of an `InPlaceOnceLockArray` lock"
+            );
+
+            let mut guard = self
+                .park_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            loop {
+                if self.mutated[word].load(Ordering::Acquire) & bit != 0 {
+                    return false;
+                }
+                // Released by a failed mutator, so try to claim it again.
+                if self.claimed[word].load(Ordering::Relaxed) & bit == 0 {
+                    break;
+                }
+                guard = self
+                    .parked
+                    .wait(guard)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+    }
+
+    /// Mutates each untouched lock among `indices` with `f(index, &mut T)`, returning how many
+    /// were mutated.
+    ///
+    /// Consecutive indices that fall in the same bitset word of 64 locks are handled together:
+    /// their locks are claimed with one atomic operation, mutated in index order, then published
+    /// with one `Release` update of the word, which wakes their waiters at once. Passing indices
+    /// in ascending order, e.g. `0..len` to warm up the whole array, makes the most of this.
+    ///
+    /// Locks that are mutated, including ones listed twice, or being mutated by another thread
+    /// are skipped rather than waited for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds. In debug builds, also panics if `f()` mutates a lock
+    /// of the word it is mutating itself, which would otherwise wait forever.
+    ///
+    /// If `f()` panics, the panic is propagated to the caller. The locks mutated before it stay
+    /// mutated, and the lock it was mutating and those after it stay untouched.
+    pub fn mutate_batch<I, F>(&self, indices: I, mut f: F) -> usize
+    where
+        I: IntoIterator<Item = usize>,
+        F: FnMut(usize, &mut T),
+    {
+        let mut indices = indices.into_iter();
+        let mut count = 0;
+        let mut next = indices.next();
+        while let Some(first) = next {
+            let word = first / BITS;
+            let mut bits = 0;
+            while let Some(index) = next.filter(|index| index / BITS == word) {
+                assert!(index < self.len(), "index out of bounds");
+                bits |= 1 << (index % BITS);
+                next = indices.next();
+            }
+
+            let won = bits & !self.claimed[word].fetch_or(bits, Ordering::Acquire);
+            if won == 0 {
+                continue;
+            }
+            let mut claim = Claim::new(self, word, won);
+            let mut todo = won;
+            while todo != 0 {
+                let index = word * BITS + todo.trailing_zeros() as usize;
+                // SAFETY: the claimed bits exclude every other mutator, and readers until they
+                // are published.
+                f(index, unsafe { &mut *self.values[index].get() });
+                claim.done |= todo & todo.wrapping_neg();
+                todo &= todo - 1;
+            }
+            count += won.count_ones() as usize;
+        }
+
+        count
+    }
+
+    /// Wakes every thread waiting for a lock of the array.
+    fn wake(&self) {
+        drop(self.park_lock.lock());
+        self.parked.notify_all();
+    }
+
+    /// Consumes the array, returning the wrapped values. Note that this includes the values of
+    /// locks that were never mutated.
+    pub fn into_inner(self) -> Vec<T> {
+        self.values
+            .into_vec()
+            .into_iter()
+            .map(UnsafeCell::into_inner)
+            .collect()
+    }
+}
+
+/// Locks of one bitset word claimed by this thread.
+///
+/// Dropping this, even while a mutator unwinds, publishes the locks that are `done` and returns
+/// the other ones to their untouched state.
+struct Claim<'a, T> {
+    array: &'a InPlaceOnceLockArray<T>,
+    word: usize,
+    bits: usize,
+    done: usize,
+}
+
+#[cfg(debug_assertions)]
+std::thread_local! {
+    /// The claims of this thread, as the address of their array, their word and their bits, to
+    /// catch a mutator waiting for a lock its own thread claimed.
+    static CLAIMS: RefCell<Vec<(usize, usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl<'a, T> Claim<'a, T> {
+    fn new(array: &'a InPlaceOnceLockArray<T>, word: usize, bits: usize) -> Self {
+        #[cfg(debug_assertions)]
+        CLAIMS.with_borrow_mut(|claims| claims.push((array.addr(), word, bits)));
+        Self {
+            array,
+            word,
+            bits,
+            done: 0,
+        }
+    }
+
+    /// Returns whether this thread claimed `bit` of `word` in `array`.
+    #[cfg(debug_assertions)]
+    fn held(array: &InPlaceOnceLockArray<T>, word: usize, bit: usize) -> bool {
+        CLAIMS.with_borrow(|claims| {
+            claims
+                .iter()
+                .any(|&claim| claim.0 == array.addr() && claim.1 == word && claim.2 & bit != 0)
+        })
+    }
+}
+
+impl<T> Drop for Claim<'_, T> {
+    fn drop(&mut self) {
+        // Claims are dropped in the reverse order they are made in.
+        #[cfg(debug_assertions)]
+        CLAIMS.with_borrow_mut(Vec::pop);
+        if self.done != 0 {
+            self.array.mutated[self.word].fetch_or(self.done, Ordering::Release);
+        }
+        let failed = self.bits & !self.done;
+        if failed != 0 {
+            self.array.claimed[self.word].fetch_and(!failed, Ordering::Release);
+        }
+        self.array.wake();
+    }
+}
+
+#[cfg(debug_assertions)]
+impl<T> InPlaceOnceLockArray<T> {
+    /// The address of the array, which identifies it among the claims of a thread.
+    #[inline]
+    fn addr(&self) -> usize {
+        std::ptr::from_ref(self).addr()
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to exactly one mutator per lock, which may run
+// on any thread, and `&T` afterwards.
+unsafe impl<T: Send + Sync> Sync for InPlaceOnceLockArray<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for InPlaceOnceLockArray<T> {
+    /// Prints the mutated locks as a map from index to value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries((0..self.len()).filter_map(|index| Some((index, Value(self.get(index)?)))))
+            .finish()
+    }
+}
+
+impl<T> From<Vec<T>> for InPlaceOnceLockArray<T> {
+    /// Creates a new `InPlaceOnceLockArray<T>` with a lock for each of `values`. These new locks
+    /// are not yet mutated.
+    #[inline]
+    fn from(values: Vec<T>) -> Self {
+        Self::new(values)
+    }
+}
@@ -0,0 +1,310 @@
+use std::alloc::Layout;
+use std::borrow::Borrow;
+use std::boxed::Box;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher, RandomState};
+use std::ptr::NonNull;
+use std::sync::{PoisonError, RwLock};
+use std::vec::Vec;
+
+use crate::InPlaceOnceLock;
+use crate::debug::Value;
+use crate::errors::AllocError;
+
+/// A concurrent hash map, where the value of each key is mutated in place exactly once.
+///
+/// This is the concurrent counterpart of [`BTreeOnceMap`](crate::BTreeOnceMap): each key gets
+/// its own [`InPlaceOnceLock`], starting out as a clone of the same placeholder, in its own
+/// allocation, so its value never moves. The table is only locked to look keys up or insert new
+/// ones; mutators run outside of it, so threads mutating different keys never wait for each
+/// other, and those mutating the same key wait for the first one, as with the lock.
+///
+/// Keys are never removed before the map is dropped.
+pub struct InPlaceOnceMap<K, V, S = RandomState> {
+    placeholder: V,
+    entries: RwLock<HashSet<Slot<K, V>, S>>,
+}
+
+struct Entry<K, V> {
+    key: K,
+    lock: InPlaceOnceLock<V>,
+}
+
+/// An entry, hashed and compared by its key, which lets the set be searched by key.
+///
+/// The entry is leaked from a `Box`, and only freed when the slot is dropped, so that references
+/// to it stay valid as the set moves its slots around.
+struct Slot<K, V>(NonNull<Entry<K, V>>);
+
+impl<K, V> InPlaceOnceMap<K, V> {
+    /// Creates an empty map, whose keys each start out with a clone of `placeholder`.
+    #[inline]
+    #[must_use]
+    pub fn new(placeholder: V) -> Self {
+        Self::with_hasher(placeholder, RandomState::new())
+    }
+}
+
+impl<K, V, S> InPlaceOnceMap<K, V, S> {
+    /// Creates an empty map, whose keys each start out with a clone of `placeholder`, and which
+    /// hashes them with `hasher`.
+    #[inline]
+    #[must_use]
+    pub const fn with_hasher(placeholder: V, hasher: S) -> Self {
+        Self {
+            placeholder,
+            entries: RwLock::new(HashSet::with_hasher(hasher)),
+        }
+    }
+
+    /// Returns the number of mutated keys in the map.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.iter_mutated().count()
+    }
+
+    /// Returns whether the map holds no mutated keys.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.iter_mutated().next().is_none()
+    }
+
+    /// Returns an iterator over the mutated keys in the map and their values, in no particular
+    /// order.
+    ///
+    /// This never stops the world: the table is only locked while the entries are listed, at the
+    /// start, and mutators are never waited for. Under concurrent mutation, the iterator is weakly
+    /// consistent:
+    ///
+    /// - every key inserted before the call is visited exactly once, and none inserted after;
+    /// - a key is yielded if it is mutated by the time it is visited, so one mutated during the
+    ///   iteration may or may not be, while one mutated before the call always is;
+    /// - every value yielded is final, as it is never mutated again.
+    pub fn iter_mutated(&self) -> impl Iterator<Item = (&K, &V)> {
+        let entries: Vec<NonNull<Entry<K, V>>> = self
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|slot| slot.0)
+            .collect();
+        entries.into_iter().filter_map(|entry| {
+            // SAFETY: entries are never freed while the map is borrowed.
+            let entry = unsafe { entry.as_ref() };
+            Some((&entry.key, entry.lock.get()?))
+        })
+    }
+
+    /// Clones the mutated keys in the map and their values into a `HashMap`, e.g. to export the
+    /// contents of a cache while other threads keep using it.
+    ///
+    /// It is as consistent as [`InPlaceOnceMap::iter_mutated`], which it collects.
+    #[must_use]
+    pub fn snapshot(&self) -> HashMap<K, V>
+    where
+        K: Clone + Eq + Hash,
+        V: Clone,
+    {
+        self.iter_mutated()
+            .map(|(key, val)| (key.clone(), val.clone()))
+            .collect()
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> InPlaceOnceMap<K, V, S> {
+    /// Gets the value of `key`.
+    ///
+    /// Returns `None` if the key is not mutated.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.lock(key)?.get()
+    }
+
+    /// Returns the lock holding the value of `key`, if the key was ever looked up for mutation.
+    fn lock(&self, key: &K) -> Option<&InPlaceOnceLock<V>> {
+        let entry = self
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)?
+            .0;
+        // SAFETY: entries are never freed while the map is borrowed.
+        Some(unsafe { &entry.as_ref().lock })
+    }
+
+    /// Gets the value of `key`, mutating a clone of the placeholder with `f` if the key is not
+    /// mutated yet.
+    ///
+    /// If several threads race to mutate the same key, one of them runs its mutator, and the
+    /// others wait for it, as with [`InPlaceOnceLock::get_or_mutate`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value of `key` is poisoned, as [`InPlaceOnceLock::get_or_mutate`] does.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate<F>(&self, key: K, f: F) -> &V
+    where
+        V: Clone,
+        F: FnOnce(&mut V),
+    {
+        self.entry(key).get_or_mutate(f)
+    }
+
+    /// Gets the value of `key`, mutating a clone of the placeholder with `f` if the key is not
+    /// mutated yet.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the key stays
+    /// unmutated.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_try_mutate<F, E>(&self, key: K, f: F) -> Result<&V, E>
+    where
+        V: Clone,
+        F: FnOnce(&mut V) -> Result<(), E>,
+    {
+        self.entry(key).get_or_try_mutate(f)
+    }
+
+    /// Like [`InPlaceOnceMap::get_or_mutate`], but returns an error rather than aborting if the
+    /// entry of a new key, or room for it in the table, cannot be allocated, in which case the map
+    /// is unchanged and `f` is not run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value of `key` is poisoned, as [`InPlaceOnceMap::get_or_mutate`] does.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn try_get_or_mutate<F>(&self, key: K, f: F) -> Result<&V, AllocError>
+    where
+        V: Clone,
+        F: FnOnce(&mut V),
+    {
+        let lock = self.entry_in(key, |entries, entry| {
+            entries.try_reserve(1)?;
+            try_box(entry)
+        })?;
+        Ok(lock.get_or_mutate(f))
+    }
+
+    /// Returns the lock holding the value of `key`, inserting one with a clone of the placeholder
+    /// if there is none.
+    fn entry(&self, key: K) -> &InPlaceOnceLock<V>
+    where
+        V: Clone,
+    {
+        match self.entry_in(key, |_, entry| {
+            Ok(NonNull::from(Box::leak(Box::new(entry))))
+        }) {
+            Ok(lock) => lock,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Implements the two `entry`s, moving the entry of a new key into the allocation returned by
+    /// `alloc`, which may also make room for it in the table.
+    fn entry_in<A>(&self, key: K, alloc: A) -> Result<&InPlaceOnceLock<V>, AllocError>
+    where
+        V: Clone,
+        A: FnOnce(
+            &mut HashSet<Slot<K, V>, S>,
+            Entry<K, V>,
+        ) -> Result<NonNull<Entry<K, V>>, AllocError>,
+    {
+        if let Some(lock) = self.lock(&key) {
+            return Ok(lock);
+        }
+
+        let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+        // Another thread may have inserted the key since it was looked up.
+        let entry = match entries.get(&key) {
+            Some(slot) => slot.0,
+            None => {
+                let entry = alloc(
+                    &mut entries,
+                    Entry {
+                        key,
+                        lock: InPlaceOnceLock::new(self.placeholder.clone()),
+                    },
+                )?;
+                entries.insert(Slot(entry));
+                entry
+            }
+        };
+        // SAFETY: entries are never freed while the map is borrowed. The set is unlocked once
+        // this returns, so that mutators may use the map.
+        Ok(unsafe { &entry.as_ref().lock })
+    }
+}
+
+/// Moves `entry` into a new allocation, like `Box::new` followed by `Box::leak`, but returns an
+/// error if the allocation fails.
+fn try_box<K, V>(entry: Entry<K, V>) -> Result<NonNull<Entry<K, V>>, AllocError> {
+    let layout = Layout::new::<Entry<K, V>>();
+    // SAFETY: an entry is never zero-sized, as its lock holds its state.
+    let ptr = NonNull::new(unsafe { std::alloc::alloc(layout) })
+        .ok_or(AllocError)?
+        .cast::<Entry<K, V>>();
+    // SAFETY: the allocation fits an entry, which `Box::from_raw` may then free with the same
+    // layout.
+    unsafe { ptr.write(entry) };
+    Ok(ptr)
+}
+
+impl<K, V> Slot<K, V> {
+    #[inline]
+    fn key(&self) -> &K {
+        // SAFETY: the entry lives as long as the slot.
+        unsafe { &self.0.as_ref().key }
+    }
+}
+
+impl<K, V> Drop for Slot<K, V> {
+    fn drop(&mut self) {
+        // SAFETY: the entry was leaked from a `Box`, and this is the last use of it.
+        drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+    }
+}
+
+impl<K, V> Borrow<K> for Slot<K, V> {
+    #[inline]
+    fn borrow(&self) -> &K {
+        self.key()
+    }
+}
+
+impl<K: Hash, V> Hash for Slot<K, V> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl<K: Eq, V> PartialEq for Slot<K, V> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<K: Eq, V> Eq for Slot<K, V> {}
+
+// SAFETY: the map owns its keys and values, which are dropped by whichever thread drops it.
+unsafe impl<K: Send, V: Send, S: Send> Send for InPlaceOnceMap<K, V, S> {}
+
+// SAFETY: a shared map moves keys and placeholders in from any thread, and shares them, and the
+// hasher, between threads.
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Sync for InPlaceOnceMap<K, V, S> {}
+
+impl<K, V: Default, S: Default> Default for InPlaceOnceMap<K, V, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(V::default(), S::default())
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for InPlaceOnceMap<K, V, S> {
+    /// Prints the mutated entries as a map.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter_mutated().map(|(key, val)| (key, Value(val))))
+            .finish()
+    }
+}
@@ -0,0 +1,248 @@
+use core::borrow::Borrow;
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use crate::debug::Value;
+
+const EMPTY: u8 = 0;
+const MUTATING: u8 = 1;
+const FILLED: u8 = 2;
+/// A slot whose mutator panicked. Lookups probe past it, and insertions may reuse it.
+const VACATED: u8 = 3;
+
+/// A fixed-capacity memo table, where the value of each key is mutated in place exactly once.
+///
+/// The table holds up to `N` entries in open-addressed slots, each starting out as a copy of the
+/// same placeholder, and never allocates, so it suits `no_std` and real-time code that caches a
+/// bounded set of derived values, e.g. filter kernels.
+pub struct OnceMemo<K, V, const N: usize> {
+    states: [Cell<u8>; N],
+    keys: [UnsafeCell<MaybeUninit<K>>; N],
+    values: [UnsafeCell<V>; N],
+}
+
+/// Where a key lives, or would live, in the table.
+enum Slot {
+    Filled(usize),
+    Vacant(usize),
+    Full,
+}
+
+impl<K, V, const N: usize> OnceMemo<K, V, N> {
+    /// Creates an empty table, every slot of which holds a copy of `placeholder`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is 0.
+    #[must_use]
+    pub const fn new(placeholder: V) -> Self
+    where
+        V: Copy,
+    {
+        const { assert!(N > 0, "a `OnceMemo` needs at least one slot") };
+
+        let mut values = [const { MaybeUninit::<UnsafeCell<V>>::uninit() }; N];
+        let mut i = 0;
+        while i < N {
+            values[i] = MaybeUninit::new(UnsafeCell::new(placeholder));
+            i += 1;
+        }
+
+        Self {
+            states: [const { Cell::new(EMPTY) }; N],
+            keys: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            // SAFETY: every element was just initialized, and `MaybeUninit<UnsafeCell<V>>` has the
+            // same layout as `UnsafeCell<V>`.
+            values: unsafe { ptr::read((&raw const values).cast::<[UnsafeCell<V>; N]>()) },
+        }
+    }
+
+    /// Returns the number of keys the table can hold.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of keys in the table.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.states
+            .iter()
+            .filter(|state| state.get() == FILLED)
+            .count()
+    }
+
+    /// Returns whether the table holds no keys.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// # Safety
+    ///
+    /// The slot at `index` must hold a key.
+    #[inline]
+    unsafe fn key(&self, index: usize) -> &K {
+        unsafe { (*self.keys[index].get()).assume_init_ref() }
+    }
+
+    /// # Safety
+    ///
+    /// The slot at `index` must be filled.
+    #[inline]
+    unsafe fn value(&self, index: usize) -> &V {
+        debug_assert_eq!(self.states[index].get(), FILLED);
+        unsafe { &*self.values[index].get() }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Slot
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut hasher = Fnv::default();
+        key.hash(&mut hasher);
+        // Truncating the hash on 32-bit targets is fine, as it only picks the first slot.
+        let start = hasher.finish() as usize % N;
+
+        let mut vacated = None;
+        for i in 0..N {
+            let index = (start + i) % N;
+            match self.states[index].get() {
+                EMPTY => return Slot::Vacant(vacated.unwrap_or(index)),
+                VACATED => {
+                    vacated.get_or_insert(index);
+                }
+                // SAFETY: filled and mutating slots hold a key.
+                state if unsafe { self.key(index) }.borrow() == key => {
+                    assert!(state == FILLED, "reentrant mutation of a `OnceMemo` entry");
+                    return Slot::Filled(index);
+                }
+                _ => {}
+            }
+        }
+
+        vacated.map_or(Slot::Full, Slot::Vacant)
+    }
+
+    /// Gets the value of `key`.
+    ///
+    /// Returns `None` if the key is not in the table.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.find(key) {
+            // SAFETY: the slot is filled.
+            Slot::Filled(index) => Some(unsafe { self.value(index) }),
+            Slot::Vacant(..) | Slot::Full => None,
+        }
+    }
+
+    /// Gets the value of `key`, mutating a free slot's placeholder with `f` and filling it with
+    /// `key` if the key is not in the table yet.
+    ///
+    /// Returns `None` if the key is not in the table and every slot is taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` looks up `key` itself. If `f` panics, the panic is propagated to the caller,
+    /// and the key is not added; a later mutator of the same slot runs on whatever `f` left
+    /// behind.
+    pub fn get_or_mutate<F>(&self, key: K, f: F) -> Option<&V>
+    where
+        K: Hash + Eq,
+        F: FnOnce(&mut V),
+    {
+        let index = match self.find(&key) {
+            // SAFETY: the slot is filled.
+            Slot::Filled(index) => return Some(unsafe { self.value(index) }),
+            Slot::Vacant(index) => index,
+            Slot::Full => return None,
+        };
+
+        // The key goes in first, so that lookups from inside `f` probe past this slot.
+        // SAFETY: the slot is vacant, so nothing borrows its key or value.
+        unsafe { (*self.keys[index].get()).write(key) };
+        self.states[index].set(MUTATING);
+        let vacate = Vacate {
+            state: &self.states[index],
+            key: self.keys[index].get(),
+        };
+        // SAFETY: the `MUTATING` state keeps every other lookup away from the value.
+        f(unsafe { &mut *self.values[index].get() });
+        core::mem::forget(vacate);
+        self.states[index].set(FILLED);
+
+        // SAFETY: the slot was just filled.
+        Some(unsafe { self.value(index) })
+    }
+
+    /// Returns an iterator over the keys in the table and their values, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        (0..N)
+            .filter(|&index| self.states[index].get() == FILLED)
+            // SAFETY: the slot is filled.
+            .map(|index| unsafe { (self.key(index), self.value(index)) })
+    }
+}
+
+/// Vacates a slot whose mutator unwinds, dropping its key.
+struct Vacate<'a, K> {
+    state: &'a Cell<u8>,
+    key: *mut MaybeUninit<K>,
+}
+
+impl<K> Drop for Vacate<'_, K> {
+    fn drop(&mut self) {
+        self.state.set(VACATED);
+        // SAFETY: the key was written before the mutator ran, and nothing else borrows it.
+        unsafe { (*self.key).assume_init_drop() };
+    }
+}
+
+impl<K, V, const N: usize> Drop for OnceMemo<K, V, N> {
+    fn drop(&mut self) {
+        for (state, key) in self.states.iter().zip(&mut self.keys) {
+            if state.get() == FILLED {
+                // SAFETY: filled slots hold a key, and this is the last use of it.
+                unsafe { key.get_mut().assume_init_drop() };
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for OnceMemo<K, V, N> {
+    /// Prints the filled entries as a map.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(key, val)| (key, Value(val))))
+            .finish()
+    }
+}
+
+/// The 64-bit FNV-1a hash, which needs no allocation or random state.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
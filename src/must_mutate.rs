@@ -0,0 +1,106 @@
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+#[cfg(debug_assertions)]
+use core::panic::Location;
+use core::ptr;
+
+use crate::InPlaceOnceCell;
+
+/// A cell that can only be mutated once, and must be: in debug builds, dropping it untouched
+/// panics.
+///
+/// This catches cells that were meant to be set up but never were, which otherwise only show up
+/// as placeholder values somewhere downstream. The panic names the type and where the cell was
+/// created. Release builds check nothing, and the wrapper costs nothing there.
+///
+/// A cell dropped while the thread is already panicking is not checked, with the `std` feature,
+/// so as not to abort the process. Use [`MustMutate::into_inner`] to take the value out whether
+/// or not it was mutated.
+pub struct MustMutate<T> {
+    cell: InPlaceOnceCell<T>,
+    #[cfg(debug_assertions)]
+    created: &'static Location<'static>,
+}
+
+impl<T> MustMutate<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    #[track_caller]
+    pub const fn new(value: T) -> Self {
+        Self {
+            cell: InPlaceOnceCell::new(value),
+            #[cfg(debug_assertions)]
+            created: Location::caller(),
+        }
+    }
+
+    /// Consumes the cell, returning the wrapped value without checking that it was mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so the cell is only moved out once.
+        unsafe { ptr::read(&this.cell) }.into_inner()
+    }
+}
+
+impl<T> Deref for MustMutate<T> {
+    type Target = InPlaceOnceCell<T>;
+
+    #[inline]
+    fn deref(&self) -> &InPlaceOnceCell<T> {
+        &self.cell
+    }
+}
+
+impl<T> DerefMut for MustMutate<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut InPlaceOnceCell<T> {
+        &mut self.cell
+    }
+}
+
+impl<T> Drop for MustMutate<T> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            #[cfg(feature = "std")]
+            if std::thread::panicking() {
+                return;
+            }
+            if self.cell.get().is_none() {
+                dropped_untouched(core::any::type_name::<T>(), self.created);
+            }
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+#[cold]
+fn dropped_untouched(ty: &str, created: &Location<'_>) -> ! {
+    panic!("`MustMutate<{ty}>` created at {created} was dropped without being mutated")
+}
+
+impl<T: Default> Default for MustMutate<T> {
+    #[inline]
+    #[track_caller]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MustMutate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.cell, f)
+    }
+}
+
+impl<T> From<T> for MustMutate<T> {
+    /// Creates a new `MustMutate<T>` containing `value`. This new cell is not yet mutated.
+    #[inline]
+    #[track_caller]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
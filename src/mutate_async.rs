@@ -0,0 +1,128 @@
+use core::future::Future;
+
+/// A cell whose value becomes available asynchronously, once it is mutated.
+///
+/// This lets generic code, e.g. middleware, accept any of the async cells of this crate without
+/// committing to a runtime or a concrete type. It is implemented by
+/// [`AsyncInPlaceOnceLock`](crate::AsyncInPlaceOnceLock) with the `async` feature,
+/// [`AsyncInPlaceOnceCsLock`](crate::AsyncInPlaceOnceCsLock) with the `critical-section`
+/// feature, and [`AsyncInPlaceLazyLock`](crate::AsyncInPlaceLazyLock), which mutates itself
+/// with its own mutator when waited on.
+///
+/// Cells that take their mutator at each call also implement [`OnceMutateAsyncWith`].
+pub trait OnceMutateAsync {
+    /// The type of the value.
+    type Value;
+
+    /// Gets the reference to the underlying value, without waiting.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    fn get(&self) -> Option<&Self::Value>;
+
+    /// Waits until the cell is mutated, then returns a reference to the value.
+    ///
+    /// Lazy cells run their own mutator if needed; the others wait for some other task to
+    /// mutate them.
+    fn wait(&self) -> impl Future<Output = &Self::Value>;
+}
+
+/// An async cell that is mutated in place once, by whichever caller gets to it first, with the
+/// mutator that caller passes.
+pub trait OnceMutateAsyncWith: OnceMutateAsync {
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated.
+    fn get_or_try_mutate<F, E>(&self, f: F) -> impl Future<Output = Result<&Self::Value, E>>
+    where
+        F: AsyncFnOnce(&mut Self::Value) -> Result<(), E>;
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    fn get_or_mutate<F>(&self, f: F) -> impl Future<Output = &Self::Value>
+    where
+        F: AsyncFnOnce(&mut Self::Value),
+    {
+        async move {
+            match self
+                .get_or_try_mutate(async |val: &mut Self::Value| {
+                    f(val).await;
+                    Ok::<(), ()>(())
+                })
+                .await
+            {
+                Ok(val) => val,
+                Err(..) => unreachable!(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> OnceMutateAsync for crate::AsyncInPlaceOnceLock<T> {
+    type Value = T;
+
+    #[inline]
+    fn get(&self) -> Option<&T> {
+        Self::get(self)
+    }
+
+    #[inline]
+    fn wait(&self) -> impl Future<Output = &T> {
+        Self::wait(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> OnceMutateAsyncWith for crate::AsyncInPlaceOnceLock<T> {
+    #[inline]
+    fn get_or_try_mutate<F, E>(&self, f: F) -> impl Future<Output = Result<&T, E>>
+    where
+        F: AsyncFnOnce(&mut T) -> Result<(), E>,
+    {
+        Self::get_or_try_mutate(self, f)
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<T> OnceMutateAsync for crate::AsyncInPlaceOnceCsLock<T> {
+    type Value = T;
+
+    #[inline]
+    fn get(&self) -> Option<&T> {
+        Self::get(self)
+    }
+
+    #[inline]
+    fn wait(&self) -> impl Future<Output = &T> {
+        Self::wait(self)
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<T> OnceMutateAsyncWith for crate::AsyncInPlaceOnceCsLock<T> {
+    #[inline]
+    fn get_or_try_mutate<F, E>(&self, f: F) -> impl Future<Output = Result<&T, E>>
+    where
+        F: AsyncFnOnce(&mut T) -> Result<(), E>,
+    {
+        Self::get_or_try_mutate(self, f)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T, F> OnceMutateAsync for crate::AsyncInPlaceLazyLock<T, F>
+where
+    F: AsyncFn(&mut T),
+{
+    type Value = T;
+
+    #[inline]
+    fn get(&self) -> Option<&T> {
+        self.try_get()
+    }
+
+    #[inline]
+    fn wait(&self) -> impl Future<Output = &T> {
+        Self::force(self)
+    }
+}
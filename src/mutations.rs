@@ -0,0 +1,94 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use alloc::vec::Vec;
+use event_listener::EventListener;
+use futures_core::Stream;
+
+use crate::AsyncInPlaceOnceLock;
+
+/// A [`Stream`] of the locks in a slice that become mutated, created by
+/// [`AsyncInPlaceOnceLock::mutations`].
+///
+/// Each lock is yielded exactly once, as `(index, &value)`, in the order the mutations are
+/// observed. Locks that are already mutated when the stream is first polled are yielded in index
+/// order. The stream ends once every lock has been yielded.
+#[must_use = "streams do nothing unless polled"]
+pub struct Mutations<'a, T> {
+    locks: &'a [AsyncInPlaceOnceLock<T>],
+    /// The listener of each lock that has not been yielded yet, once it has been registered.
+    listeners: Vec<Option<EventListener>>,
+    /// The locks that have not been yielded yet, as a circular list: `next[i]` is the one checked
+    /// after `i`. Unlinking a yielded lock takes constant time.
+    next: Vec<usize>,
+    /// The lock checked right before the next one to check, so that each poll resumes where the
+    /// last one stopped.
+    cursor: usize,
+    /// The number of locks that have not been yielded yet.
+    remaining: usize,
+}
+
+impl<T> AsyncInPlaceOnceLock<T> {
+    /// Returns a stream yielding `(index, &value)` for each lock in `locks` as it becomes
+    /// mutated.
+    ///
+    /// This lets a consumer process results as lazy mutations complete instead of polling every
+    /// lock. Each poll resumes checking the locks that have not been yielded yet where the last
+    /// one stopped, so draining locks that are already mutated takes constant time per lock, while
+    /// a poll that finds none checks each of them once.
+    pub fn mutations(locks: &[Self]) -> Mutations<'_, T> {
+        let len = locks.len();
+        Mutations {
+            locks,
+            listeners: (0..len).map(|_| None).collect(),
+            next: (0..len).map(|i| (i + 1) % len).collect(),
+            cursor: len.saturating_sub(1),
+            remaining: len,
+        }
+    }
+}
+
+impl<'a, T> Stream for Mutations<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut prev = this.cursor;
+        for _ in 0..this.remaining {
+            let idx = this.next[prev];
+            let lock = &this.locks[idx];
+            let listener = &mut this.listeners[idx];
+            loop {
+                if let Some(val) = lock.get() {
+                    *listener = None;
+                    this.next[prev] = this.next[idx];
+                    this.cursor = prev;
+                    this.remaining -= 1;
+                    return Poll::Ready(Some((idx, val)));
+                }
+
+                match listener {
+                    // Register before re-checking so a mutation finishing in between is not
+                    // missed.
+                    None => *listener = Some(lock.listen_mutated()),
+                    Some(l) => match Pin::new(l).poll(cx) {
+                        Poll::Ready(()) => *listener = None,
+                        Poll::Pending => break,
+                    },
+                }
+            }
+            prev = idx;
+        }
+
+        if this.remaining == 0 {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
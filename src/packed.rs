@@ -0,0 +1,440 @@
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ptr::{self, NonNull};
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::debug::{self, LockState};
+
+/// A type with a bit that no valid value uses, which a [`PackedInPlaceOnceCell`] borrows to
+/// store whether it was mutated.
+///
+/// The cell sets the bit while it is untouched, so a mutated value is stored unchanged. Typical
+/// spare bits are the low bit of a pointer to a type aligned to at least 2, or the high bit of a
+/// length that never exceeds `isize::MAX`.
+///
+/// # Safety
+///
+/// - No valid value of `Self` may have the spare bit set, so [`is_flag_set`] tells a flagged value
+///   from any valid one.
+/// - [`set_flag`] followed by [`clear_flag`] must give back exactly the original value, including
+///   the provenance of any pointer in it.
+/// - The spare bit must not depend on the rest of the value; all three functions must only read
+///   and write the bit at `value`, and must not move or drop anything.
+///
+/// [`is_flag_set`]: HasSpareFlagBit::is_flag_set
+/// [`set_flag`]: HasSpareFlagBit::set_flag
+/// [`clear_flag`]: HasSpareFlagBit::clear_flag
+pub unsafe trait HasSpareFlagBit: Sized {
+    /// Sets the spare bit of the value at `value`, which then no longer holds a valid `Self`.
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to a valid `Self`.
+    unsafe fn set_flag(value: *mut Self);
+
+    /// Clears the spare bit of the value at `value`, restoring the value it held before
+    /// [`HasSpareFlagBit::set_flag`].
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to a valid `Self` flagged by [`HasSpareFlagBit::set_flag`].
+    unsafe fn clear_flag(value: *mut Self);
+
+    /// Returns whether the spare bit of the value at `value` is set.
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to a valid `Self`, or to one flagged by [`HasSpareFlagBit::set_flag`].
+    unsafe fn is_flag_set(value: *const Self) -> bool;
+}
+
+/// Implements [`HasSpareFlagBit`] for thin pointers to `T`, using the low bit of the address,
+/// which is always clear when `T` is aligned to at least 2.
+macro_rules! impl_spare_flag_bit_for_ptr {
+    ($($(#[$attr:meta])* $ptr:ty;)*) => {$(
+        $(#[$attr])*
+        // SAFETY: `$ptr` is a single thin pointer to an aligned `T`, whose low bit is always clear
+        // when `align_of::<T>() >= 2`, which is checked at compile time.
+        unsafe impl<T> HasSpareFlagBit for $ptr {
+            #[inline]
+            unsafe fn set_flag(value: *mut Self) {
+                const { assert!(align_of::<T>() >= 2, "the pointee has no spare alignment bit") };
+                let word = value.cast::<*mut u8>();
+                // SAFETY: `value` points to a pointer, which has the layout of `*mut u8`.
+                unsafe { word.write(word.read().map_addr(|addr| addr | 1)) };
+            }
+
+            #[inline]
+            unsafe fn clear_flag(value: *mut Self) {
+                const { assert!(align_of::<T>() >= 2, "the pointee has no spare alignment bit") };
+                let word = value.cast::<*mut u8>();
+                // SAFETY: `value` points to a pointer, which has the layout of `*mut u8`.
+                unsafe { word.write(word.read().map_addr(|addr| addr & !1)) };
+            }
+
+            #[inline]
+            unsafe fn is_flag_set(value: *const Self) -> bool {
+                const { assert!(align_of::<T>() >= 2, "the pointee has no spare alignment bit") };
+                // SAFETY: `value` points to a pointer, which has the layout of `*const u8`.
+                unsafe { value.cast::<*const u8>().read() }.addr() & 1 != 0
+            }
+        }
+    )*};
+}
+
+impl_spare_flag_bit_for_ptr! {
+    #[cfg(feature = "alloc")]
+    alloc::boxed::Box<T>;
+    &T;
+    &mut T;
+    NonNull<T>;
+}
+
+/// A cell that can only be mutated once and is exactly as large as `T`, storing whether it was
+/// mutated in a spare bit of the value. See [`HasSpareFlagBit`].
+///
+/// `PackedInPlaceOnceCell<Box<T>>` is pointer-sized, where `InPlaceOnceCell<Box<T>>` is twice
+/// that.
+///
+/// Having no room to mark itself as being mutated, a cell being mutated is listed elsewhere, to
+/// catch a mutator that mutates its own cell. With `std`, each thread keeps its own list. Without
+/// it, the whole program shares one list behind a spinlock, so mutating a packed cell from a
+/// signal handler or an interrupt is not safe: if it preempted another mutation of a packed cell
+/// while that one held the lock, it spins forever.
+pub struct PackedInPlaceOnceCell<T: HasSpareFlagBit> {
+    /// Holds a flagged value while the cell is untouched.
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T: HasSpareFlagBit> PackedInPlaceOnceCell<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        let mut value = MaybeUninit::new(value);
+        // SAFETY: `value` is a valid `T`.
+        unsafe { T::set_flag(value.as_mut_ptr()) };
+        Self {
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        // SAFETY: the cell always holds a valid `T`, flagged while untouched.
+        !unsafe { T::is_flag_set((*self.value.get()).as_ptr()) }
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { (*self.value.get()).assume_init_ref() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if self.is_mutated() {
+            // SAFETY: the cell is mutated, so the value is not flagged.
+            Some(unsafe { self.value.get_mut().assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f(&mut T)` if the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f()` mutates the cell itself.
+    ///
+    /// If `f()` panics, the panic is propagated to the caller, and the cell is never fully mutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f(&mut T)` if the cell was never mutated.
+    ///
+    /// Returns an error if the cell was uninitialized and `f()` returns an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f()` mutates the cell itself.
+    #[inline]
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+
+        self.try_mutate(f)?;
+        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
+        Ok(unsafe { self.get_unchecked() })
+    }
+
+    // It's most likely that the value is already initialized.
+    #[cold]
+    #[inline(never)]
+    fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        // There is no room in the cell to mark it as being mutated, so it is looked up among the
+        // cells being mutated instead, before a reentrant call reads a second owned copy.
+        let mutating = Mutating {
+            cell: ptr::from_ref(self).cast(),
+            next: Cell::new(ptr::null()),
+        };
+        let _link = Link::new(&mutating);
+
+        // The flag cannot be cleared in place, since `get` would then hand out the value while
+        // `f` mutates it. `f` mutates a copy instead, which is written back flagged on failure.
+        // SAFETY: the cell is untouched, so it holds a flagged `T`, and this copy owns it until it
+        // is written back. It is not being mutated, as `_link` checked.
+        let mut val = unsafe { self.value.get().read() };
+        // SAFETY: `val` holds a flagged `T`.
+        unsafe { T::clear_flag(val.as_mut_ptr()) };
+        let mut guard = WriteBack {
+            cell: self,
+            // SAFETY: `val` now holds a valid `T`.
+            val: ManuallyDrop::new(unsafe { val.assume_init() }),
+        };
+        f(&mut guard.val)?;
+
+        // SAFETY: `guard` is forgotten right after, so the value is only moved out once.
+        let val = unsafe { ManuallyDrop::take(&mut guard.val) };
+        mem::forget(guard);
+        // SAFETY: no reference to the value is alive while the cell is untouched.
+        unsafe { self.value.get().write(MaybeUninit::new(val)) };
+
+        Ok(())
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        let value = this.value.get_mut();
+        // SAFETY: the cell always holds a valid `T`, flagged while untouched.
+        if !unsafe { T::is_flag_set(value.as_ptr()) } {
+            // SAFETY: the value is not flagged, so it is a valid `T`.
+            return unsafe { value.assume_init_read() };
+        }
+
+        // SAFETY: the value is flagged, and `this` is never dropped.
+        unsafe {
+            T::clear_flag(value.as_mut_ptr());
+            value.assume_init_read()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The cells being mutated on this thread, linked through the [`Mutating`] entries on the
+    /// stacks of their mutators. A cell is not `Sync`, so it can only be mutated again from the
+    /// thread already mutating it.
+    static MUTATING: Cell<*const Mutating> = const { Cell::new(ptr::null()) };
+}
+
+/// Runs `f` on the head of the list of the cells being mutated. `f` must not panic.
+#[cfg(feature = "std")]
+fn with_mutating<R>(f: impl FnOnce(&mut *const Mutating) -> R) -> R {
+    MUTATING.with(|head| {
+        let mut node = head.get();
+        let res = f(&mut node);
+        head.set(node);
+        res
+    })
+}
+
+/// The cells being mutated, on every thread, linked through the [`Mutating`] entries on the
+/// stacks of their mutators.
+///
+/// Only the cold mutation path locks it, for as long as it takes to link or unlink an entry. The
+/// lock is a spinlock, which is not async-signal or interrupt safe.
+#[cfg(not(feature = "std"))]
+static MUTATING: Registry = Registry {
+    locked: AtomicBool::new(false),
+    head: UnsafeCell::new(ptr::null()),
+};
+
+#[cfg(not(feature = "std"))]
+struct Registry {
+    locked: AtomicBool,
+    head: UnsafeCell<*const Mutating>,
+}
+
+// SAFETY: `head`, and the `next` links of the entries, are only accessed while holding `locked`,
+// which excludes every other thread.
+#[cfg(not(feature = "std"))]
+unsafe impl Sync for Registry {}
+
+/// Runs `f` on the head of the list of the cells being mutated, excluding every other thread.
+/// `f` must not panic.
+#[cfg(not(feature = "std"))]
+fn with_mutating<R>(f: impl FnOnce(&mut *const Mutating) -> R) -> R {
+    while MUTATING.locked.swap(true, Ordering::Acquire) {
+        core::hint::spin_loop();
+    }
+    // SAFETY: holding `locked` excludes every other access.
+    let res = f(unsafe { &mut *MUTATING.head.get() });
+    MUTATING.locked.store(false, Ordering::Release);
+    res
+}
+
+/// A cell being mutated, in the list of [`MUTATING`].
+struct Mutating {
+    cell: *const (),
+    next: Cell<*const Mutating>,
+}
+
+/// Keeps a [`Mutating`] entry linked for as long as it lives.
+struct Link<'a>(&'a Mutating);
+
+impl<'a> Link<'a> {
+    /// Links `entry`, which stays in place until the link is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell of `entry` is already being mutated, which can only be by the current
+    /// thread, from inside its own mutator.
+    fn new(entry: &'a Mutating) -> Self {
+        let reentrant = with_mutating(|head| {
+            let mut node = *head;
+            // SAFETY: linked entries stay in place until they are unlinked, inside `with_mutating`.
+            while let Some(current) = unsafe { node.as_ref() } {
+                if current.cell == entry.cell {
+                    return true;
+                }
+                node = current.next.get();
+            }
+            entry.next.set(*head);
+            *head = entry;
+            false
+        });
+        assert!(
+            !reentrant,
+            "reentrant mutation of a `PackedInPlaceOnceCell`"
+        );
+        Self(entry)
+    }
+}
+
+impl Drop for Link<'_> {
+    fn drop(&mut self) {
+        let entry: *const Mutating = self.0;
+        with_mutating(|head| {
+            let mut link = &mut *head;
+            // SAFETY: as in `Link::new`. The entry is linked, so it is found before the end.
+            while *link != entry {
+                link = unsafe { &mut *(**link).next.as_ptr() };
+            }
+            *link = self.0.next.get();
+        });
+    }
+}
+
+/// Writes the mutator's copy back to the cell, flagged, if the mutator fails or unwinds.
+struct WriteBack<'a, T: HasSpareFlagBit> {
+    cell: &'a PackedInPlaceOnceCell<T>,
+    val: ManuallyDrop<T>,
+}
+
+impl<T: HasSpareFlagBit> Drop for WriteBack<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the value is moved out once, as the guard is being dropped.
+        let mut val = MaybeUninit::new(unsafe { ManuallyDrop::take(&mut self.val) });
+        // SAFETY: `val` is a valid `T`, and the cell is untouched, so no reference to its value
+        // is alive.
+        unsafe {
+            T::set_flag(val.as_mut_ptr());
+            self.cell.value.get().write(val);
+        }
+    }
+}
+
+impl<T: HasSpareFlagBit> Drop for PackedInPlaceOnceCell<T> {
+    fn drop(&mut self) {
+        let value = self.value.get_mut();
+        // SAFETY: the cell always holds a valid `T`, flagged while untouched, which is dropped
+        // exactly once here.
+        unsafe {
+            if T::is_flag_set(value.as_ptr()) {
+                T::clear_flag(value.as_mut_ptr());
+            }
+            value.assume_init_drop();
+        }
+    }
+}
+
+impl<T: HasSpareFlagBit + Default> Default for PackedInPlaceOnceCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: HasSpareFlagBit + fmt::Debug> fmt::Debug for PackedInPlaceOnceCell<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is mutated. The value of an
+    /// untouched cell is not printed, since it is flagged.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.is_mutated() {
+            LockState::Mutated
+        } else {
+            LockState::Untouched
+        };
+        debug::fmt_cell(f, "PackedInPlaceOnceCell", state, self.get(), |_| {})
+    }
+}
+
+impl<T: HasSpareFlagBit + PartialEq> PartialEq for PackedInPlaceOnceCell<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: HasSpareFlagBit + Eq> Eq for PackedInPlaceOnceCell<T> {}
+
+impl<T: HasSpareFlagBit> From<T> for PackedInPlaceOnceCell<T> {
+    /// Creates a new `PackedInPlaceOnceCell<T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,208 @@
+use core::cell::RefCell;
+use core::fmt;
+
+use crate::InPlaceOnceCell;
+use crate::errors::PersistError;
+
+/// The marker at the start of every record written by a [`PersistentOnceCell`].
+const MARKER: [u8; 4] = *b"IPOC";
+
+/// Byte storage that survives a reset, e.g. a flash page or an EEPROM region.
+///
+/// A [`PersistentOnceCell`] stores a single record starting at offset 0: the 4-byte marker
+/// `b"IPOC"`, the encoded value, and a little-endian CRC-32 (IEEE) of the encoded value.
+pub trait NvmStorage {
+    /// The error returned by the storage driver.
+    type Error;
+
+    /// Fills `buf` with the bytes stored at `offset`.
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `data` at `offset`.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// A value with a fixed-size byte encoding that can be written to an [`NvmStorage`].
+pub trait NvmValue {
+    /// The encoded value, usually `[u8; N]`.
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
+
+    /// Encodes the value.
+    fn encode(&self) -> Self::Bytes;
+
+    /// Restores a value previously produced by [`NvmValue::encode`] in place.
+    fn decode_in_place(&mut self, bytes: &Self::Bytes);
+}
+
+macro_rules! impl_nvm_value_for_num {
+    ($($t:ty)*) => {$(
+        impl NvmValue for $t {
+            type Bytes = [u8; size_of::<$t>()];
+
+            #[inline]
+            fn encode(&self) -> Self::Bytes {
+                self.to_le_bytes()
+            }
+
+            #[inline]
+            fn decode_in_place(&mut self, bytes: &Self::Bytes) {
+                *self = <$t>::from_le_bytes(*bytes);
+            }
+        }
+    )*};
+}
+
+impl_nvm_value_for_num!(u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64);
+
+impl NvmValue for bool {
+    type Bytes = [u8; 1];
+
+    #[inline]
+    fn encode(&self) -> Self::Bytes {
+        [u8::from(*self)]
+    }
+
+    #[inline]
+    fn decode_in_place(&mut self, bytes: &Self::Bytes) {
+        *self = bytes[0] != 0;
+    }
+}
+
+impl<const N: usize> NvmValue for [u8; N]
+where
+    [u8; N]: Default,
+{
+    type Bytes = [u8; N];
+
+    #[inline]
+    fn encode(&self) -> Self::Bytes {
+        *self
+    }
+
+    #[inline]
+    fn decode_in_place(&mut self, bytes: &Self::Bytes) {
+        *self = *bytes;
+    }
+}
+
+/// A cell that can only be mutated once, whose mutated value is persisted to non-volatile
+/// storage and restored on construction.
+///
+/// This suits data that is computed once per device, such as calibration, and read forever
+/// after.
+pub struct PersistentOnceCell<T, S> {
+    cell: InPlaceOnceCell<T>,
+    storage: RefCell<S>,
+}
+
+impl<T: NvmValue, S: NvmStorage> PersistentOnceCell<T, S> {
+    /// Creates a new cell backed by `storage`.
+    ///
+    /// If `storage` holds a valid record, `value` is restored from it in place and the cell
+    /// starts out mutated. Otherwise the cell starts out untouched with `value` as placeholder.
+    ///
+    /// Returns an error if reading from `storage` fails.
+    pub fn new(value: T, mut storage: S) -> Result<Self, S::Error> {
+        let cell = InPlaceOnceCell::new(value);
+
+        let mut marker = [0; MARKER.len()];
+        storage.read(0, &mut marker)?;
+        if marker == MARKER {
+            let mut bytes = T::Bytes::default();
+            let mut crc = [0; 4];
+            storage.read(MARKER.len(), bytes.as_mut())?;
+            storage.read(MARKER.len() + bytes.as_ref().len(), &mut crc)?;
+
+            if crc32(bytes.as_ref()) == u32::from_le_bytes(crc) {
+                cell.get_or_mutate(|val| val.decode_in_place(&bytes));
+            }
+        }
+
+        Ok(Self {
+            cell,
+            storage: RefCell::new(storage),
+        })
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` and persisting the result if the cell
+    /// was never mutated.
+    ///
+    /// Returns an error if writing to the storage fails, in which case the cell stays unmutated.
+    pub fn get_or_mutate<F>(&self, f: F) -> Result<&T, S::Error>
+    where
+        F: FnOnce(&mut T),
+    {
+        self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        })
+        .map_err(|err| match err {
+            PersistError::Mutator(()) => unreachable!(),
+            PersistError::Storage(err) => err,
+        })
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` and persisting the result if the cell
+    /// was never mutated.
+    ///
+    /// Returns an error if `f` fails or writing to the storage fails, in which case the cell
+    /// stays unmutated.
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, PersistError<E, S::Error>>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        self.cell.get_or_try_mutate(|val| {
+            f(val).map_err(PersistError::Mutator)?;
+            self.persist(val).map_err(PersistError::Storage)
+        })
+    }
+
+    fn persist(&self, val: &T) -> Result<(), S::Error> {
+        let bytes = val.encode();
+        let bytes = bytes.as_ref();
+        let mut storage = self.storage.borrow_mut();
+
+        // The marker goes last, so an interrupted write never leaves a valid-looking record.
+        storage.write(MARKER.len(), bytes)?;
+        storage.write(MARKER.len() + bytes.len(), &crc32(bytes).to_le_bytes())?;
+        storage.write(0, &MARKER)
+    }
+}
+
+impl<T, S> PersistentOnceCell<T, S> {
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    /// Consumes the cell, returning the wrapped value and the storage. Note that this occurs even
+    /// when the cell was never mutated.
+    #[inline]
+    pub fn into_parts(self) -> (T, S) {
+        (self.cell.into_inner(), self.storage.into_inner())
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for PersistentOnceCell<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PersistentOnceCell")
+            .field(&self.cell)
+            .finish()
+    }
+}
+
+/// CRC-32 (IEEE 802.3), computed bitwise to avoid a lookup table.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
@@ -0,0 +1,65 @@
+use core::fmt;
+use core::ops::Deref;
+
+use alloc::boxed::Box;
+
+use crate::InPlaceOnceCell;
+
+/// A pool of cells that can each be mutated once per scope, and then all reset for reuse.
+///
+/// Each scope, e.g. a request or a frame, borrows the cells to mutate lazily through the pool,
+/// which dereferences to a slice of [`InPlaceOnceCell`]s. Between scopes,
+/// [`OncePool::reset_all`] returns every cell to the untouched state and restores its placeholder
+/// with [`Clone::clone_from`], which reuses what the value owns, e.g. the buffer of a `Vec`, so a
+/// warmed-up pool stops allocating.
+pub struct OncePool<T> {
+    placeholder: T,
+    cells: Box<[InPlaceOnceCell<T>]>,
+}
+
+impl<T: Clone> OncePool<T> {
+    /// Creates a pool of `len` cells that have not been mutated, each holding a clone of
+    /// `placeholder`.
+    #[must_use]
+    pub fn new(len: usize, placeholder: T) -> Self {
+        Self {
+            cells: (0..len)
+                .map(|_| InPlaceOnceCell::new(placeholder.clone()))
+                .collect(),
+            placeholder,
+        }
+    }
+
+    /// Returns every cell to the untouched state, restoring its placeholder in place.
+    ///
+    /// Cells that are untouched are restored too, as a failed mutator may have changed them.
+    pub fn reset_all(&mut self) {
+        for cell in &mut self.cells {
+            cell.reset().clone_from(&self.placeholder);
+        }
+    }
+}
+
+impl<T> OncePool<T> {
+    /// Returns the placeholder that cells are reset to.
+    #[inline]
+    #[must_use]
+    pub fn placeholder(&self) -> &T {
+        &self.placeholder
+    }
+}
+
+impl<T> Deref for OncePool<T> {
+    type Target = [InPlaceOnceCell<T>];
+
+    #[inline]
+    fn deref(&self) -> &[InPlaceOnceCell<T>] {
+        &self.cells
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OncePool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.cells.iter()).finish()
+    }
+}
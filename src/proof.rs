@@ -0,0 +1,91 @@
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::InPlaceOnceCell;
+
+/// An invariant lifetime, so that no two brands unify.
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// A zero-sized proof that the cell branded `'id` is mutated, handed out by [`Branded`].
+///
+/// Once a cell is mutated, it stays mutated for as long as it is borrowed, so the proof can be
+/// copied around freely, and lets [`Branded::get_proven`] skip checking the flag.
+#[derive(Clone, Copy)]
+pub struct MutationProof<'id>(Brand<'id>);
+
+/// A cell branded with a lifetime `'id` unique to it, created by [`InPlaceOnceCell::branded`].
+///
+/// The brand ties each [`MutationProof`] to the one cell it was made for, the way `GhostCell`
+/// ties its tokens to their cells.
+pub struct Branded<'id, 'a, T> {
+    cell: &'a InPlaceOnceCell<T>,
+    brand: Brand<'id>,
+}
+
+impl<T> InPlaceOnceCell<T> {
+    /// Calls `f` with this cell branded with a fresh lifetime, so that it can hand out
+    /// [`MutationProof`]s that only it accepts.
+    ///
+    /// This suits hot paths that read the cell many times after mutating it once.
+    #[inline]
+    pub fn branded<R>(&self, f: impl for<'id> FnOnce(Branded<'id, '_, T>) -> R) -> R {
+        f(Branded {
+            cell: self,
+            brand: PhantomData,
+        })
+    }
+}
+
+impl<'id, 'a, T> Branded<'id, 'a, T> {
+    /// Returns a proof that the cell is mutated, or `None` if it is not.
+    #[inline]
+    pub fn proof(&self) -> Option<MutationProof<'id>> {
+        self.cell.get().map(|_| MutationProof(PhantomData))
+    }
+
+    /// Like [`InPlaceOnceCell::get_or_mutate`], but also returns a proof that the cell is mutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> (&'a T, MutationProof<'id>)
+    where
+        F: FnOnce(&mut T),
+    {
+        (self.cell.get_or_mutate(f), MutationProof(PhantomData))
+    }
+
+    /// Gets the reference to the underlying value without checking whether the cell is mutated,
+    /// which `proof` already did.
+    #[inline]
+    pub fn get_proven(&self, proof: MutationProof<'id>) -> &'a T {
+        let _ = proof;
+        // SAFETY: `proof` could only be made for this cell once it was mutated, and a borrowed
+        // cell cannot go back to being untouched.
+        unsafe { self.cell.get_unchecked() }
+    }
+
+    /// Returns the cell itself.
+    #[inline]
+    pub fn cell(&self) -> &'a InPlaceOnceCell<T> {
+        self.cell
+    }
+}
+
+impl<T> Clone for Branded<'_, '_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Branded<'_, '_, T> {}
+
+impl fmt::Debug for MutationProof<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MutationProof")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Branded<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Branded").field(self.cell).finish()
+    }
+}
@@ -0,0 +1,189 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::debug::{self, LockState};
+
+const UNTOUCHED: u8 = 0;
+const PUBLISHING: u8 = 1;
+const MUTATED: u8 = 2;
+
+/// A lock that can only be mutated once, for cheap and idempotent mutators over `Copy` data,
+/// that never waits.
+///
+/// Threads racing to mutate the lock may all run the mutator, each on its own copy of the
+/// initial value. The first one to finish publishes its result, and the others return their own
+/// result without waiting for it. The mutator must therefore be idempotent: every run must give
+/// the same result, or callers may observe different values.
+///
+/// This is the approach of `once_cell::race`, applied to in-place mutation.
+pub struct RacyInPlaceOnceLock<T> {
+    initial: T,
+    value: UnsafeCell<MaybeUninit<T>>,
+    state: AtomicU8,
+}
+
+impl<T: Copy> RacyInPlaceOnceLock<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            initial: value,
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            state: AtomicU8::new(UNTOUCHED),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.load(Ordering::Acquire) == MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { (*self.value.get()).assume_init_ref() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == MUTATED {
+            // SAFETY: the value is initialized once the cell is mutated.
+            Some(unsafe { self.value.get_mut().assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating a copy of the initial value with `f` if the cell
+    /// was never mutated.
+    ///
+    /// If several threads race, each may run `f`, and each returns its own result.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating a copy of the initial value with `f` if the cell
+    /// was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case nothing is
+    /// published.
+    #[inline]
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(*val);
+        }
+
+        self.try_mutate(f)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn try_mutate<F, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        let mut val = self.initial;
+        f(&mut val)?;
+
+        // Losing the race is fine: the mutator is idempotent, so our result is just as good.
+        if self
+            .state
+            .compare_exchange(UNTOUCHED, PUBLISHING, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            // SAFETY: the `PUBLISHING` state excludes every other writer, and readers only read
+            // once the state is `MUTATED`.
+            unsafe { (*self.value.get()).write(val) };
+            self.state.store(MUTATED, Ordering::Release);
+        }
+
+        Ok(val)
+    }
+
+    /// Consumes the cell, returning the mutated value, or the initial value if the cell was never
+    /// mutated.
+    #[inline]
+    pub fn into_inner(mut self) -> T {
+        match self.get_mut() {
+            Some(val) => *val,
+            None => self.initial,
+        }
+    }
+}
+
+// SAFETY: the value is written by exactly one thread, and only read once it is published.
+unsafe impl<T: Send + Sync> Sync for RacyInPlaceOnceLock<T> {}
+
+impl<T: Copy + Default> Default for RacyInPlaceOnceLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for RacyInPlaceOnceLock<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is untouched, being published,
+    /// or mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            PUBLISHING => LockState::Mutating,
+            _ => LockState::Mutated,
+        };
+        let val = self.get().unwrap_or(&self.initial);
+        debug::fmt_cell(f, "RacyInPlaceOnceLock", state, Some(val), |_| {})
+    }
+}
+
+impl<T: Copy + PartialEq> PartialEq for RacyInPlaceOnceLock<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Copy + Eq> Eq for RacyInPlaceOnceLock<T> {}
+
+impl<T: Copy> From<T> for RacyInPlaceOnceLock<T> {
+    /// Creates a new `RacyInPlaceOnceLock<T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,206 @@
+//! The once-mutation protocol on its own, for building custom containers.
+//!
+//! [`RawInPlaceOnce`] runs the same state machine as the crate's atomic locks on a state word and
+//! payload that the caller stores wherever it likes, e.g. packed into a slab or an intrusive
+//! node:
+//!
+//! ```text
+//! UNTOUCHED --try_begin_mutation--> MUTATING --complete_mutation--> MUTATED
+//!     ^                                 |
+//!     +---------abort_mutation----------+
+//! ```
+//!
+//! Waiting for another mutator is left to the caller.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// The state of a [`RawInPlaceOnce`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum RawState {
+    /// The payload has not been mutated, and nobody is mutating it.
+    Untouched = 0,
+    /// A [`RawMutation`] has exclusive access to the payload.
+    Mutating = 1,
+    /// The payload is mutated, and may only be read from now on.
+    Mutated = 2,
+}
+
+impl RawState {
+    #[inline]
+    const fn from_u8(state: u8) -> Self {
+        match state {
+            0 => Self::Untouched,
+            1 => Self::Mutating,
+            _ => Self::Mutated,
+        }
+    }
+}
+
+/// The once-mutation protocol, over a user-supplied state word and payload.
+pub struct RawInPlaceOnce<'a, T> {
+    state: &'a AtomicU8,
+    value: *mut T,
+    _marker: PhantomData<&'a T>,
+}
+
+/// Exclusive access to the payload of a [`RawInPlaceOnce`], returned by
+/// [`RawInPlaceOnce::try_begin_mutation`].
+///
+/// Dropping this without calling [`RawInPlaceOnce::complete_mutation`] aborts the mutation, so a
+/// failing or panicking mutator never leaves the payload stuck in [`RawState::Mutating`].
+#[must_use = "dropping a mutation aborts it"]
+pub struct RawMutation<'a, T> {
+    state: &'a AtomicU8,
+    value: *mut T,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> RawInPlaceOnce<'a, T> {
+    /// Creates the protocol over `state` and `value`.
+    ///
+    /// A zeroed state word is [`RawState::Untouched`].
+    ///
+    /// # Safety
+    ///
+    /// `value` must be valid for reads and writes for `'a`. For as long as `state` is not
+    /// [`RawState::Untouched`], `value` must only be accessed through a `RawInPlaceOnce` over the
+    /// same `state`, and `state` must only be changed by it.
+    #[inline]
+    pub const unsafe fn new(state: &'a AtomicU8, value: *mut T) -> Self {
+        Self {
+            state,
+            value,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the pointer to the payload passed to [`RawInPlaceOnce::new`], with its provenance.
+    ///
+    /// Accesses through it must follow the same rules as for `new`.
+    #[inline]
+    pub const fn as_ptr(&self) -> *mut T {
+        self.value
+    }
+
+    /// Returns the current state.
+    #[inline]
+    pub fn state(&self) -> RawState {
+        RawState::from_u8(self.state.load(Ordering::Acquire))
+    }
+
+    /// Returns `true` if the payload is mutated.
+    #[inline]
+    pub fn is_mutated(&self) -> bool {
+        self.state() == RawState::Mutated
+    }
+
+    /// Gets the reference to the payload.
+    ///
+    /// Returns `None` if the payload is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&'a T> {
+        if self.is_mutated() {
+            // SAFETY: mutated payloads are only ever read, as guaranteed by `new`'s caller.
+            Some(unsafe { &*self.value })
+        } else {
+            None
+        }
+    }
+
+    /// Claims exclusive access to the payload to mutate it.
+    ///
+    /// Returns the current state if the payload is already mutated or being mutated.
+    #[inline]
+    pub fn try_begin_mutation(&self) -> Result<RawMutation<'a, T>, RawState> {
+        match self.state.compare_exchange(
+            RawState::Untouched as u8,
+            RawState::Mutating as u8,
+            Ordering::Acquire,
+            Ordering::Acquire,
+        ) {
+            Ok(..) => Ok(RawMutation {
+                state: self.state,
+                value: self.value,
+                _marker: PhantomData,
+            }),
+            Err(state) => Err(RawState::from_u8(state)),
+        }
+    }
+
+    /// Publishes the payload as mutated, returning a reference to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mutation` was not begun by a `RawInPlaceOnce` over the same state word.
+    #[inline]
+    pub fn complete_mutation(&self, mutation: RawMutation<'a, T>) -> &'a T {
+        assert!(
+            core::ptr::eq(self.state, mutation.state),
+            "mutation of another `RawInPlaceOnce`"
+        );
+        mem::forget(mutation);
+        self.state.store(RawState::Mutated as u8, Ordering::Release);
+        // SAFETY: the payload was just mutated, and is only ever read from now on.
+        unsafe { &*self.value }
+    }
+
+    /// Returns the payload to the untouched state, so it can be mutated again.
+    ///
+    /// This is the same as dropping `mutation`.
+    #[inline]
+    pub fn abort_mutation(&self, mutation: RawMutation<'a, T>) {
+        drop(mutation);
+    }
+}
+
+impl<T> RawMutation<'_, T> {
+    /// Gets a mutable reference to the payload.
+    #[inline]
+    pub fn value(&mut self) -> &mut T {
+        // SAFETY: the `Mutating` state excludes every other reader and writer until `self` is
+        // completed or dropped.
+        unsafe { &mut *self.value }
+    }
+
+    /// Gets a raw pointer to the payload, for mutators that write it through foreign code.
+    ///
+    /// It stays valid for writes until `self` is completed or dropped.
+    #[inline]
+    pub const fn as_mut_ptr(&mut self) -> *mut T {
+        self.value
+    }
+}
+
+impl<T> Drop for RawMutation<'_, T> {
+    fn drop(&mut self) {
+        self.state
+            .store(RawState::Untouched as u8, Ordering::Release);
+    }
+}
+
+// SAFETY: the protocol hands out `&mut T` to exactly one mutator and `&T` afterwards.
+unsafe impl<T: Send + Sync> Send for RawInPlaceOnce<'_, T> {}
+// SAFETY: the protocol hands out `&mut T` to exactly one mutator and `&T` afterwards.
+unsafe impl<T: Send + Sync> Sync for RawInPlaceOnce<'_, T> {}
+// SAFETY: a mutation is exclusive access to the payload.
+unsafe impl<T: Send> Send for RawMutation<'_, T> {}
+// SAFETY: a mutation only hands out `&mut T` through `&mut self`.
+unsafe impl<T: Sync> Sync for RawMutation<'_, T> {}
+
+impl<T> fmt::Debug for RawInPlaceOnce<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawInPlaceOnce")
+            .field(&self.state())
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for RawMutation<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawMutation").finish_non_exhaustive()
+    }
+}
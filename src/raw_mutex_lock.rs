@@ -0,0 +1,232 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+
+use crate::debug::{self, LockState};
+
+const UNTOUCHED: u8 = 0;
+const MUTATING: u8 = 1;
+const MUTATED: u8 = 2;
+
+/// A cell that can only be mutated once, whose mutator runs while holding an Embassy
+/// [`RawMutex`].
+///
+/// This is `InPlaceOnceCsLock` with the critical section swapped for whichever raw mutex the
+/// firmware already uses, such as `CriticalSectionRawMutex`,
+/// `ThreadModeRawMutex` or `NoopRawMutex`, so that mutating follows the same priority model as
+/// every other Embassy mutex. The lock is only `Sync` if `M` is.
+///
+/// Atomics are only ever loaded and stored, never compared-and-swapped, so this works on targets
+/// such as `thumbv6m` as well.
+pub struct InPlaceOnceRawMutexLock<M, T> {
+    mutex: M,
+    value: UnsafeCell<T>,
+    state: AtomicU8,
+}
+
+impl<M: RawMutex, T> InPlaceOnceRawMutexLock<M, T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            mutex: M::INIT,
+            value: UnsafeCell::new(value),
+            state: AtomicU8::new(UNTOUCHED),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.load(Ordering::Acquire) == MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == MUTATED {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// `f` runs while holding the mutex, so keep it short.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` calls back into this cell's mutation methods.
+    ///
+    /// If `f` panics, the panic is propagated to the caller and the cell stays unmutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` calls back into this cell's mutation methods.
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+
+        self.try_mutate(f)?;
+        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
+        Ok(unsafe { self.get_unchecked() })
+    }
+
+    #[cold]
+    fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        self.mutex.lock(|| {
+            match self.state.load(Ordering::Acquire) {
+                UNTOUCHED => {}
+                MUTATED => return Ok(()),
+                _ => panic!("reentrant mutation of an `InPlaceOnceRawMutexLock`"),
+            }
+
+            self.state.store(MUTATING, Ordering::Relaxed);
+            let reset = ResetOnDrop(&self.state);
+            // SAFETY: the `MUTATING` state, set while holding the mutex, excludes every other
+            // reader and writer.
+            f(unsafe { &mut *self.value.get() })?;
+            core::mem::forget(reset);
+            self.state.store(MUTATED, Ordering::Release);
+
+            Ok(())
+        })
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// Consumes the cell, returning the wrapped value and whether the cell was mutated.
+    ///
+    /// See [`InPlaceOnceCell::into_raw_parts`](crate::InPlaceOnceCell::into_raw_parts).
+    #[inline]
+    pub fn into_raw_parts(mut self) -> (T, bool) {
+        let mutated = *self.state.get_mut() == MUTATED;
+        (self.into_inner(), mutated)
+    }
+
+    /// Reassembles a cell from the parts returned by
+    /// [`InPlaceOnceRawMutexLock::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for
+    /// [`InPlaceOnceCell::from_raw_parts`](crate::InPlaceOnceCell::from_raw_parts).
+    #[inline]
+    #[must_use]
+    pub const unsafe fn from_raw_parts(value: T, mutated: bool) -> Self {
+        let mut lock = Self::new(value);
+        if mutated {
+            lock.state = AtomicU8::new(MUTATED);
+        }
+
+        lock
+    }
+}
+
+/// Returns the cell to the untouched state if the mutator fails or unwinds.
+struct ResetOnDrop<'a>(&'a AtomicU8);
+
+impl Drop for ResetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(UNTOUCHED, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to exactly one mutator and `&T` afterwards, as
+// long as the mutex excludes other threads.
+unsafe impl<M: RawMutex + Sync, T: Send + Sync> Sync for InPlaceOnceRawMutexLock<M, T> {}
+
+impl<M: RawMutex, T: Default> Default for InPlaceOnceRawMutexLock<M, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<M: RawMutex, T: fmt::Debug> fmt::Debug for InPlaceOnceRawMutexLock<M, T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is untouched, being mutated, or
+    /// mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            MUTATING => LockState::Mutating,
+            _ => LockState::Mutated,
+        };
+        debug::fmt_cell(f, "InPlaceOnceRawMutexLock", state, self.get(), |_| {})
+    }
+}
+
+impl<M: RawMutex, T: PartialEq> PartialEq for InPlaceOnceRawMutexLock<M, T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<M: RawMutex, T: Eq> Eq for InPlaceOnceRawMutexLock<M, T> {}
+
+impl<M: RawMutex, T> From<T> for InPlaceOnceRawMutexLock<M, T> {
+    /// Creates a new `InPlaceOnceRawMutexLock<M, T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
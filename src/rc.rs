@@ -0,0 +1,123 @@
+use core::fmt;
+use core::ops::Deref;
+
+use alloc::rc::Rc;
+
+use crate::InPlaceOnceCell;
+
+/// A single-threaded shared pointer to a cell that can only be mutated once.
+///
+/// This is the `Rc` counterpart of [`ArcOnce`](crate::ArcOnce), for object graphs that stay on
+/// one thread, e.g. GUI trees or interpreters, where several parents share a child that is
+/// finalized lazily, exactly once, without paying for atomics. It dereferences to the
+/// [`InPlaceOnceCell`].
+///
+/// Like `Rc`, its own functions are associated functions, e.g. `RcOnce::get_mut(&mut this)`, so
+/// that they never shadow the methods of the cell.
+pub struct RcOnce<T> {
+    inner: Rc<InPlaceOnceCell<T>>,
+}
+
+impl<T> RcOnce<T> {
+    /// Creates a new shared cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Rc::new(InPlaceOnceCell::new(value)),
+        }
+    }
+
+    /// Returns a mutable reference to the cell, if `this` is its only owner, like
+    /// [`Rc::get_mut`].
+    ///
+    /// With it, the value can be changed again once mutated.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut InPlaceOnceCell<T>> {
+        Rc::get_mut(&mut this.inner)
+    }
+
+    /// Returns the cell, if `this` is its only owner, or `this` otherwise, like
+    /// [`Rc::try_unwrap`].
+    #[inline]
+    pub fn try_unwrap(this: Self) -> Result<InPlaceOnceCell<T>, Self> {
+        Rc::try_unwrap(this.inner).map_err(|inner| Self { inner })
+    }
+
+    /// Returns the cell, if `this` is its last owner, like [`Rc::into_inner`].
+    ///
+    /// If every owner calls this, exactly one of them gets the cell.
+    #[inline]
+    pub fn into_inner(this: Self) -> Option<InPlaceOnceCell<T>> {
+        Rc::into_inner(this.inner)
+    }
+
+    /// Returns the number of owners of the cell, like [`Rc::strong_count`].
+    #[inline]
+    #[must_use]
+    pub fn strong_count(this: &Self) -> usize {
+        Rc::strong_count(&this.inner)
+    }
+
+    /// Returns whether both point to the same cell, like [`Rc::ptr_eq`].
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Rc::ptr_eq(&this.inner, &other.inner)
+    }
+}
+
+impl<T> Clone for RcOnce<T> {
+    /// Makes another owner of the same cell.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> Deref for RcOnce<T> {
+    type Target = InPlaceOnceCell<T>;
+
+    #[inline]
+    fn deref(&self) -> &InPlaceOnceCell<T> {
+        &self.inner
+    }
+}
+
+impl<T: Default> Default for RcOnce<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcOnce<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.inner, f)
+    }
+}
+
+impl<T> From<T> for RcOnce<T> {
+    /// Creates a new `RcOnce<T>` containing `value`. This new cell is not yet mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> From<Rc<InPlaceOnceCell<T>>> for RcOnce<T> {
+    /// Wraps a cell that is already shared.
+    #[inline]
+    fn from(inner: Rc<InPlaceOnceCell<T>>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T> From<RcOnce<T>> for Rc<InPlaceOnceCell<T>> {
+    #[inline]
+    fn from(rc: RcOnce<T>) -> Self {
+        rc.inner
+    }
+}
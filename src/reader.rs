@@ -0,0 +1,123 @@
+use std::io::{self, Read};
+use std::vec::Vec;
+
+#[cfg(feature = "async")]
+use crate::AsyncInPlaceOnceLock;
+use crate::{InPlaceOnceCell, InPlaceOnceLock};
+
+/// Truncates a buffer back to its original length when dropped, so that a read that fails,
+/// panics or is cancelled leaves the placeholder as it was.
+struct Rollback<'a> {
+    buf: &'a mut Vec<u8>,
+    len: usize,
+}
+
+impl<'a> Rollback<'a> {
+    fn new(buf: &'a mut Vec<u8>) -> Self {
+        let len = buf.len();
+        Self { buf, len }
+    }
+
+    /// Keeps everything that was read.
+    fn commit(mut self) {
+        self.len = self.buf.len();
+    }
+}
+
+impl Drop for Rollback<'_> {
+    fn drop(&mut self) {
+        self.buf.truncate(self.len);
+    }
+}
+
+/// Appends everything `reader` yields to `buf`, or leaves `buf` untouched on error.
+fn read_appending(buf: &mut Vec<u8>, mut reader: impl Read) -> io::Result<()> {
+    let rollback = Rollback::new(buf);
+    reader.read_to_end(rollback.buf)?;
+    rollback.commit();
+    Ok(())
+}
+
+impl InPlaceOnceCell<Vec<u8>> {
+    /// Gets the contents of the cell, appending everything `reader` yields to the placeholder if
+    /// the cell was never mutated, e.g. to load an asset file on first use.
+    ///
+    /// Partial and interrupted reads are retried until the end of the input, as with
+    /// [`Read::read_to_end`], and the placeholder's capacity is used first. Returns the error,
+    /// and leaves the placeholder as it was, if reading fails.
+    pub fn get_or_mutate_from_reader(&self, reader: impl Read) -> io::Result<&Vec<u8>> {
+        self.get_or_try_mutate(|buf: &mut Vec<u8>| read_appending(buf, reader))
+    }
+}
+
+impl InPlaceOnceLock<Vec<u8>> {
+    /// Gets the contents of the lock, appending everything `reader` yields to the placeholder if
+    /// the lock was never mutated.
+    ///
+    /// See [`InPlaceOnceCell::get_or_mutate_from_reader`]. `reader` is only read by the caller
+    /// that mutates the lock.
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate_from_reader(&self, reader: impl Read) -> io::Result<&Vec<u8>> {
+        self.get_or_try_mutate(|buf: &mut Vec<u8>| read_appending(buf, reader))
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncInPlaceOnceLock<Vec<u8>> {
+    /// Gets the contents of the lock, appending everything the `futures` `AsyncRead` `reader`
+    /// yields to the placeholder if the lock was never mutated.
+    ///
+    /// Like [`InPlaceOnceCell::get_or_mutate_from_reader`], partial and interrupted reads are
+    /// retried, and the placeholder is left as it was if reading fails. The same holds if the
+    /// future is dropped while reading, in which case the next queued mutator retries.
+    pub async fn get_or_mutate_from_async_reader<R>(&self, reader: R) -> io::Result<&Vec<u8>>
+    where
+        R: futures_io::AsyncRead + Unpin,
+    {
+        self.get_or_try_mutate(async |buf: &mut Vec<u8>| read_appending_async(buf, reader).await)
+            .await
+    }
+}
+
+/// The number of bytes to make room for when the buffer is full.
+#[cfg(feature = "async")]
+const CHUNK: usize = 8 * 1024;
+
+/// Appends everything `reader` yields to `buf`, or leaves `buf` untouched on error or
+/// cancellation.
+#[cfg(feature = "async")]
+async fn read_appending_async<R>(buf: &mut Vec<u8>, mut reader: R) -> io::Result<()>
+where
+    R: futures_io::AsyncRead + Unpin,
+{
+    use std::pin::Pin;
+    use std::task::Poll;
+
+    let rollback = Rollback::new(buf);
+    std::future::poll_fn(|cx| {
+        let buf = &mut *rollback.buf;
+        loop {
+            let len = buf.len();
+            if buf.capacity() == len {
+                buf.reserve(CHUNK);
+            }
+            buf.resize(buf.capacity(), 0);
+            let res = Pin::new(&mut reader).poll_read(cx, &mut buf[len..]);
+            let read = match res {
+                Poll::Ready(Ok(read)) => read,
+                _ => 0,
+            };
+            buf.truncate(len + read);
+            match res {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Ok(())),
+                Poll::Ready(Ok(..)) => {}
+                Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    })
+    .await?;
+    rollback.commit();
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::debug::{self, LockState};
+
+/// A cell that a fixed number of parties each fold a contribution into, in place, and that is
+/// frozen once they all have.
+///
+/// Each of the `parties` calls [`ReduceOnceLock::contribute`] once with a closure that folds its
+/// piece into the value, under a lock. After the last contribution, or an early call to
+/// [`ReduceOnceLock::finalize`], the value is frozen: it is never changed again, later
+/// contributions are turned away, and it is read lock-free with [`ReduceOnceLock::get`]. This
+/// replaces a `Mutex`, a countdown and a separate `OnceLock` for scatter-gather aggregation.
+pub struct ReduceOnceLock<T> {
+    value: UnsafeCell<T>,
+    /// Whether the value is frozen. Stored with `Release` while holding `remaining`, and loaded
+    /// with `Acquire` before reading the value without it.
+    frozen: AtomicBool,
+    /// The number of contributions still expected. Holding it is what allows folding a
+    /// contribution into the value.
+    remaining: Mutex<usize>,
+    frozen_event: Condvar,
+}
+
+impl<T> ReduceOnceLock<T> {
+    /// Creates a new cell holding `placeholder`, which is frozen after `parties` contributions,
+    /// or right away if `parties` is 0.
+    #[inline]
+    #[must_use]
+    pub const fn new(parties: usize, placeholder: T) -> Self {
+        Self {
+            value: UnsafeCell::new(placeholder),
+            frozen: AtomicBool::new(parties == 0),
+            remaining: Mutex::new(parties),
+            frozen_event: Condvar::new(),
+        }
+    }
+
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        self.remaining
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns whether the value is frozen.
+    #[inline]
+    #[must_use]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of contributions still expected before the value is frozen.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        if self.is_frozen() { 0 } else { *self.lock() }
+    }
+
+    /// Gets the reference to the frozen value.
+    ///
+    /// Returns `None` if the value is not frozen yet.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_frozen() {
+            // SAFETY: the value is frozen, so it is never changed again.
+            Some(unsafe { &*self.value.get() })
+        } else {
+            None
+        }
+    }
+
+    /// Folds a contribution into the value with `f`, freezing it if this was the last one.
+    ///
+    /// Returns `false`, without running `f`, if the value is already frozen.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated to the caller, and the contribution does not count;
+    /// the next one folds into whatever `f` left behind.
+    pub fn contribute<F>(&self, f: F) -> bool
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut remaining = self.lock();
+        if self.frozen.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        // SAFETY: the value is not frozen, so nobody reads it, and holding `remaining` excludes
+        // every other contributor.
+        f(unsafe { &mut *self.value.get() });
+        *remaining -= 1;
+        if *remaining == 0 {
+            self.freeze(remaining);
+        }
+
+        true
+    }
+
+    /// Freezes the value without waiting for the remaining contributions, and returns it.
+    pub fn finalize(&self) -> &T {
+        let remaining = self.lock();
+        if !self.frozen.load(Ordering::Relaxed) {
+            self.freeze(remaining);
+        }
+
+        // SAFETY: the value is frozen.
+        unsafe { &*self.value.get() }
+    }
+
+    fn freeze(&self, mut remaining: MutexGuard<'_, usize>) {
+        *remaining = 0;
+        self.frozen.store(true, Ordering::Release);
+        drop(remaining);
+        self.frozen_event.notify_all();
+    }
+
+    /// Blocks the current thread until the value is frozen, then returns it.
+    pub fn wait(&self) -> &T {
+        if let Some(val) = self.get() {
+            return val;
+        }
+
+        let mut remaining = self.lock();
+        while !self.frozen.load(Ordering::Relaxed) {
+            remaining = self
+                .frozen_event
+                .wait(remaining)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+
+        // SAFETY: the value is frozen.
+        unsafe { &*self.value.get() }
+    }
+
+    /// Consumes the cell, returning the wrapped value, whether or not it was frozen.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to one contributor at a time, from any thread,
+// and `&T` once the value is frozen.
+unsafe impl<T: Send + Sync> Sync for ReduceOnceLock<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for ReduceOnceLock<T> {
+    /// Prints the value once it is frozen. The alternate form (`{:#?}`) also prints how many
+    /// contributions are still expected.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.is_frozen() {
+            LockState::Mutated
+        } else {
+            LockState::Untouched
+        };
+        debug::fmt_cell(f, "ReduceOnceLock", state, self.get(), |d| {
+            d.field("remaining", &self.remaining());
+        })
+    }
+}
@@ -0,0 +1,68 @@
+//! An opt-in registry of the statics declared with
+//! [`in_place_lazy_static!`](crate::in_place_lazy_static), for diagnostics.
+//!
+//! Every such static registers itself at link time, so [`dump_states`] reports it even if it was
+//! never touched.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::LockState;
+
+/// A static registered by [`in_place_lazy_static!`](crate::in_place_lazy_static).
+#[doc(hidden)]
+pub struct Registration {
+    name: &'static str,
+    module: &'static str,
+    state: fn() -> LockState,
+}
+
+impl Registration {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, module: &'static str, state: fn() -> LockState) -> Self {
+        Self {
+            name,
+            module,
+            state,
+        }
+    }
+}
+
+inventory::collect!(Registration);
+
+/// The state of a registered static, returned by [`dump_states`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RegisteredState {
+    /// The name of the static.
+    pub name: &'static str,
+    /// The path of the module declaring the static.
+    pub module: &'static str,
+    /// The state of the static's lock.
+    pub state: LockState,
+}
+
+impl fmt::Display for RegisteredState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}: {:?}", self.module, self.name, self.state)
+    }
+}
+
+/// Returns the state of every registered static, sorted by path.
+///
+/// This tells which lazily initialized statics actually ran, e.g. during incident response.
+pub fn dump_states() -> Vec<RegisteredState> {
+    let mut states: Vec<_> = inventory::iter::<Registration>
+        .into_iter()
+        .map(|r| RegisteredState {
+            name: r.name,
+            module: r.module,
+            state: (r.state)(),
+        })
+        .collect();
+    states.sort_unstable_by_key(|s| (s.module, s.name));
+    states
+}
+
+#[doc(hidden)]
+pub use inventory::submit as __submit;
@@ -0,0 +1,280 @@
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::mem::{self, offset_of};
+use core::ptr;
+
+use crate::debug::{self, LockState};
+
+/// A cell that can only be mutated once, with a stable `#[repr(C)]` layout.
+///
+/// This is [`InPlaceOnceCell`](crate::InPlaceOnceCell) for values shared with C and other
+/// languages. Its layout is guaranteed to be that of the following C struct:
+///
+/// ```c
+/// struct ReprCOnceCell_T {
+///     uint8_t state; /* `UNTOUCHED` (0), `MUTATED` (1) or `MUTATING` (2) */
+///     T value;       /* at the next offset aligned for `T` */
+/// };
+/// ```
+///
+/// Foreign code may read `value` once `state` is [`ReprCOnceCell::MUTATED`], and must not write
+/// to either field while Rust holds a reference to the cell. The state is only
+/// [`ReprCOnceCell::MUTATING`] while a mutator runs, during which foreign code must not touch the
+/// value at all.
+#[repr(C)]
+pub struct ReprCOnceCell<T> {
+    state: Cell<u8>,
+    value: UnsafeCell<T>,
+}
+
+const _: () = {
+    assert!(offset_of!(ReprCOnceCell<u64>, state) == 0);
+    assert!(offset_of!(ReprCOnceCell<u64>, value) == align_of::<u64>());
+    assert!(size_of::<ReprCOnceCell<u8>>() == 2);
+    assert!(align_of::<ReprCOnceCell<u64>>() == align_of::<u64>());
+};
+
+impl<T> ReprCOnceCell<T> {
+    /// The value of the state byte before the cell is mutated, and after a mutator fails.
+    pub const UNTOUCHED: u8 = 0;
+    /// The value of the state byte once the cell is mutated.
+    pub const MUTATED: u8 = 1;
+    /// The value of the state byte while a mutator runs, which catches a mutator that mutates
+    /// the cell itself. It never outlives the call to `get_or_mutate`.
+    pub const MUTATING: u8 = 2;
+
+    /// The offset of the state byte, which is always 0.
+    pub const STATE_OFFSET: usize = offset_of!(Self, state);
+    /// The offset of the value.
+    pub const VALUE_OFFSET: usize = offset_of!(Self, value);
+
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: Cell::new(Self::UNTOUCHED),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.get() == Self::MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if self.is_mutated() {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` mutates the cell itself.
+    ///
+    /// If `f` panics, the panic is propagated to the caller, and the cell is never fully mutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` mutates the cell itself.
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+
+        self.try_mutate(f)?;
+        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
+        Ok(unsafe { self.get_unchecked() })
+    }
+
+    #[cold]
+    fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        assert!(
+            self.state.get() == Self::UNTOUCHED,
+            "reentrant mutation of a `ReprCOnceCell`"
+        );
+        self.state.set(Self::MUTATING);
+        let reset = ResetOnDrop(&self.state);
+        // SAFETY: the cell is not mutated, so no shared reference to the value exists, and the
+        // `MUTATING` state keeps a reentrant call from taking a second mutable one.
+        f(unsafe { &mut *self.value.get() })?;
+        mem::forget(reset);
+        self.state.set(Self::MUTATED);
+
+        Ok(())
+    }
+
+    /// Returns a raw pointer to the cell, suitable for passing to foreign code.
+    #[inline]
+    pub const fn as_ptr(&self) -> *const Self {
+        self
+    }
+
+    /// Returns a raw pointer to the state byte of the cell at `this`.
+    ///
+    /// No reference to the cell is created, so the returned pointer keeps the provenance of
+    /// `this`, and may be written through if `this` may be.
+    ///
+    /// # Safety
+    ///
+    /// `this` must point to memory that is in bounds of a single allocation and large enough
+    /// for a `ReprCOnceCell<T>`. It does not need to hold a valid cell.
+    #[inline]
+    pub const unsafe fn state_ptr(this: *const Self) -> *mut u8 {
+        // SAFETY: `this` is in bounds, as guaranteed by the caller.
+        unsafe { (&raw const (*this).state).cast_mut().cast() }
+    }
+
+    /// Returns a raw pointer to the value of the cell at `this`.
+    ///
+    /// No reference to the cell is created, so the returned pointer keeps the provenance of
+    /// `this`, and may be written through if `this` may be.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`ReprCOnceCell::state_ptr`].
+    #[inline]
+    pub const unsafe fn value_ptr(this: *const Self) -> *mut T {
+        // SAFETY: `this` is in bounds, as guaranteed by the caller.
+        UnsafeCell::raw_get(unsafe { &raw const (*this).value })
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub const fn into_inner(self) -> T {
+        // SAFETY: `self` is forgotten right after, so the value is only moved out once, and the
+        // state needs no drop.
+        let value = unsafe { ptr::read(&self.value) };
+        mem::forget(self);
+        value.into_inner()
+    }
+
+    /// Consumes the cell, returning the wrapped value and whether the cell was mutated.
+    ///
+    /// See [`InPlaceOnceCell::into_raw_parts`](crate::InPlaceOnceCell::into_raw_parts).
+    #[inline]
+    pub fn into_raw_parts(self) -> (T, bool) {
+        let mutated = self.is_mutated();
+        (self.value.into_inner(), mutated)
+    }
+
+    /// Reassembles a cell from the parts returned by [`ReprCOnceCell::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for
+    /// [`InPlaceOnceCell::from_raw_parts`](crate::InPlaceOnceCell::from_raw_parts).
+    #[inline]
+    #[must_use]
+    pub const unsafe fn from_raw_parts(value: T, mutated: bool) -> Self {
+        Self {
+            state: Cell::new(if mutated {
+                Self::MUTATED
+            } else {
+                Self::UNTOUCHED
+            }),
+            value: UnsafeCell::new(value),
+        }
+    }
+}
+
+/// Returns a cell to its untouched state if its mutator fails or unwinds.
+struct ResetOnDrop<'a>(&'a Cell<u8>);
+
+impl Drop for ResetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.set(ReprCOnceCell::<()>::UNTOUCHED);
+    }
+}
+
+impl<T: Default> Default for ReprCOnceCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReprCOnceCell<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.get() {
+            Self::MUTATED => LockState::Mutated,
+            Self::MUTATING => LockState::Mutating,
+            _ => LockState::Untouched,
+        };
+        debug::fmt_cell(f, "ReprCOnceCell", state, self.get(), |_| {})
+    }
+}
+
+impl<T: PartialEq> PartialEq for ReprCOnceCell<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq> Eq for ReprCOnceCell<T> {}
+
+impl<T> From<T> for ReprCOnceCell<T> {
+    /// Creates a new `ReprCOnceCell<T>` containing `value`. This new cell is not yet mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
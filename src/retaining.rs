@@ -0,0 +1,83 @@
+use core::fmt;
+use core::ops::Deref;
+
+use crate::InPlaceOnceCell;
+
+/// A cell that can only be mutated once, and keeps a copy of the value it started as.
+///
+/// It dereferences to the [`InPlaceOnceCell`] that is mutated, while [`RetainingOnceCell::initial`]
+/// keeps answering what the cell held before, e.g. for migration and audit tooling. The copy
+/// costs a second `T`, which is why this is a separate type.
+pub struct RetainingOnceCell<T> {
+    initial: T,
+    cell: InPlaceOnceCell<T>,
+}
+
+impl<T: Clone> RetainingOnceCell<T> {
+    /// Creates a new cell that has not been mutated, keeping a clone of `value`.
+    #[inline]
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self {
+            initial: value.clone(),
+            cell: InPlaceOnceCell::new(value),
+        }
+    }
+}
+
+impl<T> RetainingOnceCell<T> {
+    /// Returns the value the cell started as, whether or not it was mutated since.
+    #[inline]
+    #[must_use]
+    pub fn initial(&self) -> &T {
+        &self.initial
+    }
+
+    /// Returns the value the cell started as and the value it was mutated to.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    #[must_use]
+    pub fn diff(&self) -> Option<(&T, &T)> {
+        self.cell.get().map(|val| (&self.initial, val))
+    }
+
+    /// Consumes the cell, returning the value it started as, and the cell itself.
+    #[inline]
+    pub fn into_parts(self) -> (T, InPlaceOnceCell<T>) {
+        (self.initial, self.cell)
+    }
+}
+
+impl<T> Deref for RetainingOnceCell<T> {
+    type Target = InPlaceOnceCell<T>;
+
+    #[inline]
+    fn deref(&self) -> &InPlaceOnceCell<T> {
+        &self.cell
+    }
+}
+
+impl<T: Clone + Default> Default for RetainingOnceCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RetainingOnceCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetainingOnceCell")
+            .field("initial", &self.initial)
+            .field("cell", &self.cell)
+            .finish()
+    }
+}
+
+impl<T: Clone> From<T> for RetainingOnceCell<T> {
+    /// Creates a new `RetainingOnceCell<T>` containing `value`. This new cell is not yet mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,39 @@
+use alloc::borrow::Cow;
+use alloc::format;
+
+use ::schemars::{JsonSchema, Schema, SchemaGenerator};
+
+use crate::InPlaceOnceCell;
+use crate::serde::Repr;
+
+/// Describes the serialized form: an object with a single `untouched` or `mutated` property
+/// holding the value.
+impl<T: JsonSchema> JsonSchema for InPlaceOnceCell<T> {
+    fn schema_name() -> Cow<'static, str> {
+        format!("InPlaceOnceCell_{}", T::schema_name()).into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        format!("in_place_once_cell::InPlaceOnceCell<{}>", T::schema_id()).into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        Repr::<T>::json_schema(generator)
+    }
+}
+
+/// Describes the serialized form, which is the same as for [`InPlaceOnceCell`].
+#[cfg(feature = "std")]
+impl<T: JsonSchema> JsonSchema for crate::InPlaceOnceLock<T> {
+    fn schema_name() -> Cow<'static, str> {
+        format!("InPlaceOnceLock_{}", T::schema_name()).into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        format!("in_place_once_cell::InPlaceOnceLock<{}>", T::schema_id()).into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        Repr::<T>::json_schema(generator)
+    }
+}
@@ -0,0 +1,297 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! Cells serialize as their value tagged with whether they were mutated. The tag is an
+//! externally tagged enum, so formats without field names, such as `postcard`, encode it as a
+//! variant index, one byte, and nothing here allocates, so it works with `no_std` serializers.
+//! [`compact`] serializes the tag as a `u8` instead, for self-describing formats.
+//! [`as_option`] and [`as_value_or_default`] drop the tag, for embedding cells in structs whose
+//! format only has room for an optional or plain value.
+
+use core::fmt;
+
+use ::serde::de::{DeserializeSeed, EnumAccess, VariantAccess, Visitor};
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::InPlaceOnceCell;
+
+/// The serialized form of a cell: its value, tagged with whether it was mutated.
+///
+/// In JSON, an untouched cell holding `34` is `{"untouched":34}`, and a mutated one is
+/// `{"mutated":34}`. In `postcard`, they are the variant index `0` or `1`, then the value.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(::schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Repr<T> {
+    Untouched(T),
+    Mutated(T),
+}
+
+impl<T> Repr<T> {
+    fn new(value: T, mutated: bool) -> Self {
+        if mutated {
+            Self::Mutated(value)
+        } else {
+            Self::Untouched(value)
+        }
+    }
+
+    fn into_parts(self) -> (T, bool) {
+        match self {
+            Self::Untouched(value) => (value, false),
+            Self::Mutated(value) => (value, true),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for InPlaceOnceCell<T> {
+    /// Serializes the value along with whether the cell was mutated, as for `Clone`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (value, mutated) = self.peek();
+        Repr::new(value, mutated).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for InPlaceOnceCell<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (value, mutated) = Repr::deserialize(deserializer)?.into_parts();
+        // SAFETY: this grants nothing that mutating an untouched cell to `value` would not, which
+        // is what deserializing a mutated cell amounts to.
+        Ok(unsafe { Self::from_raw_parts(value, mutated) })
+    }
+
+    /// Deserializes into the existing value with `T::deserialize_in_place`, which can reuse what
+    /// it owns, e.g. the buffer of a `Vec`, rather than building a new value to move in.
+    ///
+    /// If this fails, the value is left valid but unspecified, and the cell keeps its flag.
+    fn deserialize_in_place<D: Deserializer<'de>>(
+        deserializer: D,
+        place: &mut Self,
+    ) -> Result<(), D::Error> {
+        // SAFETY: as for `deserialize`.
+        let (value, mutated) = unsafe { place.peek_mut() };
+        *mutated = deserializer.deserialize_enum("Repr", TAGS, InPlace(value))?;
+        Ok(())
+    }
+}
+
+/// The tag of a [`Repr`], for deserializing its value separately.
+#[derive(Deserialize)]
+#[serde(variant_identifier, rename_all = "lowercase")]
+enum Tag {
+    Untouched,
+    Mutated,
+}
+
+const TAGS: &[&str] = &["untouched", "mutated"];
+
+/// Deserializes a [`Repr`] into an existing value, returning whether it was mutated, and serves
+/// as the seed that deserializes the value itself.
+struct InPlace<'a, T>(&'a mut T);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for InPlace<'_, T> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an untouched or mutated value")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<bool, A::Error> {
+        let (tag, value) = data.variant::<Tag>()?;
+        value.newtype_variant_seed(self)?;
+        Ok(matches!(tag, Tag::Mutated))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for InPlace<'_, T> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        T::deserialize_in_place(deserializer, self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Serialize> Serialize for crate::InPlaceOnceLock<T> {
+    /// Serializes the value along with whether the lock was mutated, waiting for an in-flight
+    /// mutation to finish first.
+    ///
+    /// Fails if the lock is poisoned.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.peek(|value, mutated| Repr::new(value, mutated).serialize(serializer))
+            .unwrap_or_else(|| {
+                Err(::serde::ser::Error::custom(
+                    "`InPlaceOnceLock` instance has previously been poisoned",
+                ))
+            })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for crate::InPlaceOnceLock<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (value, mutated) = Repr::deserialize(deserializer)?.into_parts();
+        // SAFETY: this grants nothing that mutating an untouched lock to `value` would not, which
+        // is what deserializing a mutated lock amounts to.
+        Ok(unsafe { Self::from_raw_parts(value, mutated) })
+    }
+}
+
+impl<T: Serialize> Serialize for crate::unchecked::UncheckedOnceCell<T> {
+    /// Serializes the value alone, as there is no flag to serialize.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.peek().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for crate::unchecked::UncheckedOnceCell<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+
+    /// Deserializes into the existing value with `T::deserialize_in_place`.
+    fn deserialize_in_place<D: Deserializer<'de>>(
+        deserializer: D,
+        place: &mut Self,
+    ) -> Result<(), D::Error> {
+        T::deserialize_in_place(deserializer, place.get_mut())
+    }
+}
+
+/// A compact representation of [`InPlaceOnceCell`], for `#[serde(with = "...")]`.
+///
+/// A cell serializes as a pair of a single-byte tag, `0` if it is untouched or `1` if it is
+/// mutated, and the value. In JSON, a mutated cell holding `1156` is `[1,1156]`.
+///
+/// ```
+/// # use in_place_once_cell::InPlaceOnceCell;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Config {
+///     #[serde(with = "in_place_once_cell::serde::compact")]
+///     retries: InPlaceOnceCell<u32>,
+/// }
+/// ```
+pub mod compact {
+    use ::serde::de::{Error, Unexpected};
+    use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::InPlaceOnceCell;
+
+    /// Serializes `cell` as its tag and value.
+    pub fn serialize<T, S>(cell: &InPlaceOnceCell<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        let (value, mutated) = cell.peek();
+        (u8::from(mutated), value).serialize(serializer)
+    }
+
+    /// Deserializes a cell from its tag and value.
+    ///
+    /// Fails if the tag is neither `0` nor `1`.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<InPlaceOnceCell<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let (tag, value) = <(u8, T)>::deserialize(deserializer)?;
+        let mutated = match tag {
+            0 => false,
+            1 => true,
+            _ => {
+                return Err(D::Error::invalid_value(
+                    Unexpected::Unsigned(tag.into()),
+                    &"0 or 1",
+                ));
+            }
+        };
+        // SAFETY: as for `InPlaceOnceCell::deserialize`.
+        Ok(unsafe { InPlaceOnceCell::from_raw_parts(value, mutated) })
+    }
+}
+
+/// Maps an [`InPlaceOnceCell`] to an `Option`, for `#[serde(with = "...")]`: an untouched cell
+/// serializes as `None`, and a mutated one as `Some(value)`.
+///
+/// The placeholder of an untouched cell is not serialized, so `None` deserializes as an
+/// untouched cell holding `T::default()`. Add `#[serde(default)]` to accept a missing field too.
+///
+/// ```
+/// # use in_place_once_cell::InPlaceOnceCell;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Config {
+///     #[serde(default, with = "in_place_once_cell::serde::as_option")]
+///     retries: InPlaceOnceCell<u32>,
+/// }
+/// ```
+pub mod as_option {
+    use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::InPlaceOnceCell;
+
+    /// Serializes `cell` as `Some(value)` if it is mutated, or `None` otherwise.
+    pub fn serialize<T, S>(cell: &InPlaceOnceCell<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        cell.get().serialize(serializer)
+    }
+
+    /// Deserializes `Some(value)` as a cell mutated to `value`, and `None` as an untouched cell
+    /// holding `T::default()`.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<InPlaceOnceCell<T>, D::Error>
+    where
+        T: Deserialize<'de> + Default,
+        D: Deserializer<'de>,
+    {
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            // SAFETY: as for `InPlaceOnceCell::deserialize`.
+            Some(value) => unsafe { InPlaceOnceCell::from_raw_parts(value, true) },
+            None => InPlaceOnceCell::default(),
+        })
+    }
+}
+
+/// Maps an [`InPlaceOnceCell`] to its bare value, for `#[serde(with = "...")]`: a mutated cell
+/// serializes as its value, and an untouched one as `T::default()`.
+///
+/// Every value deserializes as a mutated cell. Add `#[serde(default)]` to deserialize a missing
+/// field as an untouched cell holding `T::default()`.
+///
+/// ```
+/// # use in_place_once_cell::InPlaceOnceCell;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Config {
+///     #[serde(default, with = "in_place_once_cell::serde::as_value_or_default")]
+///     retries: InPlaceOnceCell<u32>,
+/// }
+/// ```
+pub mod as_value_or_default {
+    use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::InPlaceOnceCell;
+
+    /// Serializes the value of `cell` if it is mutated, or `T::default()` otherwise.
+    pub fn serialize<T, S>(cell: &InPlaceOnceCell<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize + Default,
+        S: Serializer,
+    {
+        match cell.get() {
+            Some(value) => value.serialize(serializer),
+            None => T::default().serialize(serializer),
+        }
+    }
+
+    /// Deserializes a value as a cell mutated to it.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<InPlaceOnceCell<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let value = T::deserialize(deserializer)?;
+        // SAFETY: as for `InPlaceOnceCell::deserialize`.
+        Ok(unsafe { InPlaceOnceCell::from_raw_parts(value, true) })
+    }
+}
@@ -0,0 +1,280 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::{self, offset_of};
+use core::ptr;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::debug::{self, LockState};
+
+const UNTOUCHED: u32 = 0;
+const MUTATING: u32 = 1;
+const MUTATED: u32 = 2;
+
+/// A cell that can only be mutated once, designed to live in memory shared between processes.
+///
+/// The layout is `#[repr(C)]`: a 32-bit state word at offset 0, followed by the value. All of the
+/// lock's state lives in that word, so it works from any mapping of the same memory, in any
+/// process, at any address. A zeroed state word is the untouched state, so freshly mapped zeroed
+/// memory is a valid untouched lock if zeroed memory is a valid `T`.
+///
+/// # Protocol
+///
+/// Every process follows the same protocol on the state word:
+///
+/// 1. If the state is `MUTATED` (2), loaded with `Acquire`, the value may be read.
+/// 2. Otherwise, a process claims the lock by compare-and-swapping the state from `UNTOUCHED` (0)
+///    to `MUTATING` (1) with `Acquire`, mutates the value in place, then stores `MUTATED` with
+///    `Release`. If the mutator fails or panics, it stores `UNTOUCHED` instead, and another
+///    process may claim the lock.
+/// 3. Processes that fail to claim the lock spin, yielding to the scheduler, until the state is
+///    no longer `MUTATING`, then start over.
+///
+/// If a process dies while mutating, the state stays `MUTATING` and every other process waits
+/// forever; see [`SharedInPlaceOnceLock::reset_abandoned`].
+///
+/// `T` should not contain pointers, since they are only meaningful in the process that wrote
+/// them.
+#[repr(C)]
+pub struct SharedInPlaceOnceLock<T> {
+    state: AtomicU32,
+    value: UnsafeCell<T>,
+}
+
+const _: () = {
+    assert!(offset_of!(SharedInPlaceOnceLock<u64>, state) == 0);
+    assert!(offset_of!(SharedInPlaceOnceLock<u64>, value) == 8);
+    assert!(offset_of!(SharedInPlaceOnceLock<u8>, value) == 4);
+};
+
+impl<T> SharedInPlaceOnceLock<T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: AtomicU32::new(UNTOUCHED),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Gets a reference to a lock living in shared memory.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid and aligned for `'a`, and point to a lock that was either written with
+    /// [`SharedInPlaceOnceLock::new`] or is all zeroes with all zeroes being a valid `T`. Every
+    /// process accessing the memory must follow the protocol described on the type.
+    #[inline]
+    pub const unsafe fn from_ptr<'a>(ptr: *const Self) -> &'a Self {
+        unsafe { &*ptr }
+    }
+
+    /// Returns a raw pointer to the state word of the lock at `ptr`.
+    ///
+    /// No reference to the lock is created, so the returned pointer keeps the provenance of
+    /// `ptr`, and may be written through if `ptr` may be. Use it with
+    /// [`SharedInPlaceOnceLock::value_ptr`] to lay out a lock in memory that does not hold one
+    /// yet, e.g. a fresh mapping that is not zeroed.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to memory that is in bounds of a single allocation and large enough for
+    /// a `SharedInPlaceOnceLock<T>`. It does not need to hold a valid lock.
+    #[inline]
+    pub const unsafe fn state_ptr(ptr: *const Self) -> *mut AtomicU32 {
+        // SAFETY: `ptr` is in bounds, as guaranteed by the caller.
+        unsafe { (&raw const (*ptr).state).cast_mut() }
+    }
+
+    /// Returns a raw pointer to the value of the lock at `ptr`.
+    ///
+    /// No reference to the lock is created, so the returned pointer keeps the provenance of
+    /// `ptr`, and may be written through if `ptr` may be.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`SharedInPlaceOnceLock::state_ptr`].
+    #[inline]
+    pub const unsafe fn value_ptr(ptr: *const Self) -> *mut T {
+        // SAFETY: `ptr` is in bounds, as guaranteed by the caller.
+        UnsafeCell::raw_get(unsafe { &raw const (*ptr).value })
+    }
+
+    #[inline]
+    #[must_use]
+    fn is_mutated(&self) -> bool {
+        self.state.load(Ordering::Acquire) == MUTATED
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(self.is_mutated());
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.is_mutated() {
+            // SAFETY: `self.is_mutated() == true`
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == MUTATED {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated, in this
+    /// or any other process.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated to the caller, the cell stays unmutated, and another
+    /// caller may retry.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(|val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell, mutating it with `f` if the cell was never mutated, in this
+    /// or any other process.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated.
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+
+        self.try_mutate(f)?;
+        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
+        Ok(unsafe { self.get_unchecked() })
+    }
+
+    #[cold]
+    fn try_mutate<F, E>(&self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        loop {
+            match self.state.compare_exchange_weak(
+                UNTOUCHED,
+                MUTATING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(..) => break,
+                Err(MUTATED) => return Ok(()),
+                Err(..) => backoff(),
+            }
+        }
+
+        let reset = ResetOnDrop(&self.state);
+        // SAFETY: the `MUTATING` state excludes every other reader and writer, in every process.
+        f(unsafe { &mut *self.value.get() })?;
+        core::mem::forget(reset);
+        self.state.store(MUTATED, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Returns the cell to the untouched state if it is stuck mutating, returning whether it was.
+    ///
+    /// # Safety
+    ///
+    /// The process that was mutating the cell must have died, so nothing is touching the value.
+    /// The next mutator runs on whatever the dead one left behind.
+    pub unsafe fn reset_abandoned(&self) -> bool {
+        self.state
+            .compare_exchange(MUTATING, UNTOUCHED, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub const fn into_inner(self) -> T {
+        // SAFETY: `self` is forgotten right after, so the value is only moved out once, and the
+        // state needs no drop.
+        let value = unsafe { ptr::read(&self.value) };
+        mem::forget(self);
+        value.into_inner()
+    }
+}
+
+/// Waits for another mutator, possibly in another process, to make progress.
+#[inline]
+fn backoff() {
+    #[cfg(feature = "std")]
+    std::thread::yield_now();
+    #[cfg(not(feature = "std"))]
+    core::hint::spin_loop();
+}
+
+/// Returns the cell to the untouched state if the mutator fails or unwinds.
+struct ResetOnDrop<'a>(&'a AtomicU32);
+
+impl Drop for ResetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(UNTOUCHED, Ordering::Release);
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to exactly one mutator and `&T` afterwards.
+unsafe impl<T: Send + Sync> Sync for SharedInPlaceOnceLock<T> {}
+
+impl<T: Default> Default for SharedInPlaceOnceLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedInPlaceOnceLock<T> {
+    /// The alternate form (`{:#?}`) also prints whether the cell is untouched, being mutated, or
+    /// mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state.load(Ordering::Acquire) {
+            UNTOUCHED => LockState::Untouched,
+            MUTATING => LockState::Mutating,
+            _ => LockState::Mutated,
+        };
+        debug::fmt_cell(f, "SharedInPlaceOnceLock", state, self.get(), |_| {})
+    }
+}
+
+impl<T> From<T> for SharedInPlaceOnceLock<T> {
+    /// Creates a new `SharedInPlaceOnceLock<T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
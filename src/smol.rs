@@ -0,0 +1,32 @@
+use crate::AsyncInPlaceOnceLock;
+
+impl<T> AsyncInPlaceOnceLock<T> {
+    /// Gets the contents of the cell, running the synchronous mutator `f` on the `blocking`
+    /// thread pool used by smol if the cell was never mutated.
+    ///
+    /// Use this for CPU-heavy mutators that would otherwise stall the executor.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated to the caller and the cell stays unmutated.
+    ///
+    /// # Cancellation
+    ///
+    /// Once `f` has started running on the thread pool it runs to completion and publishes the
+    /// mutation even if the returned future is dropped.
+    pub async fn unblock_get_or_mutate<F>(&'static self, f: F) -> &'static T
+    where
+        T: Send + Sync,
+        F: FnOnce(&mut T) + Send + 'static,
+    {
+        let Some(mut mutation) = self.begin_mutation().await else {
+            return self.get().unwrap();
+        };
+
+        blocking::unblock(move || {
+            f(mutation.value());
+            mutation.publish()
+        })
+        .await
+    }
+}
@@ -0,0 +1,649 @@
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{Index, IndexMut, Range};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::debug::Value;
+
+const BITS: usize = usize::BITS as usize;
+
+/// A fixed-size collection of cells that can each be mutated once, stored as a struct of arrays.
+///
+/// Where `[InPlaceOnceCell<T>; N]` interleaves each value with its flag, this keeps every value
+/// in one dense array and every flag in one contiguous bitset. Checking many flags then touches
+/// a word per 64 slots, and iterating the mutated values reads them back to back, which suits
+/// scans over large tables.
+///
+/// This also serves as a column of entity-component storage, indexed by entity id:
+/// [`SoaInPlaceOnceCells::chunks_mut`] partitions it into disjoint chunks that can be mutated in
+/// parallel, e.g. one per worker thread.
+#[doc(alias = "OnceColumn")]
+pub struct SoaInPlaceOnceCells<T> {
+    values: Box<[UnsafeCell<T>]>,
+    mutated: Box<[Cell<usize>]>,
+    /// The cells whose mutator is running, to catch a mutator that mutates its own cell.
+    mutating: Box<[Cell<usize>]>,
+}
+
+impl<T> SoaInPlaceOnceCells<T> {
+    /// Creates a collection of cells that have not been mutated, one for each of `values`.
+    #[must_use]
+    pub fn new(values: impl Into<Vec<T>>) -> Self {
+        let values: Box<[UnsafeCell<T>]> = values.into().into_iter().map(UnsafeCell::new).collect();
+        let words = || {
+            (0..values.len().div_ceil(BITS))
+                .map(|_| Cell::new(0))
+                .collect()
+        };
+        Self {
+            mutated: words(),
+            mutating: words(),
+            values,
+        }
+    }
+
+    /// Creates a collection of `len` cells that have not been mutated, the one at `i` containing
+    /// `f(i)`.
+    #[must_use]
+    pub fn from_fn(len: usize, f: impl FnMut(usize) -> T) -> Self {
+        Self::new((0..len).map(f).collect::<Vec<_>>())
+    }
+
+    /// Returns the number of cells.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether there are no cells.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns whether the cell at `index` was mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn is_mutated(&self, index: usize) -> bool {
+        assert!(index < self.len(), "index out of bounds");
+        self.mutated[index / BITS].get() & (1 << (index % BITS)) != 0
+    }
+
+    /// Returns the number of mutated cells.
+    #[must_use]
+    pub fn count_mutated(&self) -> usize {
+        self.mutated
+            .iter()
+            .map(|word| word.get().count_ones() as usize)
+            .sum()
+    }
+
+    /// # Safety
+    ///
+    /// The cell at `index` must be mutated.
+    #[inline]
+    unsafe fn get_unchecked(&self, index: usize) -> &T {
+        debug_assert!(self.is_mutated(index));
+        unsafe { &*self.values[index].get() }
+    }
+
+    /// Gets the reference to the value of the cell at `index`.
+    ///
+    /// Returns `None` if the cell is not mutated or `index` is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len() && self.is_mutated(index) {
+            // SAFETY: the cell is mutated.
+            Some(unsafe { self.get_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the value of the cell at `index`.
+    ///
+    /// Returns `None` if the cell is not mutated or `index` is out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len() && self.is_mutated(index) {
+            Some(self.values[index].get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets the reference to the value of the cell at `index`, which must be mutated. This is
+    /// what indexing with `cells[index]` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or the cell is not mutated.
+    #[inline]
+    #[track_caller]
+    pub fn mutated(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(val) => val,
+            None => not_mutated(index, self.len()),
+        }
+    }
+
+    /// Gets a mutable reference to the value of the cell at `index`, which must be mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or the cell is not mutated.
+    #[inline]
+    #[track_caller]
+    pub fn mutated_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len();
+        match self.get_mut(index) {
+            Some(val) => val,
+            None => not_mutated(index, len),
+        }
+    }
+
+    /// Gets the contents of the cell at `index`, mutating it with `f(&mut T)` if the cell was
+    /// never mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if `f()` mutates the same cell itself.
+    ///
+    /// If `f()` panics, the panic is propagated to the caller, and the cell is never fully mutated.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, index: usize, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(index, |val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell at `index`, mutating it with `f(&mut T)` if the cell was
+    /// never mutated.
+    ///
+    /// Returns an error if the cell was uninitialized and `f()` returns an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if `f()` mutates the same cell itself.
+    #[inline]
+    pub fn get_or_try_mutate<F, E>(&self, index: usize, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if self.is_mutated(index) {
+            // SAFETY: the cell is mutated.
+            return Ok(unsafe { self.get_unchecked(index) });
+        }
+
+        self.try_mutate(index, f)?;
+        // SAFETY: `try_mutate`, if it does not fail, is guaranteed to make the cell mutated.
+        Ok(unsafe { self.get_unchecked(index) })
+    }
+
+    // It's most likely that the value is already initialized.
+    #[cold]
+    #[inline(never)]
+    fn try_mutate<F, E>(&self, index: usize, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        let bit = 1 << (index % BITS);
+        let mutating = &self.mutating[index / BITS];
+        assert!(
+            mutating.get() & bit == 0,
+            "reentrant mutation of a `SoaInPlaceOnceCells` cell"
+        );
+        mutating.set(mutating.get() | bit);
+        let _mutating = ClearOnDrop {
+            word: mutating,
+            bit,
+        };
+        // SAFETY: the cell is not mutated, so no shared reference to its value was handed out,
+        // and its `mutating` bit keeps a reentrant call from taking a second mutable one.
+        f(unsafe { &mut *self.values[index].get() })?;
+
+        let word = &self.mutated[index / BITS];
+        word.set(word.get() | bit);
+
+        Ok(())
+    }
+
+    /// Returns an iterator over `(index, &value)` for every mutated cell, in index order.
+    ///
+    /// Untouched cells are skipped a word of the bitset at a time.
+    pub fn iter_mutated(&self) -> IterMutated<'_, T> {
+        IterMutated {
+            cells: self,
+            word: 0,
+            bits: self.mutated.first().map_or(0, Cell::get),
+        }
+    }
+
+    /// Splits the collection into disjoint chunks of `chunk_size` cells, the last of which may be
+    /// shorter, so that each can be mutated on its own thread.
+    ///
+    /// Chunks are indexed the same way as the whole collection, so entity ids stay the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0 or not a multiple of `usize::BITS`, which keeps each word of
+    /// the bitset in one chunk.
+    pub fn chunks_mut(&mut self, chunk_size: usize) -> impl Iterator<Item = SoaChunkMut<'_, T>> {
+        assert!(
+            chunk_size != 0 && chunk_size.is_multiple_of(BITS),
+            "chunk size must be a non-zero multiple of `usize::BITS`"
+        );
+
+        self.values
+            .chunks_mut(chunk_size)
+            .zip(self.mutated.chunks_mut(chunk_size / BITS))
+            .enumerate()
+            .map(move |(i, (values, mutated))| SoaChunkMut {
+                values,
+                mutated,
+                start: i * chunk_size,
+            })
+    }
+
+    /// Consumes the collection, returning the wrapped values. Note that this includes the values
+    /// of cells that were never mutated.
+    pub fn into_inner(self) -> Vec<T> {
+        self.values
+            .into_vec()
+            .into_iter()
+            .map(UnsafeCell::into_inner)
+            .collect()
+    }
+
+    /// Consumes the collection, returning the wrapped values if every cell was mutated, or the
+    /// collection itself otherwise.
+    pub fn try_unwrap_all(self) -> Result<Vec<T>, Self> {
+        if self.count_mutated() == self.len() {
+            Ok(self.into_inner())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+/// A chunk of a [`SoaInPlaceOnceCells`], created by [`SoaInPlaceOnceCells::chunks_mut`], which
+/// mutates its cells through a unique borrow.
+pub struct SoaChunkMut<'a, T> {
+    values: &'a mut [UnsafeCell<T>],
+    mutated: &'a mut [Cell<usize>],
+    /// The index of the first cell of the chunk in the whole collection.
+    start: usize,
+}
+
+impl<T> SoaChunkMut<'_, T> {
+    /// Returns the indices of the cells in this chunk.
+    #[inline]
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.values.len()
+    }
+
+    /// Returns the position of `index` in the chunk.
+    #[inline]
+    #[track_caller]
+    fn offset(&self, index: usize) -> usize {
+        assert!(self.range().contains(&index), "index out of chunk bounds");
+        index - self.start
+    }
+
+    /// Returns whether the cell at `index` was mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not in the chunk.
+    #[inline]
+    #[must_use]
+    #[track_caller]
+    pub fn is_mutated(&self, index: usize) -> bool {
+        let offset = self.offset(index);
+        self.mutated[offset / BITS].get() & (1 << (offset % BITS)) != 0
+    }
+
+    /// Gets a mutable reference to the value of the cell at `index`.
+    ///
+    /// Returns `None` if the cell is not mutated or `index` is not in the chunk.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if self.range().contains(&index) && self.is_mutated(index) {
+            Some(self.values[index - self.start].get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the contents of the cell at `index`, mutating it with `f` if
+    /// the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not in the chunk.
+    ///
+    /// If `f()` panics, the panic is propagated to the caller, and the cell is never fully mutated.
+    #[inline]
+    #[track_caller]
+    pub fn get_mut_or_mutate<F>(&mut self, index: usize, f: F) -> &mut T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_mut_or_try_mutate(index, |val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets a mutable reference to the contents of the cell at `index`, mutating it with `f` if
+    /// the cell was never mutated.
+    ///
+    /// Returns an error if the cell was not mutated and `f()` returns an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not in the chunk.
+    #[track_caller]
+    pub fn get_mut_or_try_mutate<F, E>(&mut self, index: usize, f: F) -> Result<&mut T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        let offset = self.offset(index);
+        let word = self.mutated[offset / BITS].get_mut();
+        let bit = 1 << (offset % BITS);
+        let val = self.values[offset].get_mut();
+        if *word & bit == 0 {
+            f(val)?;
+            *word |= bit;
+        }
+
+        Ok(val)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SoaChunkMut<'_, T> {
+    /// Prints the mutated cells as a map from index to value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.range()
+                    .filter(|&index| self.is_mutated(index))
+                    // SAFETY: the chunk is borrowed shared, so no mutable reference is alive.
+                    .map(|index| {
+                        (
+                            index,
+                            Value(unsafe { &*self.values[index - self.start].get() }),
+                        )
+                    }),
+            )
+            .finish()
+    }
+}
+
+/// Clears the `mutating` bit of a cell once its mutator returns or unwinds.
+struct ClearOnDrop<'a> {
+    word: &'a Cell<usize>,
+    bit: usize,
+}
+
+impl Drop for ClearOnDrop<'_> {
+    fn drop(&mut self) {
+        self.word.set(self.word.get() & !self.bit);
+    }
+}
+
+#[cold]
+#[track_caller]
+fn not_mutated(index: usize, len: usize) -> ! {
+    assert!(
+        index < len,
+        "index out of bounds: the len is {len} but the index is {index}"
+    );
+    panic!("the cell at index {index} is not mutated")
+}
+
+/// An iterator over the mutated cells of a [`SoaInPlaceOnceCells`], created by
+/// [`SoaInPlaceOnceCells::iter_mutated`].
+pub struct IterMutated<'a, T> {
+    cells: &'a SoaInPlaceOnceCells<T>,
+    /// The index of the bitset word being scanned.
+    word: usize,
+    /// The bits of that word that have not been yielded yet.
+    bits: usize,
+}
+
+impl<'a, T> Iterator for IterMutated<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.bits == 0 {
+            self.word += 1;
+            self.bits = self.cells.mutated.get(self.word)?.get();
+        }
+
+        let index = self.word * BITS + self.bits.trailing_zeros() as usize;
+        self.bits &= self.bits - 1;
+        // SAFETY: the cell's bit is set, so it is mutated.
+        Some((index, unsafe { self.cells.get_unchecked(index) }))
+    }
+}
+
+impl<T> FusedIterator for IterMutated<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for SoaInPlaceOnceCells<T> {
+    /// Prints the mutated cells as a map from index to value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter_mutated().map(|(i, val)| (i, Value(val))))
+            .finish()
+    }
+}
+
+impl<T> Index<usize> for SoaInPlaceOnceCells<T> {
+    type Output = T;
+
+    /// See [`SoaInPlaceOnceCells::mutated`].
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: usize) -> &T {
+        self.mutated(index)
+    }
+}
+
+impl<T> IndexMut<usize> for SoaInPlaceOnceCells<T> {
+    /// See [`SoaInPlaceOnceCells::mutated_mut`].
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.mutated_mut(index)
+    }
+}
+
+impl<T> From<Vec<T>> for SoaInPlaceOnceCells<T> {
+    /// Creates a new `SoaInPlaceOnceCells<T>` with a cell for each of `values`. These new cells
+    /// are not yet mutated.
+    #[inline]
+    fn from(values: Vec<T>) -> Self {
+        Self::new(values)
+    }
+}
+
+/// A fixed-size collection of cells that can each be mutated once, keeping each payload behind a
+/// box.
+///
+/// The flags stay inline in one bitset, as in [`SoaInPlaceOnceCells`], so scanning them stays
+/// cheap, but a cell takes a single pointer until it is mutated: untouched cells share one
+/// placeholder, which is cloned into a new box when a cell is mutated. This suits huge tables of
+/// a large `T` that are rarely mutated.
+pub struct BoxedSoaInPlaceOnceCells<T> {
+    cells: SoaInPlaceOnceCells<Option<Box<T>>>,
+    placeholder: T,
+}
+
+impl<T> BoxedSoaInPlaceOnceCells<T> {
+    /// Creates a collection of `len` cells that have not been mutated, each of which starts out
+    /// with a clone of `placeholder` once it is mutated.
+    #[must_use]
+    pub fn new(len: usize, placeholder: T) -> Self {
+        Self {
+            cells: SoaInPlaceOnceCells::from_fn(len, |_| None),
+            placeholder,
+        }
+    }
+
+    /// Returns the number of cells.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns whether there are no cells.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns the placeholder that untouched cells share.
+    #[inline]
+    #[must_use]
+    pub fn placeholder(&self) -> &T {
+        &self.placeholder
+    }
+
+    /// Returns whether the cell at `index` was mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn is_mutated(&self, index: usize) -> bool {
+        self.cells.is_mutated(index)
+    }
+
+    /// Returns the number of mutated cells.
+    #[must_use]
+    pub fn count_mutated(&self) -> usize {
+        self.cells.count_mutated()
+    }
+
+    /// Gets the reference to the value of the cell at `index`.
+    ///
+    /// Returns `None` if the cell is not mutated or `index` is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.cells.get(index)?.as_deref()
+    }
+
+    /// Gets a mutable reference to the value of the cell at `index`.
+    ///
+    /// Returns `None` if the cell is not mutated or `index` is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.cells.get_mut(index)?.as_deref_mut()
+    }
+
+    /// Gets the contents of the cell at `index`, mutating a clone of the placeholder with
+    /// `f(&mut T)` if the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// If `f()` panics, the panic is propagated to the caller, and the cell stays untouched.
+    #[inline]
+    pub fn get_or_mutate<F>(&self, index: usize, f: F) -> &T
+    where
+        T: Clone,
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(index, |val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of the cell at `index`, mutating a clone of the placeholder with
+    /// `f(&mut T)` if the cell was never mutated.
+    ///
+    /// Returns an error if the cell was uninitialized and `f()` returns an error, in which case
+    /// the cell stays untouched.
+    ///
+    /// `f` mutates the clone before it is stored, so if it mutates the same cell itself, that
+    /// mutation is kept, and this one is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get_or_try_mutate<F, E>(&self, index: usize, f: F) -> Result<&T, E>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if let Some(val) = self.cells.get(index) {
+            return Ok(boxed(val));
+        }
+
+        let mut val = Box::new(self.placeholder.clone());
+        f(&mut val)?;
+        Ok(boxed(
+            self.cells.get_or_mutate(index, |slot| *slot = Some(val)),
+        ))
+    }
+
+    /// Returns an iterator over `(index, &value)` for every mutated cell, in index order.
+    ///
+    /// Untouched cells are skipped a word of the bitset at a time.
+    pub fn iter_mutated(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.cells
+            .iter_mutated()
+            .map(|(index, val)| (index, boxed(val)))
+    }
+
+    /// Consumes the collection, returning the box of each mutated cell, and `None` for each
+    /// untouched one.
+    pub fn into_boxes(self) -> Vec<Option<Box<T>>> {
+        self.cells.into_inner()
+    }
+}
+
+/// Returns the value of a mutated [`BoxedSoaInPlaceOnceCells`] cell, which always has a box.
+#[inline]
+fn boxed<T>(val: &Option<Box<T>>) -> &T {
+    match val {
+        Some(val) => val,
+        None => unreachable!(),
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for BoxedSoaInPlaceOnceCells<T> {
+    /// Prints the mutated cells as a map from index to value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter_mutated().map(|(i, val)| (i, Value(val))))
+            .finish()
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::InPlaceOnceLock;
+
+/// The one handle that may mutate a lock, created by [`InPlaceOnceLock::split`].
+///
+/// It cannot be cloned, so whichever component owns it is the only one that can mutate the
+/// lock; every other component gets a [`ReadHandle`].
+pub struct WriteHandle<T> {
+    lock: Arc<InPlaceOnceLock<T>>,
+}
+
+/// A handle that may only read a lock, created by [`InPlaceOnceLock::split`] or
+/// [`WriteHandle::reader`].
+pub struct ReadHandle<T> {
+    lock: Arc<InPlaceOnceLock<T>>,
+}
+
+pub(crate) fn split<T>(lock: InPlaceOnceLock<T>) -> (WriteHandle<T>, ReadHandle<T>) {
+    let lock = Arc::new(lock);
+    let reader = ReadHandle {
+        lock: Arc::clone(&lock),
+    };
+    (WriteHandle { lock }, reader)
+}
+
+impl<T> WriteHandle<T> {
+    /// Returns another handle that may only read the lock.
+    #[inline]
+    #[must_use]
+    pub fn reader(&self) -> ReadHandle<T> {
+        ReadHandle {
+            lock: Arc::clone(&self.lock),
+        }
+    }
+
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the lock is not mutated.
+    #[inline]
+    #[must_use]
+    pub fn get(&self) -> Option<&T> {
+        self.lock.get()
+    }
+
+    /// Gets the contents of the lock, mutating it with `f` if the lock was never mutated.
+    ///
+    /// See [`InPlaceOnceLock::get_or_mutate`].
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        self.lock.get_or_mutate(f)
+    }
+
+    /// Gets the contents of the lock, mutating it with `f` if the lock was never mutated.
+    ///
+    /// See [`InPlaceOnceLock::get_or_try_mutate`].
+    #[inline]
+    #[cfg_attr(feature = "debug-tracking", track_caller)]
+    pub fn get_or_try_mutate<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        self.lock.get_or_try_mutate(f)
+    }
+}
+
+impl<T> ReadHandle<T> {
+    /// Gets the reference to the underlying value.
+    ///
+    /// Returns `None` if the lock is not mutated.
+    #[inline]
+    #[must_use]
+    pub fn get(&self) -> Option<&T> {
+        self.lock.get()
+    }
+
+    /// Waits until the writer mutates the lock, then returns a reference to the value.
+    ///
+    /// See [`InPlaceOnceLock::wait_async`].
+    #[cfg(feature = "async")]
+    pub async fn wait_async(&self) -> &T {
+        self.lock.wait_async().await
+    }
+}
+
+impl<T> Clone for ReadHandle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            lock: Arc::clone(&self.lock),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WriteHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WriteHandle").field(&*self.lock).finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReadHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReadHandle").field(&*self.lock).finish()
+    }
+}
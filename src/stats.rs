@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Contention statistics of an [`InPlaceOnceLock`](crate::InPlaceOnceLock), returned by
+/// [`InPlaceOnceLock::stats`](crate::InPlaceOnceLock::stats).
+///
+/// Every count covers the time since the lock was created or its statistics were last reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct LockStats {
+    /// The number of callers that raced to mutate the lock and had to wait for another caller's
+    /// mutator instead.
+    pub contended: u64,
+    /// The total time those callers spent waiting.
+    pub wait_time: Duration,
+    /// How long the mutator took, if it ran.
+    pub mutation_time: Option<Duration>,
+}
+
+/// The counters behind [`LockStats`].
+pub(crate) struct Counters {
+    contended: AtomicU64,
+    wait_nanos: AtomicU64,
+    /// `u64::MAX` if the mutator has not run.
+    mutation_nanos: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            contended: AtomicU64::new(0),
+            wait_nanos: AtomicU64::new(0),
+            mutation_nanos: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    pub(crate) fn record_wait(&self, started: Instant) {
+        self.contended.fetch_add(1, Ordering::Relaxed);
+        self.wait_nanos.fetch_add(nanos(started), Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_mutation(&self, started: Instant) {
+        self.mutation_nanos.store(nanos(started), Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LockStats {
+        let mutation_nanos = self.mutation_nanos.load(Ordering::Relaxed);
+        LockStats {
+            contended: self.contended.load(Ordering::Relaxed),
+            wait_time: Duration::from_nanos(self.wait_nanos.load(Ordering::Relaxed)),
+            mutation_time: (mutation_nanos != u64::MAX)
+                .then(|| Duration::from_nanos(mutation_nanos)),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.contended.store(0, Ordering::Relaxed);
+        self.wait_nanos.store(0, Ordering::Relaxed);
+        self.mutation_nanos.store(u64::MAX, Ordering::Relaxed);
+    }
+}
+
+/// The nanoseconds elapsed since `started`, saturating well below `u64::MAX`.
+fn nanos(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_nanos()).map_or(u64::MAX - 1, |n| n.min(u64::MAX - 1))
+}
@@ -0,0 +1,139 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A cell whose value can be borrowed mutably exactly once, for the rest of the cell's life.
+///
+/// This is the building block of [`singleton!`](crate::singleton): in a `static`, it hands out a
+/// `&'static mut T` to the first caller of [`TakeOnceCell::take`], and `None` to everyone after.
+/// The value is built in place by the static's initializer, so large buffers never pass through
+/// the stack.
+///
+/// With the `critical-section` feature, taking runs inside a critical section, so it works on
+/// targets without atomic compare-and-swap, such as `thumbv6m`. Otherwise it is a single atomic
+/// swap.
+pub struct TakeOnceCell<T> {
+    taken: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+impl<T> TakeOnceCell<T> {
+    /// Creates a new cell that has not been taken.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            taken: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Returns whether the value was taken.
+    #[inline]
+    #[must_use]
+    pub fn is_taken(&self) -> bool {
+        self.taken.load(Ordering::Relaxed)
+    }
+
+    /// Borrows the value mutably for as long as the cell lives, if it was never taken.
+    ///
+    /// Returns `None` if the value was already taken.
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub fn take(&self) -> Option<&mut T> {
+        if self.claim() {
+            // SAFETY: only the one caller that claimed the cell ever gets a reference to the
+            // value, and nothing else reads it while the cell is shared.
+            Some(unsafe { &mut *self.value.get() })
+        } else {
+            None
+        }
+    }
+
+    /// Marks the cell as taken, returning whether this call did.
+    #[cfg(feature = "critical-section")]
+    fn claim(&self) -> bool {
+        critical_section::with(|_| {
+            let taken = self.taken.load(Ordering::Relaxed);
+            self.taken.store(true, Ordering::Relaxed);
+            !taken
+        })
+    }
+
+    /// Marks the cell as taken, returning whether this call did.
+    #[cfg(not(feature = "critical-section"))]
+    fn claim(&self) -> bool {
+        !self.taken.swap(true, Ordering::Relaxed)
+    }
+
+    /// Gets a mutable reference to the value, whether or not it was taken, which the unique
+    /// borrow of the cell makes safe.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Consumes the cell, returning the wrapped value, whether or not it was taken.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+// SAFETY: a shared reference hands out `&mut T` to exactly one caller, on any thread, and
+// nothing else.
+unsafe impl<T: Send> Sync for TakeOnceCell<T> {}
+
+impl<T: Default> Default for TakeOnceCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for TakeOnceCell<T> {
+    /// Prints whether the value was taken, but not the value, which its taker may be mutating.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TakeOnceCell")
+            .field("taken", &self.is_taken())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> From<T> for TakeOnceCell<T> {
+    /// Creates a new `TakeOnceCell<T>` containing `value`. This new cell is not yet taken.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Declares a static resource and borrows it mutably, returning `Some(&'static mut T)` the first
+/// time it runs and `None` every time after, like `cortex_m::singleton!`.
+///
+/// The value is held by a hidden static [`TakeOnceCell`], so the initial value must be a constant
+/// expression, which is built in place rather than on the stack. The static may be named, for
+/// attributes such as `#[link_section]`.
+///
+/// ```
+/// use in_place_once_cell::singleton;
+///
+/// fn buffer() -> Option<&'static mut [u8; 1024]> {
+///     singleton!(: [u8; 1024] = [0; 1024])
+/// }
+///
+/// let buf = buffer().unwrap();
+/// buf[0] = 1;
+/// assert!(buffer().is_none());
+/// ```
+#[macro_export]
+macro_rules! singleton {
+    ($(#[$attr:meta])* $name:ident : $ty:ty = $init:expr) => {{
+        $(#[$attr])*
+        static $name: $crate::TakeOnceCell<$ty> = $crate::TakeOnceCell::new($init);
+        $name.take()
+    }};
+    ($(#[$attr:meta])* : $ty:ty = $init:expr) => {
+        $crate::singleton!($(#[$attr])* VALUE: $ty = $init)
+    };
+}
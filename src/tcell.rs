@@ -0,0 +1,188 @@
+use core::any::TypeId;
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::marker::PhantomData;
+
+use std::collections::BTreeSet;
+use std::sync::{Mutex, PoisonError};
+
+/// The marker types that currently have an owner.
+static OWNERS: Mutex<BTreeSet<TypeId>> = Mutex::new(BTreeSet::new());
+
+/// The one owner of every [`InPlaceOnceTCell`] marked with `Q`, in the style of `qcell`'s
+/// `TCellOwner`.
+///
+/// Only one owner per marker type may exist at a time. Reading a cell takes `&self`, and mutating
+/// it takes `&mut self`, so the borrow checker alone keeps a value from being read while it is
+/// mutated, without a lock or a lifetime brand.
+pub struct InPlaceOnceTCellOwner<Q: 'static> {
+    marker: PhantomData<fn() -> Q>,
+}
+
+/// A cell that can only be mutated once, and only accessed through its
+/// [`InPlaceOnceTCellOwner`].
+///
+/// This complements [`Branded`](crate::Branded) for code already structured around `qcell`
+/// owners: the marker type `Q` takes the place of the brand.
+pub struct InPlaceOnceTCell<Q, T> {
+    is_mutated: Cell<bool>,
+    value: UnsafeCell<T>,
+    owner: PhantomData<fn() -> Q>,
+}
+
+impl<Q: 'static> InPlaceOnceTCellOwner<Q> {
+    /// Creates the owner for the marker type `Q`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an owner for `Q` already exists, which is why there is no `Default`.
+    #[must_use]
+    #[track_caller]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::try_new().expect("an owner for this marker type already exists")
+    }
+
+    /// Creates the owner for the marker type `Q`, or returns `None` if one already exists.
+    #[must_use]
+    pub fn try_new() -> Option<Self> {
+        let mut owners = OWNERS.lock().unwrap_or_else(PoisonError::into_inner);
+        if !owners.insert(TypeId::of::<Q>()) {
+            return None;
+        }
+
+        Some(Self {
+            marker: PhantomData,
+        })
+    }
+
+    /// Gets the reference to the underlying value of `cell`.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get<'a, T>(&self, cell: &'a InPlaceOnceTCell<Q, T>) -> Option<&'a T> {
+        if cell.is_mutated.get() {
+            // SAFETY: the cell is mutated, so it is never mutated again.
+            Some(unsafe { &*cell.value.get() })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the contents of `cell`, mutating it with `f` if the cell was never mutated.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated to the caller, and the cell stays unmutated.
+    #[inline]
+    pub fn get_or_mutate<'a, T, F>(&mut self, cell: &'a InPlaceOnceTCell<Q, T>, f: F) -> &'a T
+    where
+        F: FnOnce(&mut T),
+    {
+        match self.get_or_try_mutate(cell, |val: &mut T| {
+            f(val);
+            Ok::<(), ()>(())
+        }) {
+            Ok(val) => val,
+            Err(..) => unreachable!(),
+        }
+    }
+
+    /// Gets the contents of `cell`, mutating it with `f` if the cell was never mutated.
+    ///
+    /// Returns an error if `f` returns an error during mutation, in which case the cell stays
+    /// unmutated.
+    pub fn get_or_try_mutate<'a, T, F, E>(
+        &mut self,
+        cell: &'a InPlaceOnceTCell<Q, T>,
+        f: F,
+    ) -> Result<&'a T, E>
+    where
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        if !cell.is_mutated.get() {
+            // SAFETY: the cell is untouched, so no reference to its value was handed out, and
+            // every other access goes through the owner, which this call borrows mutably.
+            f(unsafe { &mut *cell.value.get() })?;
+            cell.is_mutated.set(true);
+        }
+
+        // SAFETY: the cell is mutated, so it is never mutated again.
+        Ok(unsafe { &*cell.value.get() })
+    }
+}
+
+impl<Q: 'static> Drop for InPlaceOnceTCellOwner<Q> {
+    fn drop(&mut self) {
+        OWNERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&TypeId::of::<Q>());
+    }
+}
+
+impl<Q: 'static> fmt::Debug for InPlaceOnceTCellOwner<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InPlaceOnceTCellOwner")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Q, T> InPlaceOnceTCell<Q, T> {
+    /// Creates a new cell that has not been mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            is_mutated: Cell::new(false),
+            value: UnsafeCell::new(value),
+            owner: PhantomData,
+        }
+    }
+
+    /// Gets a mutable reference to the underlying value, which needs no owner.
+    ///
+    /// Returns `None` if the cell is not mutated.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.is_mutated.get_mut() {
+            Some(self.value.get_mut())
+        } else {
+            None
+        }
+    }
+
+    /// Consumes the cell, returning the wrapped value. Note that this occurs even when the cell
+    /// was never mutated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+// SAFETY: every access goes through the owner, which hands out `&mut T` to one mutator while
+// borrowed mutably, and `&T` to any thread while borrowed shared.
+unsafe impl<Q, T: Send + Sync> Sync for InPlaceOnceTCell<Q, T> {}
+
+impl<Q, T: Default> Default for InPlaceOnceTCell<Q, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<Q, T> fmt::Debug for InPlaceOnceTCell<Q, T> {
+    /// Prints nothing of the value, which can only be read through the owner.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InPlaceOnceTCell").finish_non_exhaustive()
+    }
+}
+
+impl<Q, T> From<T> for InPlaceOnceTCell<Q, T> {
+    /// Creates a new `InPlaceOnceTCell<Q, T>` containing `value`. This new cell is not yet
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
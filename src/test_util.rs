@@ -0,0 +1,222 @@
+//! Hooks for reproducing races deterministically in tests.
+//!
+//! [`set_hook`] runs a closure whenever the current thread reaches one of the [`HookPoint`]s of
+//! [`InPlaceOnceLock`](crate::InPlaceOnceLock) and, with the `async` feature,
+//! [`AsyncInPlaceOnceLock`](crate::AsyncInPlaceOnceLock). Blocking on a barrier or a channel
+//! there forces a specific interleaving of several threads. [`FakeClock`] stands in for the real
+//! clock of the `Duration`-based timeouts, so they expire exactly when a test says so.
+//!
+//! Both are installed per thread, so tests running in parallel do not see each other's.
+//!
+//! This module is only meant for tests: enable the `test-util` feature in `[dev-dependencies]`.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+    time::Duration,
+    vec::Vec,
+};
+
+/// A point in the mutation protocol of a lock where a hook runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HookPoint {
+    /// Before trying to claim an untouched lock with a compare-and-swap.
+    BeforeCas,
+    /// Before parking, or awaiting, because another mutation is in progress or the cell is not
+    /// mutated yet.
+    BeforePark,
+    /// Before publishing a mutated value.
+    BeforePublish,
+}
+
+/// A hook installed with [`set_hook`].
+type Hook = Rc<dyn Fn(HookPoint)>;
+
+std::thread_local! {
+    static HOOK: RefCell<Option<Hook>> = const { RefCell::new(None) };
+}
+
+/// Runs `hook` whenever the current thread reaches a [`HookPoint`], until the returned guard is
+/// dropped, which restores the previous hook.
+///
+/// The hook may itself use the locks; it is not run for the points those reach.
+#[must_use = "the hook is removed when the guard is dropped"]
+pub fn set_hook<F>(hook: F) -> HookGuard
+where
+    F: Fn(HookPoint) + 'static,
+{
+    let previous = HOOK.with_borrow_mut(|current| current.replace(Rc::new(hook)));
+    HookGuard { previous }
+}
+
+/// Restores the previous hook of the thread when dropped. Returned by [`set_hook`].
+pub struct HookGuard {
+    previous: Option<Hook>,
+}
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        HOOK.with_borrow_mut(|current| *current = previous);
+    }
+}
+
+/// Runs the hook of the current thread, if there is one.
+pub(crate) fn hook(point: HookPoint) {
+    // Taken out while it runs, so that the locks it uses do not run it again.
+    let Some(hook) = HOOK.with_borrow_mut(Option::take) else {
+        return;
+    };
+    let restore = Restore(Some(hook));
+    if let Some(hook) = &restore.0 {
+        hook(point);
+    }
+}
+
+/// Puts a hook back in place when dropped, even if it panicked.
+struct Restore(Option<Hook>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let hook = self.0.take();
+        // A hook installed by the hook itself wins.
+        HOOK.with_borrow_mut(|current| {
+            if current.is_none() {
+                *current = hook;
+            }
+        });
+    }
+}
+
+#[cfg(feature = "async")]
+std::thread_local! {
+    static CLOCK: RefCell<Option<FakeClock>> = const { RefCell::new(None) };
+}
+
+/// A clock that only moves when told to.
+///
+/// Once [installed](FakeClock::install), the timeouts of
+/// [`AsyncInPlaceOnceLock::wait_timeout`](crate::AsyncInPlaceOnceLock::wait_timeout) and
+/// [`get_or_mutate_timeout`](crate::AsyncInPlaceOnceLock::get_or_mutate_timeout) that start on
+/// the current thread expire when [`FakeClock::advance`] moves the clock past them, rather than
+/// after real time. Clones share the same time.
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, Default)]
+pub struct FakeClock(Arc<Mutex<ClockState>>);
+
+#[cfg(feature = "async")]
+#[derive(Debug, Default)]
+struct ClockState {
+    now: Duration,
+    sleepers: Vec<(Duration, Waker)>,
+}
+
+#[cfg(feature = "async")]
+impl FakeClock {
+    /// Creates a new clock at time zero.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, ClockState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns how far the clock was advanced since it was created.
+    #[must_use]
+    pub fn now(&self) -> Duration {
+        self.state().now
+    }
+
+    /// Moves the clock forward by `by`, waking every sleep that is due.
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.state();
+        state.now += by;
+        let now = state.now;
+        let mut due = Vec::new();
+        state.sleepers.retain(|(until, waker)| {
+            if *until <= now {
+                due.push(waker.clone());
+                false
+            } else {
+                true
+            }
+        });
+        drop(state);
+        due.into_iter().for_each(Waker::wake);
+    }
+
+    /// Returns a future that completes once the clock was advanced by `duration` from now.
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep {
+            clock: self.clone(),
+            until: self.now() + duration,
+        }
+    }
+
+    /// Makes the timeouts that start on the current thread use this clock, until the returned
+    /// guard is dropped, which restores the previous clock.
+    #[must_use = "the clock is uninstalled when the guard is dropped"]
+    pub fn install(&self) -> ClockGuard {
+        let previous = CLOCK.with_borrow_mut(|current| current.replace(self.clone()));
+        ClockGuard { previous }
+    }
+
+    /// Returns the clock installed on the current thread, if there is one.
+    pub(crate) fn current() -> Option<Self> {
+        CLOCK.with_borrow(Clone::clone)
+    }
+}
+
+/// Restores the previous clock of the thread when dropped. Returned by [`FakeClock::install`].
+#[cfg(feature = "async")]
+pub struct ClockGuard {
+    previous: Option<FakeClock>,
+}
+
+#[cfg(feature = "async")]
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CLOCK.with_borrow_mut(|current| *current = previous);
+    }
+}
+
+/// A future that completes once a [`FakeClock`] reaches a point in time. Returned by
+/// [`FakeClock::sleep`].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Sleep {
+    clock: FakeClock,
+    until: Duration,
+}
+
+#[cfg(feature = "async")]
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.clock.state();
+        if state.now >= self.until {
+            return Poll::Ready(());
+        }
+
+        let until = self.until;
+        if !state
+            .sleepers
+            .iter()
+            .any(|(at, waker)| *at == until && waker.will_wake(cx.waker()))
+        {
+            state.sleepers.push((until, cx.waker().clone()));
+        }
+        Poll::Pending
+    }
+}
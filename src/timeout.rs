@@ -0,0 +1,118 @@
+use std::future::{self, Future};
+#[cfg(feature = "futures-timer")]
+use std::pin::Pin;
+use std::pin::pin;
+#[cfg(feature = "futures-timer")]
+use std::task::Context;
+use std::task::Poll;
+#[cfg(feature = "futures-timer")]
+use std::time::Duration;
+
+use crate::AsyncInPlaceOnceLock;
+use crate::errors::TimeoutError;
+
+/// Polls `fut` until it completes, or gives up once `deadline` completes.
+async fn race<F: Future>(fut: F, deadline: impl Future) -> Option<F::Output> {
+    let mut fut = pin!(fut);
+    let mut deadline = pin!(deadline);
+    future::poll_fn(|cx| {
+        if let Poll::Ready(val) = fut.as_mut().poll(cx) {
+            Poll::Ready(Some(val))
+        } else if deadline.as_mut().poll(cx).is_ready() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// The deadline of a `Duration`-based timeout.
+#[cfg(feature = "futures-timer")]
+enum Delay {
+    Timer(futures_timer::Delay),
+    #[cfg(feature = "test-util")]
+    Fake(crate::test_util::Sleep),
+}
+
+#[cfg(feature = "futures-timer")]
+impl Delay {
+    /// Starts a timeout of `timeout`, on the [`FakeClock`](crate::test_util::FakeClock) of the
+    /// current thread if one is installed.
+    fn new(timeout: Duration) -> Self {
+        #[cfg(feature = "test-util")]
+        if let Some(clock) = crate::test_util::FakeClock::current() {
+            return Self::Fake(clock.sleep(timeout));
+        }
+        Self::Timer(futures_timer::Delay::new(timeout))
+    }
+}
+
+#[cfg(feature = "futures-timer")]
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        match self.get_mut() {
+            Self::Timer(delay) => Pin::new(delay).poll(cx),
+            #[cfg(feature = "test-util")]
+            Self::Fake(sleep) => Pin::new(sleep).poll(cx),
+        }
+    }
+}
+
+impl<T> AsyncInPlaceOnceLock<T> {
+    /// Like [`AsyncInPlaceOnceLock::wait`], but gives up once `deadline` completes.
+    ///
+    /// With the `debug-tracking` feature, the error names the thread that was mutating the cell
+    /// when the deadline passed, and for how long it had been.
+    ///
+    /// `deadline` is usually the sleep future of whatever runtime is in use, e.g.
+    /// `tokio::time::sleep(duration)`.
+    pub async fn wait_until(&self, deadline: impl Future) -> Result<&T, TimeoutError> {
+        race(self.wait(), deadline)
+            .await
+            .ok_or_else(|| self.timed_out())
+    }
+
+    /// Like [`AsyncInPlaceOnceLock::get_or_mutate`], but gives up once `deadline` completes.
+    ///
+    /// The deadline covers both waiting for another mutator and running `f`. If it passes while
+    /// `f` is running, the mutation is cancelled as described in the
+    /// [type-level documentation](AsyncInPlaceOnceLock#cancellation).
+    pub async fn get_or_mutate_until<F>(
+        &self,
+        f: F,
+        deadline: impl Future,
+    ) -> Result<&T, TimeoutError>
+    where
+        F: AsyncFnOnce(&mut T),
+    {
+        race(self.get_or_mutate(f), deadline)
+            .await
+            .ok_or_else(|| self.timed_out())
+    }
+
+    /// Like [`AsyncInPlaceOnceLock::wait`], but gives up after `timeout`.
+    #[cfg(feature = "futures-timer")]
+    pub async fn wait_timeout(&self, timeout: Duration) -> Result<&T, TimeoutError> {
+        self.wait_until(Delay::new(timeout)).await
+    }
+
+    /// Like [`AsyncInPlaceOnceLock::get_or_mutate`], but gives up after `timeout`.
+    ///
+    /// The timeout covers both waiting for another mutator and running `f`. If it expires while
+    /// `f` is running, the mutation is cancelled as described in the
+    /// [type-level documentation](AsyncInPlaceOnceLock#cancellation).
+    #[cfg(feature = "futures-timer")]
+    pub async fn get_or_mutate_timeout<F>(
+        &self,
+        f: F,
+        timeout: Duration,
+    ) -> Result<&T, TimeoutError>
+    where
+        F: AsyncFnOnce(&mut T),
+    {
+        self.get_or_mutate_until(f, Delay::new(timeout)).await
+    }
+}
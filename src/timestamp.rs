@@ -0,0 +1,48 @@
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// When an [`InPlaceOnceLock`](crate::InPlaceOnceLock) was mutated, returned by
+/// [`InPlaceOnceLock::mutated_when`](crate::InPlaceOnceLock::mutated_when).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MutationTime {
+    /// The monotonic time, to measure how long after startup the lock was mutated.
+    pub instant: Instant,
+    /// The wall-clock time, to report in logs. This is derived from `instant`, so it does not
+    /// follow changes to the system clock made while the process runs.
+    pub system_time: SystemTime,
+}
+
+/// The times every [`Timestamp`] is counted from, taken together the first time one is recorded.
+static ANCHOR: OnceLock<(Instant, SystemTime)> = OnceLock::new();
+
+/// The time a lock's mutator returned, as nanoseconds since [`ANCHOR`], which keeps each lock
+/// a word larger rather than the size of two clocks.
+pub(crate) struct Timestamp(AtomicU64);
+
+impl Timestamp {
+    pub(crate) const fn new() -> Self {
+        Self(AtomicU64::new(u64::MAX))
+    }
+
+    /// Records the current time. Publishing the lock afterwards publishes it too.
+    pub(crate) fn record(&self) {
+        let (anchor, _) = ANCHOR.get_or_init(|| (Instant::now(), SystemTime::now()));
+        let nanos = u64::try_from(anchor.elapsed().as_nanos()).unwrap_or(u64::MAX - 1);
+        self.0.store(nanos.min(u64::MAX - 1), Ordering::Relaxed);
+    }
+
+    /// Returns the recorded time, if any. Only meaningful once the lock is mutated.
+    pub(crate) fn get(&self) -> Option<MutationTime> {
+        let nanos = self.0.load(Ordering::Relaxed);
+        let (instant, system_time) = ANCHOR.get()?;
+        (nanos != u64::MAX).then(|| {
+            let elapsed = Duration::from_nanos(nanos);
+            MutationTime {
+                instant: *instant + elapsed,
+                system_time: *system_time + elapsed,
+            }
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use ::tokio::sync::watch;
+use ::tokio::task;
+
+use crate::{AsyncInPlaceOnceLock, InPlaceOnceLock};
+
+impl<T> AsyncInPlaceOnceLock<T> {
+    /// Returns a receiver that holds `None` until the cell is mutated, and a clone of the
+    /// mutated value afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is not yet mutated and this is called outside of a tokio runtime.
+    pub fn to_watch(&'static self) -> watch::Receiver<Option<T>>
+    where
+        T: Clone + Send + Sync,
+    {
+        let (tx, rx) = watch::channel(self.get().cloned());
+        if rx.borrow().is_none() {
+            ::tokio::spawn(async move {
+                let _ = tx.send(Some(self.wait().await.clone()));
+            });
+        }
+
+        rx
+    }
+
+    /// Gets the contents of the cell, running the synchronous mutator `f` on tokio's blocking
+    /// thread pool if the cell was never mutated.
+    ///
+    /// Use this for CPU-heavy mutators that would otherwise stall the runtime's worker threads.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated to the caller and the cell stays unmutated.
+    ///
+    /// # Cancellation
+    ///
+    /// Once `f` has been handed to the blocking pool it runs to completion and publishes the
+    /// mutation even if the returned future is dropped.
+    pub async fn blocking_get_or_mutate<F>(&'static self, f: F) -> &'static T
+    where
+        T: Send + Sync,
+        F: FnOnce(&mut T) + Send + 'static,
+    {
+        let Some(mut mutation) = self.begin_mutation().await else {
+            return self.get().unwrap();
+        };
+
+        let res = task::spawn_blocking(move || {
+            f(mutation.value());
+            mutation.publish()
+        })
+        .await;
+
+        match res {
+            Ok(val) => val,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+}
+
+impl<T> InPlaceOnceLock<T> {
+    /// Gets the contents of the cell, running the mutator `f` on tokio's blocking thread pool if
+    /// the cell was never mutated.
+    ///
+    /// This lets async code use a synchronous lock with a CPU-heavy mutator without stalling the
+    /// runtime's worker threads, whether this call performs the mutation or waits for another
+    /// thread to finish it.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated to the caller.
+    pub async fn blocking_get_or_mutate<F>(&'static self, f: F) -> &'static T
+    where
+        T: Send + Sync,
+        F: FnOnce(&mut T) + Send + 'static,
+    {
+        if let Some(val) = self.get() {
+            return val;
+        }
+
+        match task::spawn_blocking(move || self.get_or_mutate(f)).await {
+            Ok(val) => val,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+}
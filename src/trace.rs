@@ -0,0 +1,144 @@
+//! Tracing instrumentation for the locks, which compiles to nothing without the `tracing`
+//! feature.
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// An optional label attached to a lock's tracing events. Zero-sized without the `tracing`
+/// feature.
+#[derive(Clone, Copy)]
+pub(crate) struct Label {
+    #[cfg(feature = "tracing")]
+    label: Option<&'static str>,
+}
+
+impl Label {
+    pub(crate) const NONE: Self = Self {
+        #[cfg(feature = "tracing")]
+        label: None,
+    };
+
+    #[inline]
+    pub(crate) const fn new(label: &'static str) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = label;
+        Self {
+            #[cfg(feature = "tracing")]
+            label: Some(label),
+        }
+    }
+}
+
+/// Traces one mutation from start to completion.
+///
+/// Dropping this before [`MutationTrace::finish`] means the mutator panicked or, for async locks,
+/// was cancelled.
+pub(crate) struct MutationTrace {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+    #[cfg(feature = "tracing")]
+    poisons: bool,
+    #[cfg(feature = "tracing")]
+    finished: bool,
+}
+
+impl MutationTrace {
+    /// Starts tracing a mutation of a `lock` holding a `T`. `poisons` is whether a panicking
+    /// mutator poisons the lock.
+    #[inline]
+    #[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+    pub(crate) fn start<T: ?Sized>(lock: &'static str, label: Label, poisons: bool) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = (lock, label, poisons);
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "mutate",
+            lock,
+            ty = core::any::type_name::<T>(),
+            label = label.label,
+        );
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &span, "mutation started");
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span,
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+            #[cfg(feature = "tracing")]
+            poisons,
+            #[cfg(feature = "tracing")]
+            finished: false,
+        }
+    }
+
+    /// Runs `f` inside the mutation's span.
+    #[inline]
+    pub(crate) fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    /// Instruments `fut` with the mutation's span.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub(crate) fn instrument<F: Future>(&self, fut: F) -> impl Future<Output = F::Output> {
+        #[cfg(feature = "tracing")]
+        return tracing::Instrument::instrument(fut, self.span.clone());
+        #[cfg(not(feature = "tracing"))]
+        fut
+    }
+
+    /// Records the outcome of the mutator.
+    #[inline]
+    #[cfg_attr(not(feature = "tracing"), allow(unused_mut))]
+    pub(crate) fn finish<E>(mut self, res: &Result<(), E>) {
+        #[cfg(not(feature = "tracing"))]
+        let _ = res;
+        #[cfg(feature = "tracing")]
+        {
+            let elapsed_us = self.start.elapsed().as_micros() as u64;
+            match res {
+                Ok(()) => tracing::debug!(parent: &self.span, elapsed_us, "mutation completed"),
+                Err(..) => tracing::warn!(parent: &self.span, elapsed_us, "mutation failed"),
+            }
+            self.finished = true;
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for MutationTrace {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        if !std::thread::panicking() {
+            tracing::info!(parent: &self.span, "mutation cancelled");
+        } else if self.poisons {
+            tracing::error!(parent: &self.span, "mutator panicked, lock poisoned");
+        } else {
+            tracing::error!(parent: &self.span, "mutator panicked");
+        }
+    }
+}
+
+/// Records that a caller of `lock` had to wait for another mutator.
+#[inline]
+#[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+pub(crate) fn contended<T: ?Sized>(lock: &'static str, label: Label) {
+    #[cfg(not(feature = "tracing"))]
+    let _ = (lock, label);
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        lock,
+        ty = core::any::type_name::<T>(),
+        label = label.label,
+        "contended with another mutator"
+    );
+}
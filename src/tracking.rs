@@ -0,0 +1,88 @@
+use std::fmt;
+use std::panic::Location;
+use std::sync::Mutex;
+#[cfg(feature = "async")]
+use std::thread::Thread;
+use std::thread::{self, ThreadId};
+#[cfg(feature = "async")]
+use std::time::Instant;
+
+#[cfg(feature = "async")]
+use crate::errors::MutationHolder;
+
+/// Where and by which thread a mutation was started.
+#[derive(Clone, Copy)]
+struct Origin {
+    location: &'static Location<'static>,
+    thread: ThreadId,
+}
+
+/// The origin of the mutation of a lock, recorded for its alternate `Debug` form.
+pub(crate) struct Tracking {
+    origin: Mutex<Option<Origin>>,
+}
+
+impl Tracking {
+    pub(crate) const fn new() -> Self {
+        Self {
+            origin: Mutex::new(None),
+        }
+    }
+
+    /// Records the start of a mutation called from `location`.
+    pub(crate) fn begin(&self, location: &'static Location<'static>) {
+        *self.origin.lock().unwrap_or_else(|e| e.into_inner()) = Some(Origin {
+            location,
+            thread: thread::current().id(),
+        });
+    }
+
+    /// Returns where the mutation was started, if there was one.
+    pub(crate) fn location(&self) -> Option<&'static Location<'static>> {
+        let origin = self.origin.lock().unwrap_or_else(|e| e.into_inner());
+        origin.map(|origin| origin.location)
+    }
+
+    /// Adds the origin of the mutation to `d`, if there was one.
+    pub(crate) fn fmt_origin(&self, d: &mut fmt::DebugStruct<'_, '_>) {
+        // `try_lock`, so formatting from inside the mutator cannot deadlock.
+        if let Ok(origin) = self.origin.try_lock()
+            && let Some(origin) = *origin
+        {
+            d.field("mutated_at", &format_args!("{}", origin.location));
+            d.field("mutated_by", &format_args!("{:?}", origin.thread));
+        }
+    }
+}
+
+/// The thread that started the mutation in progress, and when, recorded to explain timeouts.
+#[cfg(feature = "async")]
+pub(crate) struct Holder(Mutex<Option<(Thread, Instant)>>);
+
+#[cfg(feature = "async")]
+impl Holder {
+    pub(crate) const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    /// Records that the current thread starts mutating.
+    pub(crate) fn begin(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((thread::current(), Instant::now()));
+    }
+
+    /// Records that the mutation in progress ended, whether or not it succeeded.
+    pub(crate) fn end(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Returns the thread holding the mutation in progress, and for how long it has.
+    pub(crate) fn get(&self) -> Option<MutationHolder> {
+        let holder = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        holder.as_ref().map(|(thread, started)| MutationHolder {
+            thread: thread.id(),
+            thread_name: thread.name().map(Into::into),
+            elapsed: started.elapsed(),
+        })
+    }
+}
@@ -0,0 +1,103 @@
+//! A cell that does not track whether it was mutated at all, for callers who already know.
+//!
+//! Even a bit per cell is too much for some huge tables whose cells are all mutated in one known
+//! pass, e.g. during startup, and only read afterwards. [`UncheckedOnceCell`] stores nothing but
+//! its value, and leaves upholding the once-mutation contract to the caller, through `unsafe`
+//! methods.
+//!
+//! Prefer [`InPlaceOnceCell`](crate::InPlaceOnceCell) or
+//! [`SoaInPlaceOnceCells`](crate::SoaInPlaceOnceCells) unless profiling says otherwise.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+
+/// A cell that can only be mutated once, without a flag to check that it is.
+///
+/// This has the same layout as `T`.
+#[repr(transparent)]
+pub struct UncheckedOnceCell<T> {
+    value: UnsafeCell<T>,
+}
+
+impl<T> UncheckedOnceCell<T> {
+    /// Creates a new cell, which the caller considers not mutated.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Mutates the cell with `f`.
+    ///
+    /// # Safety
+    ///
+    /// The cell must not be mutated yet, and no reference to its value may be alive, which
+    /// includes `f` not accessing the cell.
+    #[inline]
+    pub unsafe fn mutate<F>(&self, f: F) -> &T
+    where
+        F: FnOnce(&mut T),
+    {
+        // SAFETY: no other reference to the value is alive, as guaranteed by the caller.
+        f(unsafe { &mut *self.value.get() });
+        // SAFETY: the mutable reference is gone.
+        unsafe { self.assume_mutated() }
+    }
+
+    /// Gets the reference to the underlying value, assuming that the cell is mutated.
+    ///
+    /// # Safety
+    ///
+    /// The cell must be mutated, and may not be mutated again while the returned reference is
+    /// alive, which [`UncheckedOnceCell::mutate`] already forbids.
+    #[inline]
+    pub const unsafe fn assume_mutated(&self) -> &T {
+        unsafe { &*self.value.get() }
+    }
+
+    /// Returns the value, whether or not the cell is mutated.
+    #[inline]
+    pub(crate) const fn peek(&self) -> &T {
+        // SAFETY: the value is always valid, and `mutate` forbids reading it while mutating.
+        unsafe { &*self.value.get() }
+    }
+
+    /// Gets a mutable reference to the underlying value, whether or not the cell is mutated.
+    #[inline]
+    pub const fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Consumes the cell, returning the wrapped value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: Default> Default for UncheckedOnceCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for UncheckedOnceCell<T> {
+    /// Prints the value, whether or not the cell is mutated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UncheckedOnceCell")
+            .field(self.peek())
+            .finish()
+    }
+}
+
+impl<T> From<T> for UncheckedOnceCell<T> {
+    /// Creates a new `UncheckedOnceCell<T>` containing `value`, which the caller considers not
+    /// mutated.
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,193 @@
+//! Kani proof harnesses for the unsafe core, run with `cargo kani --features std`.
+//!
+//! Each harness checks, for every initial value and every sequence of failing and succeeding
+//! mutators, that:
+//!
+//! - `get` returns nothing until a mutator succeeds, including from inside a running mutator,
+//!   so no shared reference can alias the value being mutated;
+//! - at most one mutation is in flight at a time, so no two `&mut` to the value overlap;
+//! - a successful mutation is never lost: the value stays mutated, and no mutator runs again.
+
+use core::cell::Cell;
+use core::ptr::NonNull;
+use core::sync::atomic::AtomicU8;
+
+use crate::raw::{RawInPlaceOnce, RawState};
+use crate::{
+    HasSpareFlagBit, InPlaceOnceCell, PackedInPlaceOnceCell, RacyInPlaceOnceLock, ReprCOnceCell,
+    SharedInPlaceOnceLock,
+};
+
+/// The number of mutation attempts each harness makes.
+const ATTEMPTS: usize = 3;
+
+/// Makes [`ATTEMPTS`] attempts to mutate a cell holding `init` through `get_or_try_mutate`, each
+/// failing at random, checking the properties above after each one. Failing mutators leave the
+/// value alone, so every mutator that runs must be handed `init`.
+macro_rules! check_attempts {
+    ($cell:expr, $init:expr) => {{
+        let cell = &$cell;
+        let init: u32 = $init;
+        let runs = Cell::new(0);
+        let mut expected_runs = 0;
+        let mut mutated = false;
+        for _ in 0..ATTEMPTS {
+            let fail: bool = kani::any();
+            let res = cell.get_or_try_mutate(|val: &mut u32| {
+                assert!(
+                    cell.get().is_none(),
+                    "the value is visible while being mutated"
+                );
+                assert_eq!(*val, init, "the mutator was not handed the stored value");
+                runs.set(runs.get() + 1);
+                if fail {
+                    return Err(());
+                }
+                *val = val.wrapping_add(1);
+                Ok(())
+            });
+
+            if !mutated {
+                expected_runs += 1;
+                mutated = !fail;
+            }
+            assert_eq!(res.ok().copied(), mutated.then_some(init.wrapping_add(1)));
+            assert_eq!(
+                cell.get().copied(),
+                mutated.then_some(init.wrapping_add(1)),
+                "the mutation was lost"
+            );
+        }
+
+        // Every attempt up to and including the first success ran the mutator, and none after.
+        assert_eq!(runs.get(), expected_runs);
+    }};
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn cell_attempts() {
+    let init = kani::any();
+    check_attempts!(InPlaceOnceCell::new(init), init);
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn repr_c_attempts() {
+    let init = kani::any();
+    check_attempts!(ReprCOnceCell::new(init), init);
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn shared_attempts() {
+    let init = kani::any();
+    check_attempts!(SharedInPlaceOnceLock::new(init), init);
+}
+
+#[cfg(feature = "std")]
+#[kani::proof]
+#[kani::unwind(4)]
+fn lock_attempts() {
+    let init = kani::any();
+    check_attempts!(crate::InPlaceOnceLock::new(init), init);
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn raw_mutation_is_exclusive() {
+    let state = AtomicU8::new(0);
+    let mut value: u32 = kani::any();
+    let init = value;
+    // SAFETY: `value` is only accessed through `raw` until the end.
+    let raw = unsafe { RawInPlaceOnce::new(&state, &raw mut value) };
+
+    let mut mutated = false;
+    for _ in 0..ATTEMPTS {
+        match raw.try_begin_mutation() {
+            Ok(mut mutation) => {
+                assert!(!mutated, "a mutated payload was handed out for writing");
+                // No second `&mut` can be taken while the first is alive.
+                assert_eq!(raw.try_begin_mutation().err(), Some(RawState::Mutating));
+                assert_eq!(raw.get(), None);
+                *mutation.value() = init.wrapping_add(1);
+                if kani::any() {
+                    assert_eq!(raw.complete_mutation(mutation), &init.wrapping_add(1));
+                    mutated = true;
+                } else {
+                    *mutation.value() = init;
+                    raw.abort_mutation(mutation);
+                    assert_eq!(raw.state(), RawState::Untouched);
+                }
+            }
+            Err(state) => {
+                assert!(mutated);
+                assert_eq!(state, RawState::Mutated);
+            }
+        }
+        assert_eq!(raw.get().is_some(), mutated, "the mutation was lost");
+    }
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn racy_attempts() {
+    let init: u32 = kani::any();
+    let lock = RacyInPlaceOnceLock::new(init);
+    let mut mutated = false;
+    for _ in 0..ATTEMPTS {
+        let fail: bool = kani::any();
+        let res = lock.get_or_try_mutate(|val| {
+            *val = val.wrapping_add(1);
+            if fail { Err(()) } else { Ok(()) }
+        });
+        if !mutated {
+            mutated = !fail;
+        }
+        assert_eq!(lock.get().copied(), mutated.then_some(init.wrapping_add(1)));
+        if res.is_ok() {
+            assert_eq!(res, Ok(init.wrapping_add(1)));
+        }
+    }
+}
+
+#[kani::proof]
+fn spare_flag_bit_round_trip() {
+    let mut val = 0u16;
+    let ptr = NonNull::from(&mut val);
+    let mut flagged = ptr;
+    // SAFETY: `flagged` is a valid `NonNull<u16>`, flagged right before being unflagged.
+    unsafe {
+        assert!(!NonNull::<u16>::is_flag_set(&raw const flagged));
+        NonNull::<u16>::set_flag(&raw mut flagged);
+        assert!(NonNull::<u16>::is_flag_set(&raw const flagged));
+        NonNull::<u16>::clear_flag(&raw mut flagged);
+    }
+    assert_eq!(flagged, ptr);
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn packed_attempts() {
+    let mut val = 0u16;
+    let ptr = NonNull::from(&mut val);
+    let cell = PackedInPlaceOnceCell::new(ptr);
+    let mut mutated = false;
+    for _ in 0..ATTEMPTS {
+        let fail: bool = kani::any();
+        let res = cell.get_or_try_mutate(|p| {
+            assert!(
+                cell.get().is_none(),
+                "the value is visible while being mutated"
+            );
+            assert_eq!(*p, ptr, "the mutator saw a flagged value");
+            if fail { Err(()) } else { Ok(()) }
+        });
+        if !mutated {
+            mutated = !fail;
+        }
+        assert_eq!(res.is_ok(), mutated);
+        assert_eq!(cell.get().copied(), mutated.then_some(ptr));
+    }
+    assert_eq!(cell.into_inner(), ptr);
+}
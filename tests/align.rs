@@ -0,0 +1,39 @@
+mod common;
+
+use in_place_once_cell::AlignedOnceCell;
+use in_place_once_cell::align::{Align64, Align4096};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+fn basic() {
+    let mut c = AlignedOnceCell::<u32, Align64>::new(U32_INIT);
+    assert_eq!(c.get(), None);
+    assert_eq!(c.get_or_try_mutate(|_| Err(())), Err(()));
+    assert_eq!(c.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(c.get_or_mutate(|_| unreachable!()), &U32_MUTATED);
+    *c.get_mut().unwrap() += 1;
+    assert_eq!(format!("{c:?}"), "AlignedOnceCell(1157)");
+    assert_eq!(c.into_inner(), U32_MUTATED + 1);
+}
+
+#[test]
+/// The value is aligned wherever the cell is stored.
+fn alignment() {
+    #[repr(align(256))]
+    struct Custom;
+
+    assert_eq!(align_of::<AlignedOnceCell<u8, Align64>>(), 64);
+    let cells: [AlignedOnceCell<u8, Align64>; 3] = Default::default();
+    for c in &cells {
+        assert_eq!(c.get_or_mutate(|_| {}) as *const u8 as usize % 64, 0);
+    }
+
+    let page = Box::new(AlignedOnceCell::<[u8; 16], Align4096>::new([0; 16]));
+    assert_eq!(page.get_or_mutate(|_| {}).as_ptr() as usize % 4096, 0);
+    let custom = AlignedOnceCell::<u32, Custom>::new(U32_INIT);
+    assert_eq!(
+        custom.get_or_mutate(u32_square) as *const u32 as usize % 256,
+        0
+    );
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+use in_place_once_cell::ArcOnce;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Only one of many owners mutates the value, and every clone sees it.
+fn shared_mutation() {
+    let calls = AtomicU32::new(0);
+    let arc = ArcOnce::new(U32_INIT);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            let arc = arc.clone();
+            let calls = &calls;
+            s.spawn(move || {
+                let val = arc.get_or_mutate(|v| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    u32_square(v);
+                });
+                assert_eq!(val, &U32_MUTATED);
+            });
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    assert_eq!(arc.get(), Some(&U32_MUTATED));
+}
+
+#[test]
+/// The lock can only be borrowed mutably or taken back by its only owner.
+fn unique_owner() {
+    let mut arc = ArcOnce::new(U32_INIT);
+    let clone = arc.clone();
+    assert_eq!(ArcOnce::strong_count(&arc), 2);
+    assert!(ArcOnce::ptr_eq(&arc, &clone));
+    assert!(ArcOnce::get_mut(&mut arc).is_none());
+
+    let clone = ArcOnce::try_unwrap(clone).unwrap_err();
+    drop(clone);
+    let lock = ArcOnce::get_mut(&mut arc).unwrap();
+    *lock.get_mut_or_mutate(u32_square) += 1;
+    assert_eq!(arc.get(), Some(&(U32_MUTATED + 1)));
+
+    let lock = ArcOnce::into_inner(arc).unwrap();
+    assert_eq!(lock.into_inner(), U32_MUTATED + 1);
+}
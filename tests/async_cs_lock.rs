@@ -0,0 +1,137 @@
+#![cfg(feature = "critical-section")]
+
+mod common;
+
+use futures::executor::block_on;
+use futures::task::{self, ArcWake};
+use in_place_once_cell::AsyncInPlaceOnceCsLock;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+/// A future driven by hand, recording whether it has been woken since its last poll.
+struct Manual<F> {
+    fut: Pin<Box<F>>,
+    woken: Arc<Woken>,
+}
+
+struct Woken(AtomicBool);
+
+impl ArcWake for Woken {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::Release);
+    }
+}
+
+impl<F: Future> Manual<F> {
+    fn new(fut: F) -> Self {
+        Self {
+            fut: Box::pin(fut),
+            woken: Arc::new(Woken(AtomicBool::new(false))),
+        }
+    }
+
+    fn poll(&mut self) -> Poll<F::Output> {
+        self.woken.0.store(false, Ordering::Release);
+        let waker = task::waker(self.woken.clone());
+        self.fut.as_mut().poll(&mut Context::from_waker(&waker))
+    }
+
+    fn is_woken(&self) -> bool {
+        self.woken.0.load(Ordering::Acquire)
+    }
+}
+
+#[test]
+/// Test basic functionality of `get` and `get_or_mutate`.
+fn basic() {
+    let lock = AsyncInPlaceOnceCsLock::new(U32_INIT);
+    assert_eq!(lock.get(), None);
+    block_on(async {
+        assert_eq!(
+            lock.get_or_mutate(async |v| u32_square(v)).await,
+            &U32_MUTATED
+        );
+        assert_eq!(lock.get_or_mutate(async |v| *v += 1).await, &U32_MUTATED);
+        assert_eq!(lock.wait().await, &U32_MUTATED);
+    });
+}
+
+#[test]
+/// Many threads race to mutate; exactly one mutator runs and everyone sees its result.
+fn race() {
+    let lock = AsyncInPlaceOnceCsLock::new(U32_INIT);
+    let calls = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..16 {
+            s.spawn(|| {
+                let val = block_on(lock.get_or_mutate(async |v| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(Duration::from_millis(10));
+                    u32_square(v);
+                }));
+                assert_eq!(val, &U32_MUTATED);
+            });
+        }
+        for _ in 0..16 {
+            s.spawn(|| assert_eq!(block_on(lock.wait()), &U32_MUTATED));
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+/// Cancelling the running mutator wakes the queued futures, and cancelled waiters unlink
+/// themselves from the waiter list.
+fn cancellation() {
+    let lock = AsyncInPlaceOnceCsLock::new(U32_INIT);
+
+    let mut first = Manual::new(lock.get_or_mutate(async |_| futures::pending!()));
+    let mut second = Manual::new(lock.get_or_mutate(async |_| unreachable!()));
+    let mut third = Manual::new(lock.get_or_mutate(async |v| u32_square(v)));
+    let mut waiter = Manual::new(lock.wait());
+    assert!(first.poll().is_pending());
+    assert!(second.poll().is_pending());
+    assert!(third.poll().is_pending());
+    assert!(waiter.poll().is_pending());
+
+    drop(second);
+    drop(first);
+    assert_eq!(lock.get(), None);
+    assert!(third.is_woken());
+    assert!(waiter.is_woken());
+
+    assert!(waiter.poll().is_pending());
+    assert_eq!(third.poll(), Poll::Ready(&U32_MUTATED));
+    assert!(waiter.is_woken());
+    assert_eq!(waiter.poll(), Poll::Ready(&U32_MUTATED));
+}
+
+#[test]
+/// A failed mutation leaves the cell unmutated.
+fn try_mutate_err() {
+    let lock = AsyncInPlaceOnceCsLock::new(U32_INIT);
+    block_on(async {
+        assert_eq!(lock.get_or_try_mutate(async |_| Err(())).await, Err(()));
+        assert_eq!(lock.get(), None);
+    });
+}
+
+#[test]
+fn debug_impl() {
+    let lock = AsyncInPlaceOnceCsLock::new(U32_INIT);
+    assert_eq!(format!("{lock:?}"), "AsyncInPlaceOnceCsLock(<untouched>)");
+    block_on(lock.get_or_mutate(async |v| u32_square(v)));
+    assert_eq!(
+        format!("{lock:?}"),
+        format!("AsyncInPlaceOnceCsLock({U32_MUTATED})")
+    );
+}
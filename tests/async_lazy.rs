@@ -0,0 +1,57 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use futures::executor::block_on;
+use in_place_once_cell::AsyncInPlaceLazyLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use common::{U32_INIT, U32_MUTATED};
+
+/// A test mutator
+async fn u32_square(v: &mut u32) {
+    *v = *v * *v;
+}
+
+#[test]
+/// Test a `static` declared with the default mutator type.
+fn static_lazy() {
+    static LAZY: AsyncInPlaceLazyLock<u32> =
+        AsyncInPlaceLazyLock::new(U32_INIT, |v| Box::pin(u32_square(v)));
+
+    assert_eq!(LAZY.try_get(), None);
+    assert_eq!(block_on(LAZY.get()), &U32_MUTATED);
+    assert_eq!(block_on(AsyncInPlaceLazyLock::force(&LAZY)), &U32_MUTATED);
+    assert_eq!(LAZY.try_get(), Some(&U32_MUTATED));
+}
+
+#[test]
+/// The mutator runs exactly once, even when many threads access the lock at once.
+fn mutates_once() {
+    let calls = AtomicUsize::new(0);
+    let lazy = AsyncInPlaceLazyLock::new(U32_INIT, async |v: &mut u32| {
+        calls.fetch_add(1, Ordering::Relaxed);
+        u32_square(v).await;
+    });
+
+    thread::scope(|s| {
+        for _ in 0..16 {
+            s.spawn(|| assert_eq!(block_on(lazy.get()), &U32_MUTATED));
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    assert_eq!(lazy.into_inner(), U32_MUTATED);
+}
+
+#[test]
+fn debug_impl() {
+    let lazy = AsyncInPlaceLazyLock::new(U32_INIT, async |v: &mut u32| u32_square(v).await);
+    assert_eq!(format!("{lazy:?}"), "AsyncInPlaceLazyLock(<untouched>)");
+    block_on(lazy.get());
+    assert_eq!(
+        format!("{lazy:?}"),
+        format!("AsyncInPlaceLazyLock({U32_MUTATED})")
+    );
+}
@@ -0,0 +1,396 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use futures::executor::block_on;
+use futures::task::{self, ArcWake};
+use in_place_once_cell::AsyncInPlaceOnceLock;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::{Pin, pin};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test basic functionality of `get` and `get_or_mutate`.
+fn basic() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.get(), None);
+    block_on(async {
+        assert_eq!(
+            lock.get_or_mutate(async |v| u32_square(v)).await,
+            &U32_MUTATED
+        );
+        assert_eq!(lock.get_or_mutate(async |v| *v += 1).await, &U32_MUTATED);
+    });
+    assert_eq!(lock.get(), Some(&U32_MUTATED));
+}
+
+#[test]
+/// A failed mutation leaves the cell unmutated.
+fn try_mutate_err() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+    block_on(async {
+        assert_eq!(lock.get_or_try_mutate(async |_| Err(())).await, Err(()));
+        assert_eq!(lock.get(), None);
+        assert_eq!(
+            lock.get_or_try_mutate(async |v| {
+                u32_square(v);
+                Ok::<(), ()>(())
+            })
+            .await,
+            Ok(&U32_MUTATED)
+        );
+    });
+}
+
+#[test]
+/// Many threads race to mutate; exactly one mutator runs and everyone sees its result.
+fn race() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+    let calls = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..16 {
+            s.spawn(|| {
+                let val = block_on(lock.get_or_mutate(async |v| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(Duration::from_millis(10));
+                    u32_square(v);
+                }));
+                assert_eq!(val, &U32_MUTATED);
+            });
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+/// `wait` resolves once another thread mutates the cell.
+fn wait() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    thread::scope(|s| {
+        let waiter = s.spawn(|| *block_on(lock.wait()));
+        block_on(lock.get_or_mutate(async |v| u32_square(v)));
+        assert_eq!(waiter.join().unwrap(), U32_MUTATED);
+    });
+}
+
+/// A future driven by hand, recording whether it has been woken since its last poll.
+struct Manual<F> {
+    fut: Pin<Box<F>>,
+    woken: Arc<Woken>,
+}
+
+struct Woken(AtomicBool);
+
+impl ArcWake for Woken {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::Release);
+    }
+}
+
+impl<F: Future> Manual<F> {
+    fn new(fut: F) -> Self {
+        Self {
+            fut: Box::pin(fut),
+            woken: Arc::new(Woken(AtomicBool::new(false))),
+        }
+    }
+
+    fn poll(&mut self) -> Poll<F::Output> {
+        self.woken.0.store(false, Ordering::Release);
+        let waker = task::waker(self.woken.clone());
+        self.fut.as_mut().poll(&mut Context::from_waker(&waker))
+    }
+
+    fn is_woken(&self) -> bool {
+        self.woken.0.load(Ordering::Acquire)
+    }
+}
+
+#[test]
+/// Dropping a future in the middle of its mutator leaves the cell untouched.
+fn cancel_while_mutating() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let mut first = Manual::new(lock.get_or_mutate(async |v| {
+        *v += 1;
+        futures::pending!();
+        u32_square(v);
+    }));
+    assert!(first.poll().is_pending());
+    assert_eq!(lock.get(), None);
+    drop(first);
+
+    assert_eq!(lock.get(), None);
+    // The next mutator sees what the cancelled one left behind.
+    assert_eq!(block_on(lock.get_or_mutate(async |v| *v -= 1)), &U32_INIT);
+}
+
+#[test]
+/// Cancelling the running mutator wakes a queued mutator, which then retries.
+fn cancel_wakes_next_mutator() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let mut first = Manual::new(lock.get_or_mutate(async |_| futures::pending!()));
+    let mut second = Manual::new(lock.get_or_mutate(async |v| u32_square(v)));
+    assert!(first.poll().is_pending());
+    assert!(second.poll().is_pending());
+    assert!(!second.is_woken());
+
+    drop(first);
+    assert!(second.is_woken());
+    assert_eq!(second.poll(), Poll::Ready(&U32_MUTATED));
+}
+
+#[test]
+/// Cancelling a queued mutator does not disturb the others.
+fn cancel_while_queued() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let mut first = Manual::new(lock.get_or_mutate(async |_| futures::pending!()));
+    let mut second = Manual::new(lock.get_or_mutate(async |_| unreachable!()));
+    let mut third = Manual::new(lock.get_or_mutate(async |v| u32_square(v)));
+    assert!(first.poll().is_pending());
+    assert!(second.poll().is_pending());
+    assert!(third.poll().is_pending());
+
+    drop(second);
+    drop(first);
+    assert!(third.is_woken());
+    assert_eq!(third.poll(), Poll::Ready(&U32_MUTATED));
+}
+
+#[test]
+/// A mutator chosen to retry that is cancelled before it runs passes its turn on.
+fn cancel_after_wakeup() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let mut first = Manual::new(lock.get_or_mutate(async |_| futures::pending!()));
+    let mut second = Manual::new(lock.get_or_mutate(async |_| unreachable!()));
+    let mut third = Manual::new(lock.get_or_mutate(async |v| u32_square(v)));
+    assert!(first.poll().is_pending());
+    assert!(second.poll().is_pending());
+    assert!(third.poll().is_pending());
+
+    drop(first);
+    assert!(second.is_woken());
+    assert!(!third.is_woken());
+
+    drop(second);
+    assert!(third.is_woken());
+    assert_eq!(third.poll(), Poll::Ready(&U32_MUTATED));
+}
+
+#[test]
+/// Cancelling a future that only waits has no effect on the cell.
+fn cancel_wait() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let mut waiter = Manual::new(lock.wait());
+    assert!(waiter.poll().is_pending());
+    drop(waiter);
+
+    let mut waiter = Manual::new(lock.wait());
+    assert!(waiter.poll().is_pending());
+    assert_eq!(
+        block_on(lock.get_or_mutate(async |v| u32_square(v))),
+        &U32_MUTATED
+    );
+    assert!(waiter.is_woken());
+    assert_eq!(waiter.poll(), Poll::Ready(&U32_MUTATED));
+}
+
+#[test]
+/// Errors and panics in the mutator wake the next queued mutator like a cancellation does.
+fn error_and_panic_wake_next_mutator() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let mut first = Manual::new(lock.get_or_try_mutate(async |_| {
+        futures::pending!();
+        Err(())
+    }));
+    let mut second = Manual::new(lock.get_or_mutate(async |_| {
+        futures::pending!();
+        panic!("oops");
+    }));
+    let mut third = Manual::new(lock.get_or_mutate(async |v| u32_square(v)));
+    assert!(first.poll().is_pending());
+    assert!(second.poll().is_pending());
+    assert!(third.poll().is_pending());
+
+    assert_eq!(first.poll(), Poll::Ready(Err(())));
+    assert!(second.is_woken());
+    assert!(second.poll().is_pending());
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| second.poll())).is_err());
+    assert!(third.is_woken());
+    assert_eq!(third.poll(), Poll::Ready(&U32_MUTATED));
+}
+
+#[test]
+fn debug_impl() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+    assert_eq!(format!("{lock:?}"), "AsyncInPlaceOnceLock(<untouched>)");
+    block_on(lock.get_or_mutate(async |v| u32_square(v)));
+    assert_eq!(
+        format!("{lock:?}"),
+        format!("AsyncInPlaceOnceLock({U32_MUTATED})")
+    );
+}
+
+#[test]
+/// Test that `AsyncInPlaceOnceLock` is `Sync` and `Send`.
+fn assert_sync_and_send() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+    assert_send::<AsyncInPlaceOnceLock<Vec<u8>>>();
+    assert_sync::<AsyncInPlaceOnceLock<Vec<u8>>>();
+}
+
+#[test]
+/// The `mutations` stream yields locks as they become mutated, each exactly once.
+fn mutations() {
+    use futures::{Stream, StreamExt};
+
+    let locks: Vec<_> = (0..4).map(AsyncInPlaceOnceLock::new).collect();
+    block_on(locks[2].get_or_mutate(async |v| *v += 10));
+
+    let mut stream = AsyncInPlaceOnceLock::mutations(&locks);
+    assert_eq!(stream.size_hint(), (4, Some(4)));
+    assert_eq!(block_on(stream.next()), Some((2, &12)));
+
+    let mut next = Manual::new(stream.next());
+    assert!(next.poll().is_pending());
+    block_on(locks[3].get_or_mutate(async |v| *v += 10));
+    assert!(next.is_woken());
+    assert_eq!(next.poll(), Poll::Ready(Some((3, &13))));
+    drop(next);
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            for lock in &locks {
+                block_on(lock.get_or_mutate(async |v| *v += 10));
+            }
+        });
+
+        let rest: Vec<_> = block_on(stream.by_ref().collect());
+        assert_eq!(rest, [(0, &10), (1, &11)]);
+    });
+    assert_eq!(stream.size_hint(), (0, Some(0)));
+}
+
+#[test]
+/// Locks already mutated when the stream is first polled are yielded in index order.
+fn mutations_already_mutated() {
+    use futures::StreamExt;
+
+    let locks: Vec<_> = (0..100).map(AsyncInPlaceOnceLock::new).collect();
+    for lock in locks.iter().skip(1) {
+        block_on(lock.get_or_mutate(async |v| *v += 1));
+    }
+    let mut stream = AsyncInPlaceOnceLock::mutations(&locks);
+    let first: Vec<_> = block_on(stream.by_ref().take(99).map(|(i, _)| i).collect());
+    assert!(first.into_iter().eq(1..100));
+
+    block_on(locks[0].get_or_mutate(async |v| *v += 1));
+    assert_eq!(block_on(stream.next()), Some((0, &1)));
+    assert_eq!(block_on(stream.next()), None);
+}
+
+#[test]
+/// A deadline that passes while waiting or mutating leaves the cell untouched.
+fn until_deadline() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    block_on(async {
+        assert!(lock.wait_until(async {}).await.is_err());
+        assert!(
+            lock.get_or_mutate_until(async |_| futures::future::pending().await, async {})
+                .await
+                .is_err()
+        );
+        assert_eq!(lock.get(), None);
+
+        assert_eq!(
+            lock.get_or_mutate_until(async |v| u32_square(v), futures::future::pending::<()>())
+                .await,
+            Ok(&U32_MUTATED)
+        );
+        assert_eq!(lock.wait_until(async {}).await, Ok(&U32_MUTATED));
+    });
+}
+
+#[test]
+#[cfg(feature = "futures-timer")]
+/// Test the `Duration`-based timeouts.
+fn timeout() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+    let short = Duration::from_millis(10);
+
+    block_on(async {
+        let err = lock.wait_timeout(short).await.unwrap_err();
+        assert_eq!(
+            std::io::Error::from(err).kind(),
+            std::io::ErrorKind::TimedOut
+        );
+        assert!(
+            lock.get_or_mutate_timeout(async |_| futures::future::pending().await, short)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            lock.get_or_mutate_timeout(async |v| u32_square(v), Duration::from_secs(60))
+                .await,
+            Ok(&U32_MUTATED)
+        );
+        assert_eq!(lock.wait_timeout(short).await, Ok(&U32_MUTATED));
+    });
+}
+
+#[test]
+/// A deadline that passes while another mutation is in progress reports which thread holds it,
+/// and only with `debug-tracking`.
+fn deadline_names_holder() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let err = thread::scope(|s| {
+        thread::Builder::new()
+            .name("loader".into())
+            .spawn_scoped(s, || {
+                block_on(async {
+                    let mut mutating =
+                        pin!(lock.get_or_mutate(async |_| futures::future::pending().await));
+                    assert!(futures::poll!(mutating.as_mut()).is_pending());
+                    lock.wait_until(async {}).await.unwrap_err()
+                })
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+    assert_eq!(lock.get(), None);
+
+    if cfg!(feature = "debug-tracking") {
+        let holder = err.holder.as_ref().unwrap();
+        assert_eq!(holder.thread_name.as_deref(), Some("loader"));
+        assert!(err.to_string().contains("thread `loader`"));
+    } else {
+        assert_eq!(err.holder, None);
+    }
+
+    // Once the mutation ends, nobody holds it.
+    let err = block_on(lock.wait_until(async {})).unwrap_err();
+    assert_eq!(err.holder, None);
+    assert_eq!(
+        err.to_string(),
+        "deadline elapsed before the cell was mutated"
+    );
+}
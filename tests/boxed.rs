@@ -0,0 +1,68 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use in_place_once_cell::InPlaceOnceBox;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test basic functionality of `get` and `get_or_mutate`.
+fn basic() {
+    let cell = InPlaceOnceBox::new(U32_INIT);
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(cell.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(cell.get(), Some(&U32_MUTATED));
+    assert_eq!(cell.into_inner(), U32_MUTATED);
+}
+
+#[test]
+/// A failed mutation leaves the cell unmutated.
+fn try_mutate_err() {
+    let mut cell = InPlaceOnceBox::new(U32_INIT);
+    assert_eq!(cell.get_or_try_mutate(|_| Err("fail")), Err("fail"));
+    assert_eq!(cell.get_mut(), None);
+    assert_eq!(
+        cell.get_or_try_mutate::<_, ()>(|v| {
+            u32_square(v);
+            Ok(())
+        }),
+        Ok(&U32_MUTATED)
+    );
+    *cell.get_mut().unwrap() += 1;
+    assert_eq!(cell.get(), Some(&(U32_MUTATED + 1)));
+}
+
+#[test]
+/// The box is a single pointer.
+fn size() {
+    assert_eq!(size_of::<InPlaceOnceBox<[u8; 64]>>(), size_of::<usize>());
+}
+
+#[test]
+fn debug_impl() {
+    let cell = InPlaceOnceBox::new(U32_INIT);
+    assert_eq!(format!("{cell:?}"), "InPlaceOnceBox(<untouched>)");
+    cell.get_or_mutate(u32_square);
+    assert_eq!(format!("{cell:?}"), "InPlaceOnceBox(1156)");
+}
+
+#[test]
+/// A mutator that mutates its own cell panics, leaving the cell untouched, and formatting the
+/// cell meanwhile does not read the value being mutated.
+fn reentrant() {
+    let cell = InPlaceOnceBox::new(U32_INIT);
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cell.get_or_mutate(|_| {
+            assert_eq!(format!("{cell:?}"), "InPlaceOnceBox(<untouched>)");
+            cell.get_or_mutate(u32_square);
+        });
+    }));
+    assert_eq!(
+        res.unwrap_err().downcast_ref::<&str>(),
+        Some(&"reentrant mutation of an `InPlaceOnceBox`")
+    );
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.get_or_mutate(u32_square), &U32_MUTATED);
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "alloc")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+
+use in_place_once_cell::BTreeOnceMap;
+
+/// An allocator that fails the next allocation of the current thread on demand.
+struct Flaky;
+
+thread_local! {
+    static FAIL_NEXT: Cell<bool> = const { Cell::new(false) };
+}
+
+// SAFETY: forwards to the system allocator, or fails.
+unsafe impl GlobalAlloc for Flaky {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if FAIL_NEXT.replace(false) {
+            return std::ptr::null_mut();
+        }
+        // SAFETY: forwarded as is.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: forwarded as is.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOC: Flaky = Flaky;
+
+#[test]
+/// Test basic functionality
+fn basic() {
+    let map = BTreeOnceMap::<u32, u64>::new(0);
+    assert!(map.is_empty());
+    assert_eq!(map.get(&3), None);
+
+    assert_eq!(map.get_or_mutate(3, |v| *v = 9), &9);
+    assert_eq!(map.get_or_mutate(3, |_| unreachable!()), &9);
+    assert_eq!(map.get(&3), Some(&9));
+    assert_eq!(map.len(), 1);
+    assert_eq!(format!("{map:?}"), "{3: 9}");
+}
+
+#[test]
+/// Test that ranges yield mutated entries only, in key order.
+fn range() {
+    let map = BTreeOnceMap::<u32, u32>::new(0);
+    for key in [5, 1, 4, 2] {
+        map.get_or_mutate(key, |v| *v = key * key);
+    }
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| map.get_or_mutate(3, |_| panic!())));
+
+    let entries: Vec<_> = map.range(2..5).collect();
+    assert_eq!(entries, [(&2, &4), (&4, &16)]);
+    assert_eq!(
+        map.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+        [1, 2, 4, 5]
+    );
+    assert_eq!(map.range(5..=5).count(), 1);
+}
+
+#[test]
+/// Test that mutating while iterating neither panics nor invalidates yielded entries.
+fn mutate_while_iterating() {
+    let map = BTreeOnceMap::<u32, u32>::new(0);
+    map.get_or_mutate(1, |v| *v = 1);
+    map.get_or_mutate(3, |v| *v = 3);
+
+    let mut iter = map.iter();
+    let first = iter.next().unwrap();
+    map.get_or_mutate(0, |v| *v = 0);
+    map.get_or_mutate(2, |v| *v = 2);
+    assert_eq!(iter.collect::<Vec<_>>(), [(&2, &2), (&3, &3)]);
+    assert_eq!(first, (&1, &1));
+}
+
+#[test]
+/// Test that a mutator may mutate other keys.
+fn nested() {
+    let map = BTreeOnceMap::<u32, u32>::new(1);
+    map.get_or_mutate(0, |v| {
+        *v = *map.get_or_mutate(1, |w| *w = 2) + 1;
+    });
+    assert_eq!(map.get(&0), Some(&3));
+    assert_eq!(map.get(&1), Some(&2));
+}
+
+#[test]
+#[should_panic(expected = "reentrant mutation")]
+fn reentrant() {
+    let map = BTreeOnceMap::<u32, u32>::new(1);
+    map.get_or_mutate(0, |_| {
+        assert_eq!(map.get(&0), None);
+        map.get_or_mutate(0, |_| {});
+    });
+}
+
+#[test]
+/// A failed allocation of a new key is reported, and leaves the map unchanged.
+fn try_get_or_mutate() {
+    let map = BTreeOnceMap::<u32, u64>::new(0);
+    FAIL_NEXT.set(true);
+    assert!(map.try_get_or_mutate(3, |_| unreachable!()).is_err());
+    assert_eq!(map.get(&3), None);
+    assert_eq!(format!("{map:?}"), "{}");
+
+    assert_eq!(map.try_get_or_mutate(3, |v| *v = 9), Ok(&9));
+    FAIL_NEXT.set(true);
+    assert_eq!(map.try_get_or_mutate(3, |_| unreachable!()), Ok(&9));
+    FAIL_NEXT.set(false);
+}
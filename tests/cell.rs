@@ -1,4 +1,7 @@
-use in_place_once_cell::InPlaceOnceCell;
+// Some mutators below are spelled out in full, as `*v = *v + 1`.
+#![allow(clippy::assign_op_pattern)]
+
+use in_place_once_cell::{Entry, InPlaceOnceCell, TryUnwrapAll};
 
 #[test]
 /// Test basic functionality
@@ -11,8 +14,227 @@ fn basic() {
     assert_eq!(c.get_or_mutate(|v| *v = *v + 1), &1156);
 }
 
+#[test]
+fn expect_mutated() {
+    let c = InPlaceOnceCell::new(34);
+    c.get_or_mutate(|v| *v *= *v);
+    assert_eq!(c.expect_mutated("squared"), &1156);
+    assert_eq!(c.unwrap_mutated(), &1156);
+}
+
+#[test]
+#[should_panic = "the table is built at startup"]
+fn expect_mutated_untouched() {
+    InPlaceOnceCell::new(34).expect_mutated("the table is built at startup");
+}
+
+#[test]
+#[should_panic = "called `InPlaceOnceCell::unwrap_mutated()` on an untouched cell"]
+fn unwrap_mutated_untouched() {
+    InPlaceOnceCell::new(34).unwrap_mutated();
+}
+
+#[test]
+fn mutate_with_default() {
+    let c = InPlaceOnceCell::new(u32::MAX);
+    assert_eq!(c.mutate_with_default(), &0);
+    assert_eq!(c.get_or_mutate(|v| *v += 1), &0);
+}
+
+#[test]
+fn try_mutate_with_value() {
+    let c = InPlaceOnceCell::new(34);
+    assert_eq!(c.try_mutate_with_value(1156), Ok(&1156));
+    assert_eq!(c.try_mutate_with_value(0), Err((0, &1156)));
+}
+
+#[test]
+fn entry() {
+    let c = InPlaceOnceCell::new(34);
+    let val = match c.entry() {
+        Entry::Untouched(e) => e.mutate(|v| *v *= *v),
+        Entry::Mutated(..) => unreachable!(),
+    };
+    assert_eq!(val, &1156);
+    assert!(matches!(c.entry(), Entry::Mutated(&1156)));
+
+    let c = InPlaceOnceCell::new(34);
+    let Entry::Untouched(e) = c.entry() else {
+        unreachable!()
+    };
+    c.get_or_mutate(|v| *v += 1);
+    assert_eq!(e.mutate_with(0), &35);
+}
+
+#[test]
+fn branded() {
+    let c = InPlaceOnceCell::new(34);
+    c.branded(|c| {
+        assert!(c.proof().is_none());
+        let (val, proof) = c.get_or_mutate(|v| *v *= *v);
+        assert_eq!(val, &1156);
+        assert_eq!(c.get_proven(proof), &1156);
+        assert!(c.proof().is_some());
+    });
+    assert_eq!(size_of::<in_place_once_cell::MutationProof<'_>>(), 0);
+}
+
+#[test]
+fn propagate_to() {
+    let config = InPlaceOnceCell::new(0);
+    let derived = InPlaceOnceCell::new(String::new());
+    assert_eq!(config.propagate_to(&derived, |_, _| unreachable!()), None);
+
+    config.get_or_mutate(|v| *v = 34);
+    let derive = |v: &u32, d: &mut String| *d = v.to_string();
+    assert_eq!(config.propagate_to(&derived, derive).unwrap(), "34");
+    assert_eq!(
+        config
+            .propagate_to(&derived, |_, _| unreachable!())
+            .unwrap(),
+        "34"
+    );
+}
+
+#[test]
+fn fetch_mutate() {
+    let c = InPlaceOnceCell::new(34);
+    assert_eq!(c.fetch_mutate(|v| *v *= *v), (Some(34), &1156));
+    assert_eq!(c.fetch_mutate(|v| *v += 1), (None, &1156));
+}
+
+#[test]
+fn get_or_mutate_status() {
+    let c = InPlaceOnceCell::new(34);
+    assert_eq!(c.get_or_mutate_status(|v| *v *= *v), (&1156, true));
+    assert_eq!(c.get_or_mutate_status(|v| *v += 1), (&1156, false));
+}
+
+#[test]
+/// Test that the value returned by the mutator is passed back only to the caller that ran it.
+fn get_or_mutate_map() {
+    let c = InPlaceOnceCell::new(34);
+    assert_eq!(
+        c.get_or_mutate_map(|v| core::mem::replace(v, 1156)),
+        (Some(34), &1156)
+    );
+    assert_eq!(c.get_or_mutate_map(|v| *v + 1), (None, &1156));
+}
+
+#[test]
+/// Test that cells can be unwrapped in constants.
+fn const_into_inner() {
+    const VALUE: u32 = InPlaceOnceCell::new(34).into_inner();
+    const PARTS: (u32, bool) = InPlaceOnceCell::new(34).into_raw_parts();
+    assert_eq!(VALUE, 34);
+    assert_eq!(PARTS, (34, false));
+    const { assert!(InPlaceOnceCell::new(34).try_into_inner().is_err()) };
+}
+
+#[test]
+/// Only a mutated cell gives up its value; an untouched one is handed back.
+fn try_into_inner() {
+    let c = InPlaceOnceCell::new(34);
+    let c = c.try_into_inner().unwrap_err();
+    c.get_or_mutate(|v| *v = *v * *v);
+    assert_eq!(c.try_into_inner().ok(), Some(1156));
+}
+
 #[test]
 fn size_of_cell() {
     use std::mem;
     assert_eq!(mem::size_of::<InPlaceOnceCell<i32>>(), 8);
 }
+
+#[test]
+/// Arrays of cells are built from a single value, and every cell is mutated independently.
+fn new_array() {
+    let cells: [InPlaceOnceCell<u32>; 4] = const { InPlaceOnceCell::new_array(34) };
+    assert_eq!(cells[1].get_or_mutate(|v| *v = *v * *v), &1156);
+    assert_eq!(cells[0].get(), None);
+    assert_eq!(cells[3].get_or_mutate(|v| *v += 1), &35);
+}
+
+#[test]
+fn array_macro() {
+    use in_place_once_cell::in_place_once_array;
+
+    let cells: [InPlaceOnceCell<String>; 3] = in_place_once_array![String::new(); 3];
+    assert_eq!(cells[2].get_or_mutate(|s| s.push('a')), "a");
+    assert_eq!(cells[0].get(), None);
+
+    #[cfg(feature = "std")]
+    {
+        use in_place_once_cell::InPlaceOnceLock;
+
+        static LOCKS: [InPlaceOnceLock<u32>; 8] =
+            in_place_once_array![InPlaceOnceLock<u32> => 34; 8];
+        assert_eq!(LOCKS[7].get_or_mutate(|v| *v = *v * *v), &1156);
+        assert_eq!(LOCKS[6].get(), None);
+    }
+}
+
+#[test]
+/// Cells survive being taken apart and reassembled, mutated or not.
+fn raw_parts() {
+    let c = InPlaceOnceCell::new(34);
+    let (val, mutated) = c.into_raw_parts();
+    assert_eq!((val, mutated), (34, false));
+
+    let c = unsafe { InPlaceOnceCell::from_raw_parts(val, mutated) };
+    c.get_or_mutate(|v| *v = *v * *v);
+    let (val, mutated) = c.into_raw_parts();
+    assert_eq!((val, mutated), (1156, true));
+
+    let c = unsafe { InPlaceOnceCell::from_raw_parts(val, mutated) };
+    assert_eq!(c.get(), Some(&1156));
+    assert_eq!(c.get_or_mutate(|v| *v += 1), &1156);
+}
+
+#[test]
+/// A batch of cells only unwraps once every cell is mutated.
+fn try_unwrap_all() {
+    let cells: [InPlaceOnceCell<u32>; 3] = InPlaceOnceCell::new_array(34);
+    cells[0].get_or_mutate(|v| *v = *v * *v);
+    let cells = cells.try_unwrap_all().unwrap_err();
+    cells[1].get_or_mutate(|v| *v += 1);
+    cells[2].get_or_mutate(|v| *v += 2);
+    assert_eq!(cells.try_unwrap_all(), Ok([1156, 35, 36]));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+/// A `Vec` of cells only unwraps once every cell is mutated.
+fn try_unwrap_all_vec() {
+    let cells = vec![InPlaceOnceCell::new(34), InPlaceOnceCell::new(35)];
+    cells[1].get_or_mutate(|v| *v += 1);
+    let cells = cells.try_unwrap_all().unwrap_err();
+    cells[0].get_or_mutate(|v| *v += 1);
+    assert_eq!(cells.try_unwrap_all(), Ok(vec![35, 36]));
+}
+
+#[test]
+/// Formatting appends to the placeholder once, and a full fixed-capacity buffer fails cleanly.
+fn mutate_fmt() {
+    let prefix = InPlaceOnceCell::new(String::from("["));
+    let s: &str = prefix.get_or_mutate_fmt(format_args!("{}:{}] ", "worker", 3));
+    assert_eq!(s, "[worker:3] ");
+    assert_eq!(
+        prefix.get_or_mutate_fmt(format_args!("ignored")),
+        "[worker:3] "
+    );
+
+    let key = InPlaceOnceCell::new(heapless::String::<4>::new());
+    assert!(
+        key.get_or_try_mutate_fmt(format_args!("{}", 123_456))
+            .is_err()
+    );
+    assert_eq!(key.get(), None);
+
+    let key = InPlaceOnceCell::new(heapless::String::<4>::new());
+    assert_eq!(
+        key.get_or_try_mutate_fmt(format_args!("{}", 1234))
+            .map(|k| k.as_str()),
+        Ok("1234")
+    );
+}
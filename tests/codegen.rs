@@ -0,0 +1,106 @@
+#![cfg(all(
+    feature = "std",
+    target_arch = "x86_64",
+    target_os = "linux",
+    not(miri)
+))]
+
+//! Guards the code generated for the mutated fast path.
+//!
+//! The test rebuilds this file in release mode with `--emit asm` and checks that each `probe_*`
+//! function below reaches its first `ret` with nothing but a load and a branch: no calls, and
+//! nothing spilled to the stack. Run it with `cargo test --release --test codegen -- --ignored`.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use in_place_once_cell::{InPlaceOnceCell, InPlaceOnceLock};
+
+use common::{U32_INIT, u32_square};
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub fn probe_cell_get_or_mutate(cell: &InPlaceOnceCell<u32>) -> u32 {
+    *cell.get_or_mutate(u32_square)
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub fn probe_lock_get(lock: &InPlaceOnceLock<u32>) -> Option<u32> {
+    lock.get().copied()
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub fn probe_lock_get_or_mutate(lock: &InPlaceOnceLock<u32>) -> u32 {
+    *lock.get_or_mutate(u32_square)
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub fn probe_lock_get_or_mutate_vec(lock: &InPlaceOnceLock<Vec<u32>>) -> usize {
+    lock.get_or_mutate(|val| val.push(U32_INIT)).len()
+}
+
+/// Returns the instructions of `symbol` in `asm`, up to and including the first `ret`.
+fn fast_path<'a>(asm: &'a str, symbol: &str) -> Vec<&'a str> {
+    let start = asm
+        .lines()
+        .position(|line| line == format!("{symbol}:"))
+        .unwrap_or_else(|| panic!("`{symbol}` not found in the assembly"));
+
+    let mut insts = Vec::new();
+    for line in asm.lines().skip(start + 1).map(str::trim) {
+        // Skip directives, labels and comments.
+        if line.is_empty() || line.starts_with(['.', '#']) || line.ends_with(':') {
+            continue;
+        }
+
+        insts.push(line);
+        if line.starts_with("ret") {
+            return insts;
+        }
+    }
+
+    panic!("`{symbol}` has no `ret`");
+}
+
+#[test]
+#[ignore = "rebuilds the crate; run with `--release -- --ignored`"]
+/// Test that the mutated fast path of `get` and `get_or_mutate` is a single load and branch.
+fn fast_path_is_load_and_branch() {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("codegen");
+    let asm_path = target_dir.join("codegen.s");
+    let status = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["rustc", "--release", "--test", "codegen", "--target-dir"])
+        .arg(&target_dir)
+        .args(["--", "-Ccodegen-units=1", "--emit"])
+        .arg(format!("asm={}", asm_path.display()))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let asm = fs::read_to_string(&asm_path).unwrap();
+    for symbol in [
+        "probe_cell_get_or_mutate",
+        "probe_lock_get",
+        "probe_lock_get_or_mutate",
+        "probe_lock_get_or_mutate_vec",
+    ] {
+        let insts = fast_path(&asm, symbol);
+        for inst in &insts {
+            assert!(
+                !inst.starts_with("call") && !inst.starts_with("push") && !inst.contains("%rsp"),
+                "`{symbol}` spills or calls before returning: {insts:#?}",
+            );
+        }
+        assert!(
+            insts.len() <= 6,
+            "`{symbol}` has a long fast path: {insts:#?}"
+        );
+    }
+}
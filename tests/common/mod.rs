@@ -0,0 +1,11 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+pub const U32_INIT: u32 = 34;
+pub const U32_MUTATED: u32 = U32_INIT * U32_INIT;
+
+/// A test mutator
+pub const fn u32_square(v: &mut u32) {
+    *v *= *v;
+}
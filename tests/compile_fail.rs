@@ -0,0 +1,11 @@
+#![cfg(feature = "std")]
+
+//! Pins the guarantees that only the compiler can check: which types are not `Sync` or `Clone`,
+//! and that brands and owners cannot be mixed up. Each case in `tests/compile_fail` must fail to
+//! build with the error recorded next to it; regenerate those with `TRYBUILD=overwrite`.
+
+#[test]
+/// Every case fails to compile, with the recorded error.
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/compile_fail/*.rs");
+}
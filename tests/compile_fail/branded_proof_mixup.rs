@@ -0,0 +1,14 @@
+//! A `MutationProof` of one branded cell cannot vouch for another.
+
+use in_place_once_cell::InPlaceOnceCell;
+
+fn main() {
+    let a = InPlaceOnceCell::new(34_u32);
+    let b = InPlaceOnceCell::new(34_u32);
+    a.branded(|a| {
+        b.branded(|b| {
+            let (_, proof) = a.get_or_mutate(|v| *v += 1);
+            b.get_proven(proof);
+        });
+    });
+}
@@ -0,0 +1,9 @@
+//! `InPlaceOnceCell` checks its flag without synchronization, so it cannot be shared.
+
+use in_place_once_cell::InPlaceOnceCell;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<InPlaceOnceCell<u32>>();
+}
@@ -0,0 +1,10 @@
+//! Sharing an `InPlaceOnceLock<Rc<T>>` would let threads clone the same `Rc`.
+
+use in_place_once_cell::InPlaceOnceLock;
+use std::rc::Rc;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<InPlaceOnceLock<Rc<u32>>>();
+}
@@ -0,0 +1,10 @@
+//! A shared `TakeOnceCell` hands its value to another thread, so the value must be `Send`.
+
+use in_place_once_cell::TakeOnceCell;
+use std::rc::Rc;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<TakeOnceCell<Rc<u32>>>();
+}
@@ -0,0 +1,12 @@
+//! An `InPlaceOnceTCell` is only accessed through the owner of its own marker type.
+
+use in_place_once_cell::{InPlaceOnceTCell, InPlaceOnceTCellOwner};
+
+struct A;
+struct B;
+
+fn main() {
+    let owner = InPlaceOnceTCellOwner::<A>::new();
+    let cell = InPlaceOnceTCell::<B, u32>::new(34);
+    owner.get(&cell);
+}
@@ -0,0 +1,8 @@
+//! There is only ever one `WriteHandle` per lock.
+
+use in_place_once_cell::InPlaceOnceLock;
+
+fn main() {
+    let (writer, _reader) = InPlaceOnceLock::new(34_u32).split();
+    let _other = writer.clone();
+}
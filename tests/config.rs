@@ -0,0 +1,64 @@
+#![cfg(feature = "config")]
+
+use std::error::Error;
+use std::num::ParseIntError;
+use std::path::Path;
+
+use in_place_once_cell::{ConfigError, InPlaceOnceLock};
+
+/// Stores the value of the `name` key of a TOML file.
+fn parse_name(name: &mut String, text: &str) -> Result<(), &'static str> {
+    let line = text.lines().find_map(|line| line.strip_prefix("name = "));
+    *name = line.ok_or("no name")?.trim_matches('"').to_owned();
+    Ok(())
+}
+
+#[test]
+/// Test mutating from an environment variable, which cargo sets for tests.
+fn from_env() {
+    let lock = InPlaceOnceLock::new(String::new());
+    let name = lock.get_or_mutate_from_env("CARGO_PKG_NAME", |name, text| {
+        *name = text.to_owned();
+        Ok::<_, ()>(())
+    });
+    assert_eq!(name.unwrap(), "in_place_once_cell");
+}
+
+#[test]
+/// Test that a missing variable or a failing parser leaves the lock unmutated.
+fn from_env_errors() {
+    let lock = InPlaceOnceLock::new(0u32);
+    let res = lock.get_or_mutate_from_env("IN_PLACE_ONCE_CELL_UNSET", |_, _| Ok::<_, ()>(()));
+    assert!(matches!(res, Err(ConfigError::Env { .. })));
+
+    let res = lock.get_or_mutate_from_env("CARGO_PKG_NAME", |val, text| {
+        *val = text.parse()?;
+        Ok::<_, ParseIntError>(())
+    });
+    let err = res.unwrap_err();
+    assert!(matches!(err, ConfigError::Parse(..)));
+    assert!(err.source().is_some());
+    assert_eq!(lock.get(), None);
+}
+
+#[test]
+/// Test mutating from a file, and that the file is not read again.
+fn from_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let lock = InPlaceOnceLock::new(String::new());
+    assert_eq!(
+        lock.get_or_mutate_from_file(&path, parse_name).unwrap(),
+        "in_place_once_cell"
+    );
+    assert_eq!(
+        lock.get_or_mutate_from_file("does/not/exist", parse_name)
+            .unwrap(),
+        "in_place_once_cell"
+    );
+
+    let lock = InPlaceOnceLock::new(String::new());
+    let err = lock
+        .get_or_mutate_from_file("does/not/exist", parse_name)
+        .unwrap_err();
+    assert!(err.to_string().starts_with("failed to read does/not/exist"));
+}
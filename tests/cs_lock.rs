@@ -0,0 +1,80 @@
+#![cfg(feature = "critical-section")]
+
+mod common;
+
+use in_place_once_cell::InPlaceOnceCsLock;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+/// Another test mutator
+const fn u32_increment(v: &mut u32) {
+    *v = *v + 1;
+}
+
+#[test]
+/// Test basic functionality of `get` and `get_or_mutate`.
+fn basic() {
+    let lock = InPlaceOnceCsLock::new(U32_INIT);
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(lock.get_or_mutate(u32_increment), &U32_MUTATED);
+}
+
+#[test]
+/// Many threads race to mutate; the critical section lets exactly one mutator run.
+fn race() {
+    let lock = InPlaceOnceCsLock::new(U32_INIT);
+
+    thread::scope(|s| {
+        for _ in 0..16 {
+            s.spawn(|| assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED));
+        }
+    });
+}
+
+#[test]
+/// Failed and panicking mutations leave the cell unmutated.
+fn failed_mutation() {
+    let lock = InPlaceOnceCsLock::new(U32_INIT);
+    assert_eq!(lock.get_or_try_mutate(|_| Err(())), Err(()));
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| lock.get_or_mutate(|_| panic!()))).is_err());
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+}
+
+#[test]
+#[should_panic = "reentrant mutation"]
+/// Reentrant mutation panics instead of handing out a second `&mut`.
+fn reentrant() {
+    let lock = InPlaceOnceCsLock::new(U32_INIT);
+    lock.get_or_mutate(|_| {
+        lock.get_or_mutate(u32_square);
+    });
+}
+
+#[test]
+fn debug_impl() {
+    let lock = InPlaceOnceCsLock::new(U32_INIT);
+    assert_eq!(format!("{lock:?}"), "InPlaceOnceCsLock(<untouched>)");
+    lock.get_or_mutate(u32_square);
+    assert_eq!(
+        format!("{lock:?}"),
+        format!("InPlaceOnceCsLock({U32_MUTATED})")
+    );
+}
+
+#[test]
+fn raw_parts() {
+    let lock = InPlaceOnceCsLock::new(U32_INIT);
+    lock.get_or_mutate(u32_square);
+    let (val, mutated) = lock.into_raw_parts();
+    assert_eq!((val, mutated), (U32_MUTATED, true));
+
+    let lock = unsafe { InPlaceOnceCsLock::from_raw_parts(val, mutated) };
+    assert_eq!(lock.get_or_mutate(u32_increment), &U32_MUTATED);
+
+    let lock = unsafe { InPlaceOnceCsLock::from_raw_parts(U32_INIT, false) };
+    assert_eq!(lock.get(), None);
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use in_place_once_cell::InPlaceOnceCell;
+
+use common::{U32_INIT, u32_square};
+
+#[test]
+/// The alternate form prints the state along with the value.
+fn cell_alternate() {
+    let c = InPlaceOnceCell::new(U32_INIT);
+    assert_eq!(format!("{c:?}"), "InPlaceOnceCell(34 <untouched>)");
+    assert_eq!(
+        format!("{c:#?}"),
+        "InPlaceOnceCell {\n    state: Untouched,\n    value: 34,\n}"
+    );
+
+    c.get_or_mutate(u32_square);
+    assert_eq!(format!("{c:?}"), "InPlaceOnceCell(1156)");
+    assert_eq!(
+        format!("{c:#?}"),
+        "InPlaceOnceCell {\n    state: Mutated,\n    value: 1156,\n}"
+    );
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+/// An in-progress mutation is told apart from an untouched lock.
+fn mutating() {
+    use in_place_once_cell::InPlaceOnceCsLock;
+
+    let lock = InPlaceOnceCsLock::new(U32_INIT);
+    assert_eq!(
+        format!("{lock:#?}"),
+        "InPlaceOnceCsLock {\n    state: Untouched,\n}"
+    );
+    lock.get_or_mutate(|v| {
+        assert_eq!(
+            format!("{lock:#?}"),
+            "InPlaceOnceCsLock {\n    state: Mutating,\n}"
+        );
+        u32_square(v);
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+/// The state of a lock tells an in-progress mutation and a poisoned lock from an untouched one.
+fn lock_state() {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use in_place_once_cell::{InPlaceOnceLock, LockState};
+
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.state(), LockState::Untouched);
+    lock.get_or_mutate(|v| {
+        assert_eq!(lock.state(), LockState::Mutating);
+        assert!(format!("{lock:#?}").contains("state: Mutating"));
+        u32_square(v);
+    });
+    assert_eq!(lock.state(), LockState::Mutated);
+
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| lock.get_or_mutate(|_| panic!())));
+    assert!(res.is_err());
+    assert_eq!(lock.state(), LockState::Poisoned);
+}
+
+#[cfg(feature = "debug-tracking")]
+#[test]
+/// The lock records where and by which thread it was mutated, and whether its mutator panicked.
+fn tracking() {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::thread;
+
+    use in_place_once_cell::InPlaceOnceLock;
+
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    lock.get_or_mutate(|v| {
+        assert!(format!("{lock:#?}").contains("state: Mutating"));
+        u32_square(v);
+    });
+    let line = line!() - 4;
+
+    let debug = format!("{lock:#?}");
+    assert!(debug.contains("state: Mutated"));
+    assert!(
+        debug.contains(&format!("mutated_at: {}:{line}:", file!())),
+        "{debug}"
+    );
+    assert!(debug.contains(&format!("mutated_by: {:?}", thread::current().id())));
+
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| lock.get_or_mutate(|_| panic!())));
+    assert!(res.is_err());
+    assert!(format!("{lock:#?}").contains("state: Poisoned"));
+}
+
+#[cfg(feature = "debug-tracking")]
+#[test]
+/// A strict lock names where it was mutated when it rejects a second mutator.
+fn strict_names_origin() {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use in_place_once_cell::InPlaceOnceLock;
+
+    let lock = InPlaceOnceLock::new(U32_INIT).with_strict();
+    lock.get_or_mutate(u32_square);
+    let line = line!() - 1;
+
+    let payload =
+        panic::catch_unwind(AssertUnwindSafe(|| lock.get_or_mutate(u32_square))).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains(&format!("already mutated at {}:{line}:", file!())),
+        "{message}"
+    );
+}
+
+#[cfg(feature = "debug-tracking")]
+#[test]
+/// Mutators that lose the race are replayed on the placeholder, and must agree with the winner.
+fn checked_divergence() {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use in_place_once_cell::InPlaceOnceLock;
+
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(*lock.get_or_mutate_checked(u32_square), U32_INIT * U32_INIT);
+    assert_eq!(
+        *lock.get_or_mutate_checked(|v| *v *= U32_INIT),
+        U32_INIT * U32_INIT
+    );
+
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| {
+        lock.get_or_mutate_checked(|v| *v += 1);
+    }))
+    .unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains("diverged from the one that won at"),
+        "{message}"
+    );
+}
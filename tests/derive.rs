@@ -0,0 +1,80 @@
+#![cfg(feature = "derive")]
+
+use in_place_once_cell::{InPlaceOnceFields, OnceBuilder};
+
+#[derive(Debug, PartialEq, InPlaceOnceFields)]
+struct Config {
+    port: u16,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, InPlaceOnceFields)]
+struct Pair<A, B: Clone> {
+    pub a: A,
+    pub b: B,
+}
+
+#[test]
+/// Each field is mutated independently, and `finish` only succeeds once all of them are.
+fn once_fields() {
+    let fields = ConfigOnceFields::new(Config {
+        port: 80,
+        name: String::new(),
+    });
+    assert_eq!(fields.get_port(), None);
+    assert_eq!(fields.mutate_port(|p| *p += 8000), &8080);
+    assert_eq!(fields.mutate_port(|p| *p += 1), &8080);
+    assert_eq!(fields.get_port(), Some(&8080));
+    assert!(!fields.is_complete());
+
+    let fields = fields.finish().unwrap_err();
+    fields.mutate_name(|n| n.push_str("server"));
+    assert!(fields.is_complete());
+    assert_eq!(
+        fields.finish().ok(),
+        Some(Config {
+            port: 8080,
+            name: "server".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn generics() {
+    let fields = PairOnceFields::from(Pair { a: 1u8, b: 'b' });
+    fields.mutate_a(|a| *a += 1);
+    fields.mutate_b(|b| *b = 'c');
+    assert_eq!(fields.finish().ok(), Some(Pair { a: 2, b: 'c' }));
+}
+
+#[derive(Debug, PartialEq, OnceBuilder)]
+struct Request {
+    method: &'static str,
+    retries: u32,
+}
+
+#[test]
+/// Every setter succeeds only once, and `build` only succeeds once every field is set.
+fn once_builder() {
+    let builder = Request::builder();
+    assert_eq!(builder.method("GET").map(|_| ()), Ok(()));
+    assert_eq!(builder.method("POST").map(|_| ()), Err("POST"));
+    assert!(!builder.is_complete());
+
+    let builder = builder.build().unwrap_err();
+    builder.retries(3).unwrap();
+    assert_eq!(
+        builder.build().ok(),
+        Some(Request {
+            method: "GET",
+            retries: 3,
+        })
+    );
+}
+
+#[test]
+fn once_builder_chained() {
+    let builder = RequestBuilder::default();
+    builder.retries(1).unwrap().method("PUT").unwrap();
+    assert_eq!(builder.build().map(|r| r.method).ok(), Some("PUT"));
+}
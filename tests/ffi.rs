@@ -0,0 +1,86 @@
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+use in_place_once_cell::ffi::{ipoc_free, ipoc_get, ipoc_get_or_mutate, ipoc_new, ipoc_size};
+
+use common::{U32_INIT, U32_MUTATED};
+
+unsafe extern "C" fn square(value: *mut c_void, calls: *mut c_void) {
+    unsafe {
+        (*calls.cast::<AtomicU32>()).fetch_add(1, Ordering::Relaxed);
+        let value = value.cast::<u32>();
+        *value *= *value;
+    }
+}
+
+#[test]
+/// The C callback runs exactly once, and the value is readable through the returned pointer.
+fn basic() {
+    let calls = AtomicU32::new(0);
+    let calls_ptr = ptr::from_ref(&calls).cast_mut().cast();
+    unsafe {
+        let lock = ipoc_new(ptr::from_ref(&U32_INIT).cast(), size_of::<u32>());
+        assert_eq!(ipoc_size(lock), size_of::<u32>());
+        assert!(ipoc_get(lock).is_null());
+
+        let val = ipoc_get_or_mutate(lock, square, calls_ptr);
+        assert_eq!(*val.cast::<u32>(), U32_MUTATED);
+        assert_eq!(ipoc_get(lock), val);
+        assert_eq!(ipoc_get_or_mutate(lock, square, calls_ptr), val);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        ipoc_free(lock);
+        ipoc_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn zeroed() {
+    unsafe extern "C" fn set(value: *mut c_void, _: *mut c_void) {
+        unsafe {
+            assert_eq!(*value.cast::<[u64; 3]>(), [0; 3]);
+            value.cast::<[u64; 3]>().write([1, 2, 3]);
+        }
+    }
+
+    unsafe {
+        let lock = ipoc_new(ptr::null(), 24);
+        let val = ipoc_get_or_mutate(lock, set, ptr::null_mut());
+        assert_eq!(val.addr() % 16, 0);
+        assert_eq!(*val.cast::<[u64; 3]>(), [1, 2, 3]);
+        ipoc_free(lock);
+    }
+}
+
+#[test]
+fn concurrent() {
+    struct Lock(*mut in_place_once_cell::ffi::IpocLock);
+    unsafe impl Sync for Lock {}
+
+    impl Lock {
+        fn ptr(&self) -> *mut in_place_once_cell::ffi::IpocLock {
+            self.0
+        }
+    }
+
+    let calls = AtomicU32::new(0);
+    let lock = Lock(unsafe { ipoc_new(ptr::from_ref(&U32_INIT).cast(), size_of::<u32>()) });
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| unsafe {
+                let calls_ptr = ptr::from_ref(&calls).cast_mut().cast();
+                let val = ipoc_get_or_mutate(lock.ptr(), square, calls_ptr);
+                assert_eq!(*val.cast::<u32>(), U32_MUTATED);
+            });
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    unsafe { ipoc_free(lock.0) };
+}
@@ -0,0 +1,69 @@
+#![cfg(all(unix, feature = "fork"))]
+
+mod common;
+
+use std::sync::mpsc;
+use std::thread;
+
+use in_place_once_cell::InPlaceOnceLock;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+/// Forks, runs `child` in the child, and returns whether it succeeded.
+fn fork_and_check(child: impl FnOnce() -> bool) -> bool {
+    unsafe {
+        let pid = libc::fork();
+        assert!(pid >= 0);
+        if pid == 0 {
+            libc::_exit(if child() { 0 } else { 1 });
+        }
+
+        let mut status = 0;
+        assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+        libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "miri does not support fork")]
+/// Test that a lock mutated by another thread at the time of the fork is untouched in the child,
+/// and mutated as usual in the parent.
+fn mutating_in_other_thread() {
+    static LOCK: InPlaceOnceLock<u32> = InPlaceOnceLock::new(U32_INIT);
+    LOCK.reset_on_fork();
+
+    let (started_tx, started_rx) = mpsc::channel();
+    let (finish_tx, finish_rx) = mpsc::channel::<()>();
+    let mutator = thread::spawn(move || {
+        LOCK.get_or_mutate(|v| {
+            started_tx.send(()).unwrap();
+            finish_rx.recv().unwrap();
+            u32_square(v);
+        });
+    });
+    started_rx.recv().unwrap();
+
+    assert!(fork_and_check(|| {
+        LOCK.get().is_none() && *LOCK.get_or_mutate(|v| *v += 1) == U32_INIT + 1
+    }));
+
+    finish_tx.send(()).unwrap();
+    mutator.join().unwrap();
+    assert_eq!(LOCK.get(), Some(&U32_MUTATED));
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "miri does not support fork")]
+/// Test that a lock mutated by the forking thread itself keeps being mutated in the child.
+fn mutating_in_forking_thread() {
+    static LOCK: InPlaceOnceLock<u32> = InPlaceOnceLock::new(U32_INIT);
+    LOCK.reset_on_fork();
+    // Registering twice must not deadlock the handlers.
+    LOCK.reset_on_fork();
+
+    LOCK.get_or_mutate(|v| {
+        assert!(fork_and_check(|| LOCK.get().is_none()));
+        u32_square(v);
+    });
+    assert_eq!(LOCK.get(), Some(&U32_MUTATED));
+}
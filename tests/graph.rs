@@ -0,0 +1,129 @@
+#![cfg(feature = "alloc")]
+
+use std::panic::{self, AssertUnwindSafe};
+
+use in_place_once_cell::OnceGraph;
+
+#[test]
+/// Dependencies are mutated first, each exactly once, whatever order they were declared in.
+fn resolve_order() {
+    let mut graph = OnceGraph::new();
+    let top = graph.add_node(0);
+    let left = graph.add_node(1);
+    let right = graph.add_node(2);
+    let base = graph.add_node(10);
+    graph.add_dependency(top, left);
+    graph.add_dependency(top, right);
+    graph.add_dependency(left, base);
+    graph.add_dependency(right, base);
+    assert_eq!(graph.dependencies(top), [left, right]);
+
+    let mut order = Vec::new();
+    let val = graph.resolve(top, |id, val, deps| {
+        order.push(id);
+        *val += deps.iter().sum::<u32>();
+    });
+    assert_eq!(val, Ok(&23));
+    assert_eq!(order, [base, left, right, top]);
+    assert_eq!(graph.get(left), Some(&11));
+    assert_eq!(graph.resolve(top, |_, _, _| unreachable!()), Ok(&23));
+}
+
+#[test]
+/// A cycle is reported before any node on it is mutated.
+fn cycle() {
+    let mut graph = OnceGraph::new();
+    let a = graph.add_node(0u32);
+    let b = graph.add_node(0);
+    let c = graph.add_node(0);
+    graph.add_dependency(a, b);
+    graph.add_dependency(b, c);
+    graph.add_dependency(c, b);
+
+    let err = graph.resolve(a, |_, _, _| unreachable!()).unwrap_err();
+    assert_eq!(err.cycle, [b, c, b]);
+    assert_eq!(err.to_string(), "dependency cycle: 1 -> 2 -> 1");
+    // Resolving fails the same way again, rather than seeing leftover state.
+    assert!(graph.resolve(b, |_, _, _| unreachable!()).is_err());
+    assert_eq!(graph.get(c), None);
+}
+
+#[test]
+/// Dependencies resolved before a cycle is reached stay mutated, while the cycle and the nodes
+/// depending on it do not.
+fn cycle_after_resolved_dependency() {
+    let mut graph = OnceGraph::new();
+    let a = graph.add_node(0u32);
+    let b = graph.add_node(0);
+    let c = graph.add_node(0);
+    let d = graph.add_node(0);
+    graph.add_dependency(a, b);
+    graph.add_dependency(a, c);
+    graph.add_dependency(c, d);
+    graph.add_dependency(d, c);
+
+    let err = graph.resolve(a, |_, v, _| *v = 1).unwrap_err();
+    assert_eq!(err.cycle, [c, d, c]);
+    assert_eq!(graph.get(b), Some(&1));
+    assert_eq!([a, c, d].map(|node| graph.get(node)), [None; 3]);
+}
+
+#[test]
+/// A node is resolved after a mutator that panicked midway.
+fn panic_midway() {
+    let mut graph = OnceGraph::new();
+    let a = graph.add_node(0u32);
+    let b = graph.add_node(1);
+    graph.add_dependency(a, b);
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        graph.resolve(a, |id, _, _| assert_ne!(id, a)).ok()
+    }));
+    assert!(res.is_err());
+    assert_eq!(graph.get(b), Some(&1));
+    assert_eq!(graph.resolve(a, |_, val, deps| *val = deps[0] + 1), Ok(&2));
+}
+
+#[test]
+/// Resolving a node from its own mutator is reported as a cycle.
+fn reentrant() {
+    let mut graph = OnceGraph::new();
+    let a = graph.add_node(0u32);
+    let val = graph.resolve(a, |id, val, _| {
+        assert!(graph.resolve(id, |_, _, _| unreachable!()).is_err());
+        *val = 1;
+    });
+    assert_eq!(val, Ok(&1));
+}
+
+#[test]
+/// The fallible counterparts add nodes and dependencies like the others, and report a capacity
+/// that cannot be allocated instead of aborting.
+fn try_alloc() {
+    let mut graph = OnceGraph::new();
+    assert!(graph.try_reserve(usize::MAX).is_err());
+    assert!(graph.is_empty());
+
+    graph.try_reserve(2).unwrap();
+    let top = graph.try_add_node(1).unwrap();
+    let base = graph.try_add_node(2).unwrap();
+    graph.try_add_dependency(top, base).unwrap();
+    assert_eq!(graph.dependencies(top), [base]);
+    assert_eq!(
+        graph.resolve(top, |_, v, deps| *v += deps.iter().sum::<i32>()),
+        Ok(&3)
+    );
+}
+
+#[test]
+#[should_panic(expected = "node not in graph")]
+/// A node from a larger graph is rejected rather than indexing out of bounds.
+fn foreign_node() {
+    let mut other = OnceGraph::new();
+    other.add_node(0u32);
+    let foreign = other.add_node(0u32);
+
+    let mut graph = OnceGraph::new();
+    let a = graph.add_node(0u32);
+    graph.try_add_dependency(foreign, a).unwrap();
+}
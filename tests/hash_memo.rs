@@ -0,0 +1,92 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use in_place_once_cell::HashMemoCell;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash, Hasher};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+thread_local! {
+    static HASHED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A key that counts how many times it was hashed.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Counted(Vec<u8>);
+
+impl Hash for Counted {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        HASHED.set(HASHED.get() + 1);
+        self.0.hash(state);
+    }
+}
+
+#[test]
+/// The hash is computed once, at mutation time, with the cell's hasher.
+fn precomputed_hash() {
+    let cell = HashMemoCell::new(U32_INIT);
+    assert_eq!(cell.precomputed_hash(), None);
+    assert_eq!(*cell.get_or_mutate(u32_square), U32_MUTATED);
+    assert_eq!(
+        cell.precomputed_hash(),
+        Some(BuildHasherDefault::<DefaultHasher>::default().hash_one(U32_MUTATED))
+    );
+    assert_eq!(cell.into_inner(), U32_MUTATED);
+}
+
+#[test]
+/// A failed mutation leaves the cell unmutated and unhashed.
+fn try_mutate() {
+    let cell = HashMemoCell::new(U32_INIT);
+    assert_eq!(cell.get_or_try_mutate(|_| Err("fail")), Err("fail"));
+    assert_eq!(cell.precomputed_hash(), None);
+    assert_eq!(cell.get(), None);
+}
+
+#[test]
+// The hash and equality of a mutated cell never change, which is all a key needs.
+#[allow(clippy::mutable_key_type)]
+/// Looking the key up in a set does not hash the value again.
+fn no_rehash() {
+    HASHED.set(0);
+    let key = HashMemoCell::new(Counted::default());
+    key.get_or_mutate(|v| v.0.resize(1 << 20, 7));
+    assert_eq!(HASHED.get(), 1);
+
+    let mut set = HashSet::new();
+    set.insert(key);
+    for _ in 0..10 {
+        let probe = HashMemoCell::new(Counted::default());
+        assert!(!set.contains(&probe));
+    }
+    assert_eq!(HASHED.get(), 1);
+}
+
+#[test]
+#[allow(clippy::mutable_key_type)]
+/// Equal values compare and hash equal; untouched cells are equal to each other.
+fn eq() {
+    let a = HashMemoCell::new(U32_INIT);
+    let b = HashMemoCell::new(U32_MUTATED);
+    assert_eq!(a, HashMemoCell::new(0));
+    a.get_or_mutate(u32_square);
+    assert_ne!(a, b);
+    b.get_or_mutate(|_| {});
+    assert_eq!(a, b);
+
+    let mut set = HashSet::new();
+    assert!(set.insert(a));
+    assert!(!set.insert(b));
+}
+
+#[test]
+/// The alternate form prints the hash once the cell is mutated.
+fn debug() {
+    let cell = HashMemoCell::new(U32_INIT);
+    assert!(!format!("{cell:#?}").contains("hash"));
+    cell.get_or_mutate(u32_square);
+    assert!(format!("{cell:#?}").contains("hash"));
+}
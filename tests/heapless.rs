@@ -0,0 +1,51 @@
+#![cfg(feature = "heapless")]
+
+mod common;
+
+use in_place_once_cell::InPlaceOnceVec;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that cells are pushed up to the capacity, and mutated independently.
+fn push_and_mutate() {
+    let mut v = InPlaceOnceVec::<u32, 2>::new();
+    assert_eq!(v.capacity(), 2);
+    assert!(v.is_empty());
+    assert_eq!(v.push(U32_INIT), Ok(()));
+    assert_eq!(v.push(U32_INIT), Ok(()));
+    assert_eq!(v.push(U32_INIT), Err(U32_INIT));
+    assert_eq!(v.len(), 2);
+
+    assert_eq!(v[1].get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(v[0].get(), None);
+    assert_eq!(v[1].get_or_mutate(|_| unreachable!()), &U32_MUTATED);
+    assert_eq!(
+        format!("{v:?}"),
+        "[InPlaceOnceCell(34 <untouched>), InPlaceOnceCell(1156)]"
+    );
+
+    assert_eq!(v.pop().unwrap().get(), Some(&U32_MUTATED));
+    assert_eq!(&v.into_inner()[..], [U32_INIT]);
+}
+
+#[test]
+/// Test that a `heapless::Vec` of values becomes a vector of untouched cells.
+fn from_heapless() {
+    let values = heapless::Vec::<u32, 4>::from_slice(&[U32_INIT, U32_MUTATED]).unwrap();
+    let v = InPlaceOnceVec::from(values);
+    assert_eq!(v.len(), 2);
+    assert!(v.iter().all(|c| c.get().is_none()));
+}
+
+#[test]
+/// Test that the values only unwrap once every cell is mutated.
+fn try_unwrap_all() {
+    let mut v = InPlaceOnceVec::<u32, 2>::new();
+    v.push(U32_INIT).unwrap();
+    v.push(U32_INIT).unwrap();
+    v[0].get_or_mutate(u32_square);
+    let v = v.try_unwrap_all().unwrap_err();
+    v[1].get_or_mutate(u32_square);
+    assert_eq!(&v.try_unwrap_all().unwrap()[..], [U32_MUTATED; 2]);
+}
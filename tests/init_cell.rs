@@ -0,0 +1,51 @@
+mod common;
+
+use in_place_once_cell::{InPlaceOnceInitCell, InitToken};
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that only the token holder mutates, and that other threads then read the value.
+fn init_then_read() {
+    static CELL: InPlaceOnceInitCell<u32> = InPlaceOnceInitCell::new(U32_INIT);
+
+    // SAFETY: no other thread creates a token.
+    let token = unsafe { InitToken::new() };
+    assert_eq!(CELL.get(), None);
+    assert_eq!(CELL.get_or_mutate(&token, u32_square), &U32_MUTATED);
+    assert_eq!(CELL.get_or_mutate(&token, |_| unreachable!()), &U32_MUTATED);
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(CELL.expect_mutated("mutated in init"), &U32_MUTATED));
+        }
+    });
+}
+
+#[test]
+/// Failed and panicking mutations leave the cell unmutated.
+fn failed_mutation() {
+    // SAFETY: no other thread creates a token.
+    let token = unsafe { InitToken::new() };
+    let cell = InPlaceOnceInitCell::new(U32_INIT);
+    assert_eq!(cell.get_or_try_mutate(&token, |_| Err(())), Err(()));
+    assert!(
+        panic::catch_unwind(AssertUnwindSafe(|| cell.get_or_mutate(&token, |_| panic!()))).is_err()
+    );
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.get_or_mutate(&token, u32_square), &U32_MUTATED);
+}
+
+#[test]
+#[should_panic = "reentrant mutation"]
+/// Reentrant mutation panics instead of handing out a second `&mut`.
+fn reentrant() {
+    // SAFETY: no other thread creates a token.
+    let token = unsafe { InitToken::new() };
+    let cell = InPlaceOnceInitCell::new(U32_INIT);
+    cell.get_or_mutate(&token, |_| {
+        cell.get_or_mutate(&token, u32_square);
+    });
+}
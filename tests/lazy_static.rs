@@ -0,0 +1,49 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+use in_place_once_cell::in_place_lazy_static;
+
+use common::{U32_INIT, U32_MUTATED};
+
+static CALLS: AtomicU32 = AtomicU32::new(0);
+
+in_place_lazy_static! {
+    /// A squared value.
+    static ref SQUARE: u32 = U32_INIT; mutate |v| {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        *v *= *v;
+    };
+    pub(crate) static ref NAMES: Vec<&'static str> = Vec::new(); mutate |names| names.push("a");
+}
+
+#[test]
+/// The mutator runs exactly once, even when the static is dereferenced from many threads.
+fn mutated_once() {
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| assert_eq!(*SQUARE, U32_MUTATED));
+        }
+    });
+
+    assert_eq!(*SQUARE, U32_MUTATED);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn deref_methods() {
+    assert_eq!(NAMES.len(), 1);
+    assert_eq!(NAMES.first(), Some(&"a"));
+}
+
+#[cfg(feature = "stable_deref_trait")]
+#[test]
+/// The declared types prove that their value never moves.
+fn stable_deref() {
+    const fn assert_stable_deref<T: stable_deref_trait::StableDeref>(_: &T) {}
+    assert_stable_deref(&SQUARE);
+    assert_stable_deref(&NAMES);
+}
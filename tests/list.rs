@@ -0,0 +1,60 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use in_place_once_cell::OnceList;
+use std::thread;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that pushed locks are mutated later, and only mutated ones are iterated over.
+fn push_then_mutate() {
+    let list = OnceList::new();
+    assert!(list.is_empty());
+    let first = list.push(U32_INIT);
+    let second = list.push(U32_INIT);
+    assert!(!list.is_empty());
+    assert_eq!(list.iter().count(), 2);
+    assert_eq!(list.iter_mutated().count(), 0);
+
+    first.get_or_mutate(u32_square);
+    assert_eq!(list.iter_mutated().collect::<Vec<_>>(), [&U32_MUTATED]);
+    second.get_or_mutate(|val| *val += 1);
+    assert_eq!(
+        list.iter_mutated().collect::<Vec<_>>(),
+        [&(U32_INIT + 1), &U32_MUTATED]
+    );
+}
+
+#[test]
+/// Test that threads push and mutate concurrently, without losing a node.
+fn concurrent_push() {
+    let list = OnceList::new();
+    thread::scope(|s| {
+        for i in 0..8 {
+            let list = &list;
+            s.spawn(move || {
+                for j in 0..100 {
+                    let lock = list.push(0);
+                    lock.get_or_mutate(|val| *val = i * 100 + j);
+                }
+            });
+            s.spawn(|| list.iter_mutated().count());
+        }
+    });
+
+    let mut values: Vec<u32> = list.iter_mutated().copied().collect();
+    values.sort_unstable();
+    assert_eq!(values, (0..800).collect::<Vec<_>>());
+}
+
+#[test]
+fn drop_values() {
+    let list = OnceList::new();
+    let value = std::sync::Arc::new(());
+    list.push(value.clone());
+    list.push(value.clone());
+    drop(list);
+    assert_eq!(std::sync::Arc::strong_count(&value), 1);
+}
@@ -1,14 +1,13 @@
-use in_place_once_cell::InPlaceOnceLock;
+#![cfg(feature = "std")]
+
+mod common;
+
+use in_place_once_cell::{Entry, InPlaceOnceLock, mutate_both};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
-const U32_INIT: u32 = 34;
-const U32_MUTATED: u32 = U32_INIT * U32_INIT;
+use common::{U32_INIT, U32_MUTATED, u32_square};
 
-/// A test mutator
-const fn u32_square(v: &mut u32) {
-    *v = *v * *v;
-}
 /// Another test mutator
 const fn u32_increment(v: &mut u32) {
     *v = *v + 1;
@@ -23,6 +22,210 @@ fn basic_single() {
     assert_eq!(lock.get_or_mutate(u32_increment), &U32_MUTATED);
 }
 
+#[test]
+/// Test that exactly one of many racing callers reports having mutated the lock.
+fn get_or_mutate_status() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let mutators = thread::scope(|s| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| s.spawn(|| lock.get_or_mutate_status(u32_square)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .inspect(|&(val, _)| assert_eq!(val, &U32_MUTATED))
+            .filter(|&(_, mutated)| mutated)
+            .count()
+    });
+    assert_eq!(mutators, 1);
+}
+
+#[test]
+/// Test that exactly one of many racing callers gets the value returned by the mutator.
+fn get_or_mutate_map() {
+    let lock = &InPlaceOnceLock::new(U32_INIT);
+    let returned: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                s.spawn(move || {
+                    lock.get_or_mutate_map(|v| {
+                        u32_square(v);
+                        i
+                    })
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .inspect(|&(_, val)| assert_eq!(val, &U32_MUTATED))
+            .filter_map(|(ret, _)| ret)
+            .collect()
+    });
+    assert_eq!(returned.len(), 1);
+}
+
+#[test]
+#[should_panic = "called `InPlaceOnceLock::unwrap_mutated()` on an untouched cell"]
+fn unwrap_mutated_untouched() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(
+        lock.get_or_mutate(u32_square),
+        lock.expect_mutated("squared")
+    );
+    InPlaceOnceLock::new(U32_INIT).unwrap_mutated();
+}
+
+#[test]
+fn mutate_with_default() {
+    let lock = InPlaceOnceLock::new(vec![U32_INIT]);
+    assert!(lock.mutate_with_default().is_empty());
+    assert!(lock.get_or_mutate(|v| v.push(U32_INIT)).is_empty());
+}
+
+#[test]
+/// Test that two locks are mutated together, seeing each other's placeholders.
+fn mutate_both_pair() {
+    let a = InPlaceOnceLock::new(U32_INIT);
+    let b = InPlaceOnceLock::new(vec![U32_INIT]);
+    let res = mutate_both(&a, &b, |a, b| {
+        b.push(*a);
+        u32_square(a);
+    });
+    assert_eq!(res, Some((&U32_MUTATED, &vec![U32_INIT, U32_INIT])));
+    assert_eq!(mutate_both(&a, &b, |_, _| unreachable!()), res);
+
+    let c = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(mutate_both(&a, &c, |_, _| unreachable!()), None);
+    assert_eq!(c.get(), None);
+}
+
+#[test]
+/// Test that mutating the same pair in opposite orders from many threads does not deadlock.
+fn mutate_both_opposite_orders() {
+    for _ in 0..100 {
+        let a = InPlaceOnceLock::new(U32_INIT);
+        let b = InPlaceOnceLock::new(U32_INIT);
+        let runs = std::sync::atomic::AtomicUsize::new(0);
+        thread::scope(|s| {
+            for i in 0..8 {
+                let (a, b, runs) = (&a, &b, &runs);
+                s.spawn(move || {
+                    let f = |x: &mut u32, y: &mut u32| {
+                        runs.fetch_add(1, Ordering::Relaxed);
+                        u32_square(x);
+                        u32_square(y);
+                    };
+                    if i % 2 == 0 {
+                        mutate_both(a, b, f);
+                    } else {
+                        mutate_both(b, a, f);
+                    }
+                });
+            }
+        });
+        assert_eq!(runs.into_inner(), 1);
+        assert_eq!((a.get(), b.get()), (Some(&U32_MUTATED), Some(&U32_MUTATED)));
+    }
+}
+
+#[test]
+#[should_panic(expected = "same lock twice")]
+fn mutate_both_same_lock() {
+    let a = InPlaceOnceLock::new(U32_INIT);
+    mutate_both(&a, &a, |_, _| {});
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "reentrant mutation of an `InPlaceOnceLock`")]
+/// Test that mutating either lock from the mutator of `mutate_both` panics instead of deadlocking.
+fn mutate_both_reentrant() {
+    let a = InPlaceOnceLock::new(U32_INIT);
+    let b = InPlaceOnceLock::new(U32_INIT);
+    mutate_both(&a, &b, |_, val| {
+        *val = *b.get_or_mutate(u32_square);
+    });
+}
+
+#[test]
+/// Test that a lock derived from another is mutated once the other is, from any thread.
+fn propagate_to() {
+    let config = InPlaceOnceLock::new(U32_INIT);
+    let derived = InPlaceOnceLock::new(0u64);
+    assert_eq!(config.propagate_to(&derived, |_, _| unreachable!()), None);
+
+    config.get_or_mutate(u32_square);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                let val = config.propagate_to(&derived, |v, d| *d = u64::from(*v) + 1);
+                assert_eq!(val, Some(&(u64::from(U32_MUTATED) + 1)));
+            });
+        }
+    });
+}
+
+#[test]
+/// Test that an entry tells an untouched lock from a mutated one.
+fn entry() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let val = match lock.entry() {
+        Entry::Untouched(e) => e.try_mutate(|v| {
+            u32_square(v);
+            Ok::<_, ()>(())
+        }),
+        Entry::Mutated(..) => unreachable!(),
+    };
+    assert_eq!(val, Ok(&U32_MUTATED));
+    assert!(matches!(lock.entry(), Entry::Mutated(&U32_MUTATED)));
+}
+
+#[test]
+/// Test that the value of the losing caller is handed back.
+fn try_mutate_with_value() {
+    let lock = InPlaceOnceLock::new(vec![U32_INIT]);
+    assert_eq!(
+        lock.try_mutate_with_value(vec![U32_MUTATED]),
+        Ok(&vec![U32_MUTATED])
+    );
+    let (rejected, current) = lock.try_mutate_with_value(vec![0]).unwrap_err();
+    assert_eq!((rejected, current), (vec![0], &vec![U32_MUTATED]));
+}
+
+#[test]
+fn fetch_mutate() {
+    let lock = InPlaceOnceLock::new(vec![U32_INIT]);
+    let (before, after) = lock.fetch_mutate(|v| v.push(U32_MUTATED));
+    assert_eq!(before, Some(vec![U32_INIT]));
+    assert_eq!(after, &[U32_INIT, U32_MUTATED]);
+    assert_eq!(lock.fetch_mutate(|v| v.clear()).0, None);
+}
+
+#[test]
+fn compare_and_mutate() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let err = lock.compare_and_mutate(&0, U32_MUTATED).unwrap_err();
+    assert_eq!((err.current, err.new), (U32_INIT, U32_MUTATED));
+    assert_eq!(lock.get(), None);
+
+    assert_eq!(
+        lock.compare_and_mutate(&U32_INIT, U32_MUTATED),
+        Ok(&U32_MUTATED)
+    );
+    // Once mutated, the lock never matches the placeholder again.
+    let err = lock.compare_and_mutate(&U32_INIT, 0).unwrap_err();
+    assert_eq!((err.current, err.new), (U32_MUTATED, 0));
+}
+
+#[test]
+fn try_into_inner() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let lock = lock.try_into_inner().unwrap_err();
+    lock.get_or_mutate(u32_square);
+    assert_eq!(lock.try_into_inner().ok(), Some(U32_MUTATED));
+}
+
 #[test]
 /// Test functionality of `get` and `get_or_mutate` across threads;
 fn basic_multi() {
@@ -157,3 +360,144 @@ fn assert_sync_and_send() {
     assert_send::<InPlaceOnceLock<Vec<u8>>>();
     assert_sync::<InPlaceOnceLock<Vec<u8>>>();
 }
+
+#[test]
+fn raw_parts() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let (val, mutated) = lock.into_raw_parts();
+    assert!(!mutated);
+
+    let lock = unsafe { InPlaceOnceLock::from_raw_parts(val, mutated) };
+    lock.get_or_mutate(u32_square);
+    let (val, mutated) = lock.into_raw_parts();
+    assert_eq!((val, mutated), (U32_MUTATED, true));
+
+    let lock = unsafe { InPlaceOnceLock::from_raw_parts(val, mutated) };
+    assert_eq!(lock.get_or_mutate(u32_increment), &U32_MUTATED);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "reentrant mutation of an `InPlaceOnceLock`")]
+/// Test that mutating the lock from its own mutator panics instead of deadlocking.
+fn reentrant_mutation() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    lock.get_or_mutate(|val| {
+        *val = *lock.get_or_mutate(u32_square);
+    });
+}
+
+#[test]
+/// Test that a failed mutation leaves the lock untouched for the next one.
+fn try_mutate_error() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.get_or_try_mutate(|_| Err(())), Err(()));
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+}
+
+#[test]
+#[should_panic(expected = "previously been poisoned")]
+/// Test that a panicking mutator poisons the lock.
+fn poison() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let res = std::panic::catch_unwind(|| lock.get_or_mutate(|_| panic!()));
+    assert!(res.is_err());
+    lock.get_or_mutate(u32_square);
+}
+
+#[test]
+#[should_panic(expected = "that panicked with: no value")]
+/// Test that mutating a poisoned lock re-panics with the panic that poisoned it.
+fn poison_message() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let res = std::panic::catch_unwind(|| lock.get_or_mutate(|_| panic!("no value")));
+    assert!(res.is_err());
+    lock.get_or_mutate(u32_square);
+}
+
+#[test]
+#[should_panic(expected = "that panicked with: bad value 34")]
+/// Test that the original message is kept when it was formatted into a `String`.
+fn poison_formatted_message() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let res = std::panic::catch_unwind(|| lock.get_or_mutate(|val| panic!("bad value {val}")));
+    assert!(res.is_err());
+    lock.get_or_mutate(u32_square);
+}
+
+#[test]
+/// Test that a panicking mutator leaves a retrying lock untouched, and the next mutator runs on
+/// what it left behind.
+fn retry_after_panic() {
+    let lock = InPlaceOnceLock::new(U32_INIT).with_retry_after_panic();
+    let res = std::panic::catch_unwind(|| {
+        lock.get_or_mutate(|val| {
+            *val += 1;
+            panic!();
+        })
+    });
+    assert!(res.is_err());
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.get_or_mutate(|val| *val -= 1), &U32_INIT);
+}
+
+#[test]
+#[should_panic(expected = "a mutator was passed to a strict `InPlaceOnceLock`")]
+/// Test that a strict lock panics on a second mutator instead of ignoring it.
+fn strict_second_mutator() {
+    let lock = InPlaceOnceLock::new(U32_INIT).with_strict();
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(lock.get(), Some(&U32_MUTATED));
+    lock.get_or_mutate(u32_increment);
+}
+
+#[test]
+/// Test that only the winner of a race to mutate a strict lock returns, and the losers panic.
+fn strict_race() {
+    let lock = InPlaceOnceLock::new(U32_INIT).with_strict();
+    let winners = thread::scope(|s| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| s.spawn(|| *lock.get_or_mutate(u32_square)))
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok())
+            .inspect(|&val| assert_eq!(val, U32_MUTATED))
+            .count()
+    });
+    assert_eq!(winners, 1);
+}
+
+#[test]
+#[should_panic(expected = "a mutator was passed to a strict `InPlaceOnceLock`")]
+/// Test that a lock made strict after it was assembled as mutated still rejects mutators.
+fn strict_from_raw_parts() {
+    // SAFETY: the value is the mutated value.
+    let lock = unsafe { InPlaceOnceLock::from_raw_parts(U32_MUTATED, true) }.with_strict();
+    assert_eq!(lock.get(), Some(&U32_MUTATED));
+    lock.get_or_mutate(u32_square);
+}
+
+#[test]
+/// Test that a failed mutation of a strict lock does not count as its mutation.
+fn strict_try_mutate_error() {
+    let lock = InPlaceOnceLock::new(U32_INIT).with_strict();
+    assert_eq!(lock.get_or_try_mutate(|_| Err(())), Err(()));
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+}
+
+#[test]
+/// Formatting into a lock happens once, even when many threads race to do it.
+fn mutate_fmt() {
+    let prefix = InPlaceOnceLock::new(String::with_capacity(16));
+    thread::scope(|s| {
+        for i in 0..8 {
+            let prefix = &prefix;
+            s.spawn(move || prefix.get_or_mutate_fmt(format_args!("[{i}] ")).len());
+        }
+    });
+    let prefix = prefix.get().unwrap();
+    assert!(prefix.starts_with('[') && prefix.ends_with("] "));
+    assert_eq!(prefix.len(), 4);
+}
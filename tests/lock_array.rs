@@ -0,0 +1,120 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use in_place_once_cell::InPlaceOnceLockArray;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+fn basic() {
+    let locks = InPlaceOnceLockArray::new(vec![U32_INIT; 4]);
+    assert_eq!(locks.len(), 4);
+    assert_eq!(locks.get(1), None);
+    assert_eq!(locks.get_or_mutate(1, u32_square), &U32_MUTATED);
+    assert_eq!(locks.get_or_mutate(1, |_| unreachable!()), &U32_MUTATED);
+    assert!(!locks.is_mutated(0));
+    assert_eq!(locks.get_or_try_mutate(0, |_| Err(())), Err(()));
+    assert_eq!(locks.get(0), None);
+    assert_eq!(locks.get(4), None);
+    assert_eq!(locks.count_mutated(), 1);
+    assert_eq!(format!("{locks:?}"), "{1: 1156}");
+    assert_eq!(
+        locks.into_inner(),
+        [U32_INIT, U32_MUTATED, U32_INIT, U32_INIT]
+    );
+}
+
+#[test]
+/// Test that a batch mutates each untouched lock once, skipping mutated and repeated ones.
+fn mutate_batch() {
+    let bits = usize::BITS as usize;
+    let locks = InPlaceOnceLockArray::new(vec![U32_INIT; bits * 2 + 1]);
+    locks.get_or_mutate(1, |v| *v = 0);
+    let count = locks.mutate_batch([0, 1, 1, bits + 1, 2, 0], |_, v| u32_square(v));
+    assert_eq!(count, 3);
+    assert_eq!(locks.count_mutated(), 4);
+    assert_eq!(locks.get(1), Some(&0));
+    for index in [0, 2, bits + 1] {
+        assert_eq!(locks.get(index), Some(&U32_MUTATED));
+    }
+
+    let count = locks.mutate_batch(0..locks.len(), |i, v| *v = i as u32);
+    assert_eq!(count, bits * 2 + 1 - 4);
+    assert_eq!(locks.count_mutated(), locks.len());
+    assert_eq!(locks.get(bits * 2), Some(&(bits as u32 * 2)));
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn mutate_batch_out_of_bounds() {
+    let locks = InPlaceOnceLockArray::new(vec![U32_INIT; 4]);
+    locks.mutate_batch([0, 5], |_, v| u32_square(v));
+}
+
+#[test]
+/// Test that locks mutated before a panicking mutator are published, and the others stay
+/// untouched and can be mutated again.
+fn mutate_batch_panic() {
+    let locks = InPlaceOnceLockArray::new(vec![U32_INIT; 4]);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        locks.mutate_batch(0..4, |i, v| {
+            assert_ne!(i, 2);
+            u32_square(v);
+        })
+    }));
+    assert!(res.is_err());
+    assert_eq!(locks.count_mutated(), 2);
+    assert_eq!(locks.get(1), Some(&U32_MUTATED));
+    assert_eq!(locks.get(2), None);
+    assert_eq!(locks.mutate_batch(0..4, |_, v| *v += 1), 2);
+    assert_eq!(locks.get(3), Some(&(U32_INIT + 1)));
+}
+
+#[test]
+/// Test that batches and single mutations racing over the same locks mutate each exactly once,
+/// and that waiters see the value a batch published.
+fn mutate_batch_concurrent() {
+    let locks = InPlaceOnceLockArray::new(vec![U32_INIT; 1000]);
+    let runs = AtomicUsize::new(0);
+    let mutate = |_: usize, v: &mut u32| {
+        runs.fetch_add(1, Ordering::Relaxed);
+        u32_square(v);
+    };
+    thread::scope(|s| {
+        for i in 0..8 {
+            let (locks, mutate) = (&locks, &mutate);
+            s.spawn(move || {
+                if i % 2 == 0 {
+                    locks.mutate_batch(0..1000, mutate);
+                } else {
+                    for index in (0..1000).rev() {
+                        assert_eq!(
+                            locks.get_or_mutate(index, |v| mutate(index, v)),
+                            &U32_MUTATED
+                        );
+                    }
+                }
+            });
+        }
+    });
+    assert_eq!(runs.into_inner(), 1000);
+    assert!(locks.into_inner().iter().all(|&v| v == U32_MUTATED));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "reentrant mutation of an `InPlaceOnceLockArray` lock")]
+/// Test that mutating a lock of the word a batch is mutating panics instead of deadlocking.
+fn mutate_batch_reentrant() {
+    let locks = InPlaceOnceLockArray::new(vec![U32_INIT; 4]);
+    locks.mutate_batch(0..4, |i, v| {
+        if i == 1 {
+            *v = *locks.get_or_mutate(0, u32_square);
+        }
+    });
+}
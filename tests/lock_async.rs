@@ -0,0 +1,82 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use futures::executor::block_on;
+use in_place_once_cell::InPlaceOnceLock;
+use std::thread;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// `wait_async` resolves immediately on an already mutated lock.
+fn wait_async_mutated() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    lock.get_or_mutate(u32_square);
+    assert_eq!(block_on(lock.wait_async()), &U32_MUTATED);
+}
+
+#[test]
+/// `wait_async` resolves once another thread mutates the lock.
+fn wait_async_other_thread() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+
+    thread::scope(|s| {
+        let waiters: Vec<_> = (0..8)
+            .map(|_| s.spawn(|| *block_on(lock.wait_async())))
+            .collect();
+
+        s.spawn(|| lock.get_or_mutate(u32_square));
+
+        for waiter in waiters {
+            assert_eq!(waiter.join().unwrap(), U32_MUTATED);
+        }
+    });
+}
+
+#[test]
+/// A read handle waits for the writer on another thread.
+fn read_handle_wait_async() {
+    let (writer, reader) = InPlaceOnceLock::new(U32_INIT).split();
+    let waiter = thread::spawn(move || *block_on(reader.wait_async()));
+    writer.get_or_mutate(u32_square);
+    assert_eq!(waiter.join().unwrap(), U32_MUTATED);
+}
+
+#[test]
+/// A pending `wait_async` is woken when the mutator panics, and then panics itself, as the
+/// blocking waiters do.
+fn wait_async_poisoned() {
+    use futures::task::{self, ArcWake};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Context;
+
+    struct Woken(AtomicBool);
+
+    impl ArcWake for Woken {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.store(true, Ordering::Release);
+        }
+    }
+
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let woken = Arc::new(Woken(AtomicBool::new(false)));
+    let waker = task::waker(woken.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut waiter = pin!(lock.wait_async());
+    assert!(waiter.as_mut().poll(&mut cx).is_pending());
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        lock.get_or_mutate(|_| panic::resume_unwind(Box::new("mutator panicked")))
+    }));
+    assert!(res.is_err());
+    assert!(woken.0.load(Ordering::Acquire));
+
+    let payload =
+        panic::catch_unwind(AssertUnwindSafe(|| waiter.as_mut().poll(&mut cx))).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.contains("previously been poisoned"), "{message}");
+}
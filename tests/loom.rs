@@ -0,0 +1,86 @@
+#![cfg(loom)]
+
+//! Model checks the memory orderings of `InPlaceOnceLock`.
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+
+use loom::sync::Arc;
+use loom::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use loom::thread;
+
+use in_place_once_cell::InPlaceOnceLock;
+
+#[test]
+/// Test that a reader that sees the lock mutated also sees the value written by the mutator.
+fn publish() {
+    loom::model(|| {
+        let lock = Arc::new(InPlaceOnceLock::new(AtomicU32::new(0)));
+        let mutator = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                lock.get_or_mutate(|val| val.store(1, Ordering::Relaxed));
+            })
+        };
+
+        if let Some(val) = lock.get() {
+            assert_eq!(val.load(Ordering::Relaxed), 1);
+        }
+
+        mutator.join().unwrap();
+        assert_eq!(lock.get().unwrap().load(Ordering::Relaxed), 1);
+    });
+}
+
+#[test]
+/// Test that racing mutators run exactly once, and that the loser waits for the winner.
+fn race() {
+    loom::model(|| {
+        let lock = Arc::new(InPlaceOnceLock::new(AtomicU32::new(0)));
+        let runs = Arc::new(AtomicUsize::new(0));
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let (lock, runs) = (lock.clone(), runs.clone());
+                thread::spawn(move || {
+                    let val = lock.get_or_mutate(|val| {
+                        runs.fetch_add(1, Ordering::Relaxed);
+                        val.store(1, Ordering::Relaxed);
+                    });
+                    assert_eq!(val.load(Ordering::Relaxed), 1);
+                })
+            })
+            .collect();
+
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+    });
+}
+
+#[test]
+/// Test that a mutator claiming the lock after a failed one sees what the failed one left behind.
+fn retry_after_failure() {
+    loom::model(|| {
+        let lock = Arc::new(InPlaceOnceLock::new(AtomicU32::new(0)));
+        let failing = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let res = lock.get_or_try_mutate(|val| {
+                    val.store(1, Ordering::Relaxed);
+                    Err(())
+                });
+                // Fails unless the other mutator got there first.
+                if res.is_ok() {
+                    assert_eq!(lock.get().unwrap().load(Ordering::Relaxed), 10);
+                }
+            })
+        };
+
+        let val = lock.get_or_mutate(|val| {
+            val.store(val.load(Ordering::Relaxed) + 10, Ordering::Relaxed);
+        });
+        assert!([10, 11].contains(&val.load(Ordering::Relaxed)));
+
+        failing.join().unwrap();
+    });
+}
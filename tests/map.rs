@@ -0,0 +1,136 @@
+#![cfg(feature = "std")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Barrier;
+use std::thread;
+
+use in_place_once_cell::InPlaceOnceMap;
+
+/// An allocator that fails the next allocation of the current thread on demand.
+struct Flaky;
+
+thread_local! {
+    static FAIL_NEXT: Cell<bool> = const { Cell::new(false) };
+}
+
+// SAFETY: forwards to the system allocator, or fails.
+unsafe impl GlobalAlloc for Flaky {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if FAIL_NEXT.replace(false) {
+            return std::ptr::null_mut();
+        }
+        // SAFETY: forwarded as is.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: forwarded as is.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOC: Flaky = Flaky;
+
+#[test]
+/// Test basic functionality
+fn basic() {
+    let map = InPlaceOnceMap::<u32, u64>::new(0);
+    assert!(map.is_empty());
+    assert_eq!(map.get(&3), None);
+
+    assert_eq!(map.get_or_mutate(3, |v| *v = 9), &9);
+    assert_eq!(map.get_or_mutate(3, |_| unreachable!()), &9);
+    assert_eq!(map.get(&3), Some(&9));
+    assert_eq!(map.len(), 1);
+    assert_eq!(format!("{map:?}"), "{3: 9}");
+}
+
+#[test]
+/// A failed mutation leaves the key unmutated and out of the snapshot, for the next mutator.
+fn try_mutate() {
+    let map = InPlaceOnceMap::<&str, u32>::new(1);
+    assert_eq!(map.get_or_try_mutate("a", |_| Err(())), Err(()));
+    assert_eq!(map.get_or_mutate("b", |v| *v += 1), &2);
+    assert_eq!(map.snapshot(), HashMap::from([("b", 2)]));
+
+    assert_eq!(map.get_or_mutate("a", |v| *v += 2), &3);
+    assert_eq!(map.snapshot(), HashMap::from([("a", 3), ("b", 2)]));
+}
+
+#[test]
+/// A mutator may use the map, including to mutate other keys.
+fn reentrant() {
+    let map = InPlaceOnceMap::<u32, u32>::new(1);
+    let val = map.get_or_mutate(10, |v| {
+        *v += map.get_or_mutate(2, |v| *v *= 2);
+    });
+    assert_eq!(val, &3);
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+/// Each key is mutated once, even when many threads race to do it.
+fn race() {
+    let map = InPlaceOnceMap::<u32, u32>::new(0);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                for key in 0..64 {
+                    assert_eq!(map.get_or_mutate(key, |v| *v += key), &key);
+                }
+            });
+        }
+    });
+    assert_eq!(map.snapshot(), (0..64).map(|key| (key, key)).collect());
+}
+
+#[test]
+/// Snapshots taken while other threads mutate keys hold every key mutated before they were taken,
+/// and only final values.
+fn snapshot_while_mutating() {
+    let map = InPlaceOnceMap::<u32, u32>::new(0);
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            for key in 0..256 {
+                map.get_or_mutate(key, |v| *v = key * 2);
+                if key == 127 {
+                    barrier.wait();
+                }
+            }
+        });
+
+        barrier.wait();
+        let snapshot = map.snapshot();
+        assert!(snapshot.len() >= 128);
+        for (key, val) in snapshot {
+            assert_eq!(val, key * 2);
+        }
+        for key in 0..128 {
+            assert_eq!(map.get(&key), Some(&(key * 2)));
+        }
+    });
+    assert_eq!(map.len(), 256);
+}
+
+#[test]
+/// A failed allocation of the table or of a new key is reported, and leaves the map unchanged.
+fn try_get_or_mutate() {
+    let map = InPlaceOnceMap::<u32, u64>::new(0);
+    FAIL_NEXT.set(true);
+    assert!(map.try_get_or_mutate(3, |_| unreachable!()).is_err());
+    assert_eq!(map.get(&3), None);
+
+    assert_eq!(map.try_get_or_mutate(3, |v| *v = 9), Ok(&9));
+    FAIL_NEXT.set(true);
+    assert_eq!(map.try_get_or_mutate(3, |_| unreachable!()), Ok(&9));
+    // The table has room left, so only the entry is allocated.
+    FAIL_NEXT.set(true);
+    assert!(map.try_get_or_mutate(4, |_| unreachable!()).is_err());
+    FAIL_NEXT.set(false);
+    assert_eq!(format!("{map:?}"), "{3: 9}");
+    assert_eq!(map.try_get_or_mutate(4, |v| *v = 1), Ok(&1));
+}
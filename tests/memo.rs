@@ -0,0 +1,99 @@
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+
+use in_place_once_cell::OnceMemo;
+
+#[test]
+/// Test basic functionality
+fn basic() {
+    let memo = OnceMemo::<u32, u64, 4>::new(0);
+    assert!(memo.is_empty());
+    assert_eq!(memo.get(&3), None);
+
+    assert_eq!(memo.get_or_mutate(3, |v| *v = 9), Some(&9));
+    assert_eq!(memo.get_or_mutate(3, |_| unreachable!()), Some(&9));
+    assert_eq!(memo.get(&3), Some(&9));
+    assert_eq!(memo.len(), 1);
+}
+
+#[test]
+/// Test that a full table refuses new keys, but still serves the ones it holds.
+fn full() {
+    let memo = OnceMemo::<u32, u32, 4>::new(0);
+    for key in 0..4 {
+        assert_eq!(
+            memo.get_or_mutate(key, |v| *v = key * key),
+            Some(&(key * key))
+        );
+    }
+    assert_eq!(memo.get_or_mutate(4, |_| unreachable!()), None);
+    for key in 0..4 {
+        assert_eq!(memo.get(&key), Some(&(key * key)));
+    }
+    assert_eq!(memo.capacity(), 4);
+}
+
+#[test]
+/// Test that a mutator may fill other entries, which stay reachable afterwards.
+fn nested() {
+    let memo = OnceMemo::<u32, u32, 8>::new(1);
+    memo.get_or_mutate(0, |v| {
+        *v = *memo.get_or_mutate(1, |w| *w = 2).unwrap() + 1;
+    });
+    assert_eq!(memo.get(&0), Some(&3));
+    assert_eq!(memo.get(&1), Some(&2));
+}
+
+#[test]
+#[should_panic(expected = "reentrant mutation")]
+fn reentrant() {
+    let memo = OnceMemo::<u32, u32, 8>::new(1);
+    memo.get_or_mutate(0, |_| {
+        memo.get(&0);
+    });
+}
+
+#[test]
+/// Test that a panicking mutator leaves the key out, and its key is dropped.
+fn panic_vacates() {
+    let drops = Cell::new(0);
+    struct Key<'a>(u32, &'a Cell<u32>);
+    impl std::hash::Hash for Key<'_> {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+    impl PartialEq for Key<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Key<'_> {}
+    impl Drop for Key<'_> {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+        }
+    }
+
+    let memo = OnceMemo::<Key<'_>, u32, 2>::new(0);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        memo.get_or_mutate(Key(0, &drops), |_| panic!());
+    }));
+    assert!(res.is_err());
+    assert_eq!(drops.get(), 1);
+    assert_eq!(memo.get(&Key(0, &drops)), None);
+    assert_eq!(drops.get(), 2);
+
+    memo.get_or_mutate(Key(1, &drops), |v| *v = 1);
+    memo.get_or_mutate(Key(2, &drops), |v| *v = 2);
+    assert_eq!(memo.len(), 2);
+    drop(memo);
+    assert_eq!(drops.get(), 4);
+}
+
+#[test]
+fn debug() {
+    let memo = OnceMemo::<u32, u32, 4>::new(0);
+    memo.get_or_mutate(1, |v| *v = 34);
+    assert_eq!(format!("{memo:?}"), "{1: 34}");
+}
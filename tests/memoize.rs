@@ -0,0 +1,39 @@
+#![cfg(all(feature = "derive", feature = "std"))]
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use in_place_once_cell::memoize_in_place;
+
+use common::{U32_INIT, U32_MUTATED};
+
+static CALLS: AtomicU32 = AtomicU32::new(0);
+
+#[memoize_in_place(init = U32_INIT)]
+fn square(v: &mut u32) {
+    CALLS.fetch_add(1, Ordering::Relaxed);
+    *v *= *v;
+}
+
+/// The squares of `0..16`.
+#[memoize_in_place]
+pub fn squares(table: &mut Vec<u32>) {
+    table.extend((0..16).map(|i| i * i));
+}
+
+#[test]
+/// The body runs exactly once, starting from the `init` value.
+fn init() {
+    assert_eq!(*square(), U32_MUTATED);
+    assert_eq!(*square(), U32_MUTATED);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+/// Without `init`, the body starts from the default value.
+fn default() {
+    assert_eq!(squares().len(), 16);
+    assert_eq!(squares()[U32_INIT as usize % 16], 4);
+    assert!(std::ptr::eq(squares(), squares()));
+}
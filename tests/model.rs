@@ -0,0 +1,345 @@
+#![cfg(feature = "std")]
+
+//! Applies random sequences of operations to the cells and locks, checking each step against a
+//! model. Every new API that mutates or takes apart a cell should get an `Op` here.
+
+use in_place_once_cell::{InPlaceOnceCell, InPlaceOnceLock};
+use proptest::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
+
+/// An operation on a cell holding a `u32`. Every mutator adds to the value in place.
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    /// Reads the cell.
+    Get,
+    /// Mutates the cell with `get_or_mutate`.
+    Mutate(u32),
+    /// Mutates the cell with `get_or_mutate_status`.
+    MutateStatus(u32),
+    /// Mutates the cell with `get_or_mutate_map`, returning the value before adding.
+    Map(u32),
+    /// Mutates the cell with `get_or_try_mutate`, failing after adding if `fail` is set.
+    TryMutate { add: u32, fail: bool },
+    /// Mutates the cell with a mutator that panics after adding.
+    Panic(u32),
+    /// Replaces the placeholder with `try_mutate_with_value`.
+    WithValue(u32),
+    /// Mutates the cell with `fetch_mutate`.
+    Fetch(u32),
+    /// Adds to the mutated value through `get_mut`.
+    GetMut(u32),
+    /// Takes the value out with `try_into_inner`, then puts it back.
+    TryIntoInner,
+    /// Takes the value and the mutated flag out of the cell, then puts them back.
+    Take,
+    /// Takes the value out of the cell, then puts it back untouched.
+    Reset,
+}
+
+impl Op {
+    /// Whether the operation runs a mutator, and so panics on a poisoned lock.
+    fn mutates(self) -> bool {
+        matches!(
+            self,
+            Op::Mutate(..)
+                | Op::MutateStatus(..)
+                | Op::Map(..)
+                | Op::TryMutate { .. }
+                | Op::Panic(..)
+                | Op::WithValue(..)
+                | Op::Fetch(..)
+        )
+    }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        Just(Op::Get),
+        any::<u32>().prop_map(Op::Mutate),
+        any::<u32>().prop_map(Op::MutateStatus),
+        any::<u32>().prop_map(Op::Map),
+        (any::<u32>(), any::<bool>()).prop_map(|(add, fail)| Op::TryMutate { add, fail }),
+        any::<u32>().prop_map(Op::Panic),
+        any::<u32>().prop_map(Op::WithValue),
+        any::<u32>().prop_map(Op::Fetch),
+        any::<u32>().prop_map(Op::GetMut),
+        Just(Op::TryIntoInner),
+        Just(Op::Take),
+        Just(Op::Reset),
+    ]
+}
+
+/// A cell under test.
+trait Subject: Sized {
+    /// Whether a panicking mutator poisons the cell until it is taken apart.
+    const POISONS: bool;
+
+    fn new(value: u32) -> Self;
+
+    fn get(&self) -> Option<u32>;
+
+    fn get_mut(&mut self) -> Option<&mut u32>;
+
+    fn get_or_mutate(&self, f: impl FnOnce(&mut u32)) -> u32;
+
+    fn get_or_mutate_status(&self, f: impl FnOnce(&mut u32)) -> (u32, bool);
+
+    fn get_or_mutate_map(&self, f: impl FnOnce(&mut u32) -> u32) -> (Option<u32>, u32);
+
+    fn get_or_try_mutate(&self, f: impl FnOnce(&mut u32) -> Result<(), ()>) -> Result<u32, ()>;
+
+    fn try_mutate_with_value(&self, value: u32) -> Result<u32, (u32, u32)>;
+
+    fn fetch_mutate(&self, f: impl FnOnce(&mut u32)) -> (Option<u32>, u32);
+
+    fn try_into_inner(self) -> Result<u32, Self>;
+
+    /// Consumes the cell, returning its value and whether it was mutated.
+    fn take(self) -> (u32, bool);
+
+    /// Rebuilds a cell from the parts returned by [`Subject::take`].
+    fn put(value: u32, mutated: bool) -> Self;
+}
+
+/// What a cell should hold: its value, whether it is mutated, and whether it is poisoned.
+#[derive(Debug)]
+struct Model {
+    value: u32,
+    mutated: bool,
+    poisoned: bool,
+}
+
+impl Model {
+    fn get(&self) -> Option<u32> {
+        self.mutated.then_some(self.value)
+    }
+
+    /// Applies a mutator that adds `add`, if the cell is not mutated yet, returning whether it
+    /// ran.
+    fn mutate(&mut self, add: u32) -> bool {
+        let ran = !self.mutated;
+        if ran {
+            self.value = self.value.wrapping_add(add);
+            self.mutated = true;
+        }
+        ran
+    }
+}
+
+/// Applies `ops` to a cell starting out as `init`, asserting after each one that the cell agrees
+/// with the model.
+fn run<S: Subject>(init: u32, ops: &[Op]) {
+    let mut model = Model {
+        value: init,
+        mutated: false,
+        poisoned: false,
+    };
+    let mut cell = S::new(init);
+
+    for &op in ops {
+        if model.poisoned && op.mutates() {
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                cell.get_or_mutate(|_| panic!("a poisoned lock ran a mutator"));
+            }));
+            assert!(res.is_err(), "{op:?} on a poisoned lock did not panic");
+            assert_eq!(cell.get(), None);
+            continue;
+        }
+
+        match op {
+            Op::Get => {}
+            Op::Mutate(add) => {
+                let res = cell.get_or_mutate(|val| *val = val.wrapping_add(add));
+                model.mutate(add);
+                assert_eq!(res, model.value);
+            }
+            Op::MutateStatus(add) => {
+                let res = cell.get_or_mutate_status(|val| *val = val.wrapping_add(add));
+                let ran = model.mutate(add);
+                assert_eq!(res, (model.value, ran));
+            }
+            Op::Map(add) => {
+                let before = (!model.mutated).then_some(model.value);
+                let res = cell.get_or_mutate_map(|val| {
+                    let before = *val;
+                    *val = val.wrapping_add(add);
+                    before
+                });
+                model.mutate(add);
+                assert_eq!(res, (before, model.value));
+            }
+            Op::TryMutate { add, fail } => {
+                let res = cell.get_or_try_mutate(|val| {
+                    *val = val.wrapping_add(add);
+                    if fail { Err(()) } else { Ok(()) }
+                });
+                if !model.mutated {
+                    model.value = model.value.wrapping_add(add);
+                    model.mutated = !fail;
+                }
+                assert_eq!(res, model.get().ok_or(()));
+            }
+            Op::Panic(add) => {
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    cell.get_or_mutate(|val| {
+                        *val = val.wrapping_add(add);
+                        // Skips the panic hook, to keep the output readable.
+                        panic::resume_unwind(Box::new("mutator panicked"));
+                    })
+                }));
+                if model.mutated {
+                    assert_eq!(res.ok(), Some(model.value));
+                } else {
+                    assert!(res.is_err(), "the mutator did not run");
+                    model.value = model.value.wrapping_add(add);
+                    model.poisoned = S::POISONS;
+                }
+            }
+            Op::WithValue(value) => {
+                let res = cell.try_mutate_with_value(value);
+                if model.mutated {
+                    assert_eq!(res, Err((value, model.value)));
+                } else {
+                    model.value = value;
+                    model.mutated = true;
+                    assert_eq!(res, Ok(value));
+                }
+            }
+            Op::Fetch(add) => {
+                let before = (!model.mutated).then_some(model.value);
+                let res = cell.fetch_mutate(|val| *val = val.wrapping_add(add));
+                model.mutate(add);
+                assert_eq!(res, (before, model.value));
+            }
+            Op::GetMut(add) => {
+                if let Some(val) = cell.get_mut() {
+                    *val = val.wrapping_add(add);
+                }
+                if model.mutated {
+                    model.value = model.value.wrapping_add(add);
+                }
+            }
+            Op::TryIntoInner => {
+                cell = match cell.try_into_inner() {
+                    Ok(value) => {
+                        assert_eq!(Some(value), model.get());
+                        S::put(value, true)
+                    }
+                    Err(cell) => {
+                        assert!(!model.mutated);
+                        cell
+                    }
+                };
+            }
+            Op::Take | Op::Reset => {
+                let (value, mutated) = cell.take();
+                assert_eq!((value, mutated), (model.value, model.mutated));
+                if let Op::Reset = op {
+                    model.mutated = false;
+                }
+                model.poisoned = false;
+                cell = S::put(value, model.mutated);
+            }
+        }
+
+        assert_eq!(cell.get(), model.get(), "after {op:?}");
+    }
+}
+
+/// Declares a [`Subject`] wrapping `$inner`, forwarding to the inherent methods of the same
+/// names. `$build` configures every cell built, including those put back.
+macro_rules! subject {
+    ($name:ident($inner:ty), $build:expr, poisons = $poisons:expr) => {
+        struct $name($inner);
+
+        impl Subject for $name {
+            const POISONS: bool = $poisons;
+
+            fn new(value: u32) -> Self {
+                Self($build(<$inner>::new(value)))
+            }
+
+            fn get(&self) -> Option<u32> {
+                self.0.get().copied()
+            }
+
+            fn get_mut(&mut self) -> Option<&mut u32> {
+                self.0.get_mut()
+            }
+
+            fn get_or_mutate(&self, f: impl FnOnce(&mut u32)) -> u32 {
+                *self.0.get_or_mutate(f)
+            }
+
+            fn get_or_mutate_status(&self, f: impl FnOnce(&mut u32)) -> (u32, bool) {
+                let (val, mutated) = self.0.get_or_mutate_status(f);
+                (*val, mutated)
+            }
+
+            fn get_or_mutate_map(&self, f: impl FnOnce(&mut u32) -> u32) -> (Option<u32>, u32) {
+                let (ret, val) = self.0.get_or_mutate_map(f);
+                (ret, *val)
+            }
+
+            fn get_or_try_mutate(
+                &self,
+                f: impl FnOnce(&mut u32) -> Result<(), ()>,
+            ) -> Result<u32, ()> {
+                self.0.get_or_try_mutate(f).copied()
+            }
+
+            fn try_mutate_with_value(&self, value: u32) -> Result<u32, (u32, u32)> {
+                self.0
+                    .try_mutate_with_value(value)
+                    .copied()
+                    .map_err(|(value, val)| (value, *val))
+            }
+
+            fn fetch_mutate(&self, f: impl FnOnce(&mut u32)) -> (Option<u32>, u32) {
+                let (before, val) = self.0.fetch_mutate(f);
+                (before, *val)
+            }
+
+            fn try_into_inner(self) -> Result<u32, Self> {
+                self.0.try_into_inner().map_err(Self)
+            }
+
+            fn take(self) -> (u32, bool) {
+                self.0.into_raw_parts()
+            }
+
+            fn put(value: u32, mutated: bool) -> Self {
+                // SAFETY: a `u32` is valid in any state.
+                Self($build(unsafe { <$inner>::from_raw_parts(value, mutated) }))
+            }
+        }
+    };
+}
+
+subject!(Cell(InPlaceOnceCell<u32>), |cell| cell, poisons = false);
+subject!(Lock(InPlaceOnceLock<u32>), |lock| lock, poisons = true);
+subject!(
+    RetryLock(InPlaceOnceLock<u32>),
+    InPlaceOnceLock::with_retry_after_panic,
+    poisons = false
+);
+
+proptest! {
+    #[test]
+    /// `InPlaceOnceCell` agrees with the model.
+    fn cell(init: u32, ops in prop::collection::vec(op(), 0..32)) {
+        run::<Cell>(init, &ops);
+    }
+
+    #[test]
+    /// `InPlaceOnceLock` agrees with the model.
+    fn lock(init: u32, ops in prop::collection::vec(op(), 0..32)) {
+        run::<Lock>(init, &ops);
+    }
+
+    #[test]
+    /// `InPlaceOnceLock::with_retry_after_panic` agrees with the model.
+    fn retry_lock(init: u32, ops in prop::collection::vec(op(), 0..32)) {
+        run::<RetryLock>(init, &ops);
+    }
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use in_place_once_cell::MustMutate;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+fn mutated() {
+    let mut cell = MustMutate::new(U32_INIT);
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.get_or_mutate(u32_square), &U32_MUTATED);
+    *cell.get_mut().unwrap() += 1;
+    assert_eq!(cell.get(), Some(&(U32_MUTATED + 1)));
+}
+
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    should_panic = "`MustMutate<u32>` created at tests/must_mutate.rs"
+)]
+/// Dropping an untouched cell panics in debug builds, naming where it was created.
+fn dropped_untouched() {
+    drop(MustMutate::new(U32_INIT));
+}
+
+#[test]
+/// Taking the value out is never checked.
+fn into_inner() {
+    let cell = MustMutate::new(U32_INIT);
+    assert_eq!(cell.into_inner(), U32_INIT);
+}
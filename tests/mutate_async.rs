@@ -0,0 +1,64 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use futures::executor::block_on;
+use in_place_once_cell::{
+    AsyncInPlaceLazyLock, AsyncInPlaceOnceLock, OnceMutateAsync, OnceMutateAsyncWith,
+};
+
+use common::{U32_INIT, U32_MUTATED};
+
+/// A test mutator
+async fn u32_square(v: &mut u32) {
+    *v = *v * *v;
+}
+
+/// Generic code that only knows it was given some async cell.
+async fn read_twice<C: OnceMutateAsync<Value = u32>>(cell: &C) -> u32 {
+    *cell.wait().await + *cell.get().unwrap()
+}
+
+/// Generic code that mutates any async cell taking its mutator at each call.
+async fn square<C: OnceMutateAsyncWith<Value = u32>>(cell: &C) -> u32 {
+    *cell.get_or_mutate(u32_square).await
+}
+
+#[test]
+/// The async lock is mutated once through the trait.
+fn lock() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+    assert_eq!(OnceMutateAsync::get(&lock), None);
+    assert_eq!(block_on(square(&lock)), U32_MUTATED);
+    assert_eq!(block_on(square(&lock)), U32_MUTATED);
+    assert_eq!(block_on(read_twice(&lock)), 2 * U32_MUTATED);
+}
+
+#[test]
+/// A failed mutation through the trait leaves the lock unmutated.
+fn lock_try_mutate() {
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+    let res = block_on(OnceMutateAsyncWith::get_or_try_mutate(
+        &lock,
+        async |_: &mut u32| Err("fail"),
+    ));
+    assert_eq!(res, Err("fail"));
+    assert_eq!(OnceMutateAsync::get(&lock), None);
+}
+
+#[test]
+/// Waiting on the lazy lock through the trait runs its own mutator.
+fn lazy() {
+    let lazy = AsyncInPlaceLazyLock::new(U32_INIT, u32_square);
+    assert_eq!(OnceMutateAsync::get(&lazy), None);
+    assert_eq!(block_on(read_twice(&lazy)), 2 * U32_MUTATED);
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+/// The critical-section async lock is mutated once through the trait.
+fn cs_lock() {
+    let lock = in_place_once_cell::AsyncInPlaceOnceCsLock::new(U32_INIT);
+    assert_eq!(block_on(square(&lock)), U32_MUTATED);
+    assert_eq!(block_on(read_twice(&lock)), 2 * U32_MUTATED);
+}
@@ -0,0 +1,32 @@
+use in_place_once_cell::InPlaceOnceCell;
+
+const U32_INIT: u32 = 34;
+const U32_MUTATED: u32 = U32_INIT * U32_INIT;
+
+/// Built at compile time with the same calls as at runtime.
+const SQUARED: u32 = {
+    let c = InPlaceOnceCell::new(U32_INIT);
+    assert!(c.get().is_none());
+    *c.get_or_mutate(const |v: &mut u32| *v *= *v)
+};
+
+const FAILED: Result<u32, u32> = {
+    let c = InPlaceOnceCell::new(U32_INIT);
+    match c.get_or_try_mutate(const |v: &mut u32| Err(*v)) {
+        Ok(v) => Ok(*v),
+        Err(e) => Err(e),
+    }
+};
+
+#[test]
+fn const_mutation() {
+    assert_eq!(SQUARED, U32_MUTATED);
+    assert_eq!(FAILED, Err(U32_INIT));
+}
+
+#[test]
+/// Test that non-`const` closures still work at runtime.
+fn runtime_mutation() {
+    let c = InPlaceOnceCell::new(vec![U32_INIT]);
+    assert_eq!(c.get_or_mutate(|v| v.push(U32_MUTATED)).len(), 2);
+}
@@ -0,0 +1,11 @@
+#![cfg(feature = "nightly")]
+#![cfg_attr(
+    feature = "nightly",
+    feature(arbitrary_self_types, const_closures, const_trait_impl)
+)]
+
+//! `const` closures do not even parse on stable, so the tests using them live in a module that
+//! is only loaded with the `nightly` feature.
+
+mod const_mutation;
+mod unsize;
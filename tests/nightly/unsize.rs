@@ -0,0 +1,75 @@
+use in_place_once_cell::InPlaceOnceBox;
+use std::fmt::Debug;
+
+trait Plugin {
+    fn start(&mut self);
+
+    fn name(&self) -> String;
+
+    /// Called through the box itself, which needs `DispatchFromDyn`.
+    fn describe(self: InPlaceOnceBox<Self>) -> String;
+}
+
+#[derive(Debug, Default)]
+struct Counter(u32);
+
+impl Plugin for Counter {
+    fn start(&mut self) {
+        self.0 += 1;
+    }
+
+    fn name(&self) -> String {
+        format!("counter {}", self.0)
+    }
+
+    fn describe(self: InPlaceOnceBox<Self>) -> String {
+        format!("{:?}", self.get())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Greeter(String);
+
+impl Plugin for Greeter {
+    fn start(&mut self) {
+        self.0.push_str("hello");
+    }
+
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+
+    fn describe(self: InPlaceOnceBox<Self>) -> String {
+        format!("{:?}", self.get())
+    }
+}
+
+#[test]
+/// Boxes of concrete types coerce to boxes of trait objects, which are each mutated once.
+fn coerce_to_dyn() {
+    let plugins: Vec<InPlaceOnceBox<dyn Plugin>> = vec![
+        InPlaceOnceBox::new(Counter::default()),
+        InPlaceOnceBox::new(Greeter::default()),
+    ];
+
+    for plugin in &plugins {
+        assert!(plugin.get().is_none());
+        plugin.get_or_mutate(|p| p.start());
+        plugin.get_or_mutate(|p| p.start());
+    }
+    let names: Vec<_> = plugins.iter().map(|p| p.get().unwrap().name()).collect();
+    assert_eq!(names, ["counter 1", "hello"]);
+
+    let descriptions: Vec<_> = plugins.into_iter().map(Plugin::describe).collect();
+    assert_eq!(
+        descriptions,
+        ["Some(Counter(1))", "Some(Greeter(\"hello\"))"]
+    );
+}
+
+#[test]
+/// Unsized values keep their `Debug` output.
+fn debug_dyn() {
+    let cell: InPlaceOnceBox<dyn Debug> = InPlaceOnceBox::new(34_u32);
+    assert_eq!(format!("{cell:?}"), "InPlaceOnceBox(34 <untouched>)");
+}
@@ -0,0 +1,106 @@
+//! Checks that the core cell and lock paths never allocate.
+
+use in_place_once_cell::InPlaceOnceCell;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the allocations made by the current thread.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Asserts that `f` does not allocate on the current thread.
+fn assert_no_alloc<R>(f: impl FnOnce() -> R) -> R {
+    let before = ALLOCATIONS.with(Cell::get);
+    let res = f();
+    assert_eq!(ALLOCATIONS.with(Cell::get), before, "allocated");
+    res
+}
+
+#[test]
+fn cell() {
+    assert_no_alloc(|| {
+        let mut c = InPlaceOnceCell::new([0u8; 64]);
+        assert!(c.get().is_none());
+        assert_eq!(c.get_or_try_mutate(|_| Err(())), Err(()));
+        c.get_or_mutate(|v| v[0] = 1);
+        c.get_mut_or_mutate(|v| v[1] = 1)[2] = 1;
+        assert_eq!(c.get_mut().unwrap()[..3], [1, 0, 1]);
+        assert_eq!(format_args!("{:?}", c.get()).as_str(), None);
+        c.into_inner()
+    });
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn lock() {
+    use in_place_once_cell::InPlaceOnceLock;
+
+    assert_no_alloc(|| {
+        let mut l = InPlaceOnceLock::new([0u8; 64]);
+        assert!(l.get().is_none());
+        l.get_or_mutate(|v| v[0] = 1);
+        assert_eq!(l.get_or_mutate(|v| v[1] = 1)[..2], [1, 0]);
+        l.get_mut().unwrap()[2] = 1;
+        l.into_inner()
+    });
+
+    static LOCK: InPlaceOnceLock<u32> = InPlaceOnceLock::new(34);
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_no_alloc(|| *LOCK.get_or_mutate(|v| *v += 1)));
+        }
+    });
+}
+
+#[test]
+#[cfg(feature = "critical-section")]
+fn cs_lock() {
+    use in_place_once_cell::InPlaceOnceCsLock;
+
+    assert_no_alloc(|| {
+        let lock = InPlaceOnceCsLock::new(34u32);
+        assert_eq!(lock.get_or_try_mutate(|_| Err(())), Err(()));
+        assert_eq!(lock.get_or_mutate(|v| *v += 1), &35);
+        lock.into_inner()
+    });
+}
+
+#[test]
+#[cfg(feature = "critical-section")]
+fn async_cs_lock() {
+    use in_place_once_cell::AsyncInPlaceOnceCsLock;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    assert_no_alloc(|| {
+        let lock = AsyncInPlaceOnceCsLock::new(34u32);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut first = pin!(lock.get_or_mutate(async |v| {
+            core::future::ready(()).await;
+            *v += 1;
+        }));
+        let mut waiter = pin!(lock.wait());
+        assert!(waiter.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(first.as_mut().poll(&mut cx), Poll::Ready(&35));
+        assert_eq!(waiter.as_mut().poll(&mut cx), Poll::Ready(&35));
+    });
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use std::cell::Cell;
+use std::ptr::NonNull;
+
+use in_place_once_cell::{HasSpareFlagBit, InPlaceOnceCell, PackedInPlaceOnceCell};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that the cell is no larger than the value it holds.
+fn size() {
+    assert_eq!(
+        size_of::<PackedInPlaceOnceCell<Box<u32>>>(),
+        size_of::<usize>()
+    );
+    assert_eq!(size_of::<PackedInPlaceOnceCell<&u32>>(), size_of::<usize>());
+    assert_eq!(
+        size_of::<PackedInPlaceOnceCell<NonNull<u32>>>(),
+        size_of::<usize>()
+    );
+    assert!(size_of::<InPlaceOnceCell<Box<u32>>>() > size_of::<usize>());
+}
+
+#[test]
+fn basic_box() {
+    let cell = PackedInPlaceOnceCell::new(Box::new(U32_INIT));
+    assert_eq!(cell.get(), None);
+    assert_eq!(**cell.get_or_mutate(|val| u32_square(val)), U32_MUTATED);
+    assert_eq!(cell.get().map(|val| **val), Some(U32_MUTATED));
+    assert_eq!(**cell.get_or_mutate(|_| unreachable!()), U32_MUTATED);
+    assert_eq!(*cell.into_inner(), U32_MUTATED);
+}
+
+#[test]
+/// Test that the mutator can replace the value, and that a failed mutation keeps its changes.
+fn replace_box() {
+    let cell = PackedInPlaceOnceCell::new(Box::new(0));
+    let res = cell.get_or_try_mutate(|val| {
+        drop(std::mem::replace(val, Box::new(U32_INIT)));
+        Err(())
+    });
+    assert_eq!(res, Err(()));
+    assert_eq!(cell.get(), None);
+
+    assert_eq!(**cell.get_or_mutate(|val| u32_square(val)), U32_MUTATED);
+}
+
+#[test]
+/// Test that the unmutated value can be taken out of the cell.
+fn into_inner_untouched() {
+    let cell = PackedInPlaceOnceCell::new(Box::new(U32_INIT));
+    assert_eq!(*cell.into_inner(), U32_INIT);
+}
+
+#[test]
+fn reference() {
+    let (a, b) = (U32_INIT, U32_MUTATED);
+    let cell = PackedInPlaceOnceCell::new(&a);
+    assert_eq!(cell.get(), None);
+    assert_eq!(**cell.get_or_mutate(|val| *val = &b), U32_MUTATED);
+    assert!(std::ptr::eq(cell.into_inner(), &b));
+}
+
+#[test]
+/// Test that a panicking mutator neither leaks nor double-drops the value.
+fn panic_drop() {
+    struct Counted<'a>(&'a Cell<u32>);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Cell::new(0);
+    let cell = PackedInPlaceOnceCell::new(Box::new(Counted(&drops)));
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cell.get_or_mutate(|_| panic!());
+    }));
+    assert!(res.is_err());
+    assert_eq!(cell.get().map(|_| ()), None);
+    assert_eq!(drops.get(), 0);
+
+    drop(cell);
+    assert_eq!(drops.get(), 1);
+}
+
+#[test]
+/// Test that a mutator reaching its own cell panics before the value gets a second owner, and
+/// leaves the cell untouched.
+fn reentrant() {
+    let cell = PackedInPlaceOnceCell::new(Box::new(U32_INIT));
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cell.get_or_mutate(|val| {
+            **val += 1;
+            cell.get_or_mutate(|val| drop(std::mem::replace(val, Box::new(0))));
+        });
+    }));
+    let payload = res.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"reentrant mutation of a `PackedInPlaceOnceCell`")
+    );
+    assert_eq!(cell.get(), None);
+
+    // Other cells may still be mutated from inside a mutator.
+    let other = PackedInPlaceOnceCell::new(Box::new(U32_INIT));
+    assert_eq!(
+        **cell.get_or_mutate(|val| **val = **other.get_or_mutate(|val| u32_square(val))),
+        U32_MUTATED
+    );
+    assert_eq!(*cell.into_inner(), U32_MUTATED);
+}
+
+/// A length that never uses its high bit.
+#[derive(Debug, PartialEq)]
+struct Len(usize);
+
+// SAFETY: the high bit of a `Len` is never set.
+unsafe impl HasSpareFlagBit for Len {
+    unsafe fn set_flag(value: *mut Self) {
+        unsafe { (*value).0 |= 1 << (usize::BITS - 1) };
+    }
+
+    unsafe fn clear_flag(value: *mut Self) {
+        unsafe { (*value).0 &= !(1 << (usize::BITS - 1)) };
+    }
+
+    unsafe fn is_flag_set(value: *const Self) -> bool {
+        unsafe { (*value).0 >> (usize::BITS - 1) != 0 }
+    }
+}
+
+#[test]
+/// Test that the trait can be implemented for custom types.
+fn custom() {
+    let cell = PackedInPlaceOnceCell::new(Len(0));
+    assert_eq!(size_of_val(&cell), size_of::<usize>());
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.get_or_mutate(|len| len.0 = 3), &Len(3));
+}
+
+#[test]
+fn debug_impl() {
+    let cell = PackedInPlaceOnceCell::new(Box::new(U32_INIT));
+    assert_eq!(format!("{cell:?}"), "PackedInPlaceOnceCell(<untouched>)");
+    cell.get_or_mutate(|val| u32_square(val));
+    assert_eq!(format!("{cell:?}"), "PackedInPlaceOnceCell(1156)");
+}
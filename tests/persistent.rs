@@ -0,0 +1,101 @@
+#![cfg(feature = "nvm")]
+
+mod common;
+
+use in_place_once_cell::{NvmStorage, PersistError, PersistentOnceCell};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+/// Storage backed by RAM, optionally failing writes.
+#[derive(Default)]
+struct Ram {
+    bytes: [u8; 16],
+    fail_writes: bool,
+}
+
+#[derive(Debug, PartialEq)]
+struct WriteFailed;
+
+impl NvmStorage for Ram {
+    type Error = WriteFailed;
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), WriteFailed> {
+        buf.copy_from_slice(&self.bytes[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), WriteFailed> {
+        if self.fail_writes {
+            return Err(WriteFailed);
+        }
+        self.bytes[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+#[test]
+/// A mutated value survives being reconstructed from the same storage.
+fn restore() {
+    let cell = PersistentOnceCell::new(U32_INIT, Ram::default()).unwrap();
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.get_or_mutate(u32_square), Ok(&U32_MUTATED));
+    let (_, storage) = cell.into_parts();
+
+    let cell = PersistentOnceCell::new(U32_INIT, storage).unwrap();
+    assert_eq!(cell.get(), Some(&U32_MUTATED));
+    assert_eq!(cell.get_or_mutate(|_| unreachable!()), Ok(&U32_MUTATED));
+}
+
+#[test]
+/// A corrupted record is ignored.
+fn corrupted() {
+    let cell = PersistentOnceCell::new(U32_INIT, Ram::default()).unwrap();
+    cell.get_or_mutate(u32_square).unwrap();
+    let (_, mut storage) = cell.into_parts();
+    storage.bytes[5] ^= 1;
+
+    let cell = PersistentOnceCell::new(U32_INIT, storage).unwrap();
+    assert_eq!(cell.get(), None);
+}
+
+#[test]
+/// Failing mutators and storage leave the cell unmutated.
+fn errors() {
+    let storage = Ram {
+        fail_writes: true,
+        ..Ram::default()
+    };
+    let cell = PersistentOnceCell::new(U32_INIT, storage).unwrap();
+    assert_eq!(cell.get_or_mutate(u32_square), Err(WriteFailed));
+    assert_eq!(cell.get(), None);
+
+    let cell = PersistentOnceCell::new(U32_INIT, Ram::default()).unwrap();
+    assert_eq!(
+        cell.get_or_try_mutate(|_| Err("bad calibration")),
+        Err(PersistError::Mutator("bad calibration"))
+    );
+    assert_eq!(cell.get(), None);
+}
+
+#[test]
+/// Errors describe themselves and chain to their cause.
+fn error_impl() {
+    use std::error::Error;
+
+    let err = PersistError::<std::fmt::Error, std::fmt::Error>::Storage(std::fmt::Error);
+    assert_eq!(
+        err.to_string(),
+        "failed to persist the mutated value: an error occurred when formatting an argument"
+    );
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn debug_impl() {
+    let cell = PersistentOnceCell::new(U32_INIT, Ram::default()).unwrap();
+    cell.get_or_mutate(u32_square).unwrap();
+    assert_eq!(
+        format!("{cell:?}"),
+        format!("PersistentOnceCell(InPlaceOnceCell({U32_MUTATED}))")
+    );
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "alloc")]
+
+use in_place_once_cell::OncePool;
+
+#[test]
+/// Test that resetting restores every placeholder, reusing the values' buffers.
+fn reset_all() {
+    let mut pool = OncePool::new(3, Vec::<u32>::with_capacity(8));
+    assert_eq!(pool.len(), 3);
+
+    pool[0].get_or_mutate(|val| val.extend([34, 1156]));
+    assert!(
+        pool[2]
+            .get_or_try_mutate(|val| {
+                val.push(34);
+                Err(())
+            })
+            .is_err()
+    );
+    let buf = pool[0].get().unwrap().as_ptr();
+
+    pool.reset_all();
+    assert!(pool.iter().all(|cell| cell.get().is_none()));
+    assert_eq!(pool[0].get_or_mutate(|val| val.push(1)), &[1]);
+    assert_eq!(pool[0].get().unwrap().as_ptr(), buf);
+    assert_eq!(pool[2].get_or_mutate(|_| {}), pool.placeholder());
+}
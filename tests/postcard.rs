@@ -0,0 +1,48 @@
+//! Checks that cells round-trip through `postcard` without `std` or an allocator.
+#![cfg(feature = "serde")]
+#![no_std]
+
+mod common;
+
+use in_place_once_cell::InPlaceOnceCell;
+use serde::{Deserialize, Serialize};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[derive(Serialize, Deserialize)]
+struct Compact {
+    #[serde(with = "in_place_once_cell::serde::compact")]
+    cell: InPlaceOnceCell<u32>,
+}
+
+#[test]
+/// The state is a one-byte variant index ahead of the value.
+fn round_trip() {
+    let mut buf = [0u8; 8];
+    let c = InPlaceOnceCell::new(U32_INIT);
+    assert_eq!(postcard::to_slice(&c, &mut buf).unwrap(), &[0, 34]);
+    let c: InPlaceOnceCell<u32> = postcard::from_bytes(&[0, 34]).unwrap();
+    assert_eq!(c.get(), None);
+
+    c.get_or_mutate(u32_square);
+    let bytes = postcard::to_slice(&c, &mut buf).unwrap();
+    assert_eq!(bytes, &[1, 0x84, 0x09]);
+    let c: InPlaceOnceCell<u32> = postcard::from_bytes(bytes).unwrap();
+    assert_eq!(c.get(), Some(&U32_MUTATED));
+}
+
+#[test]
+/// The compact representation is a single byte tag ahead of the value.
+fn compact_round_trip() {
+    let mut buf = [0u8; 8];
+    let c = Compact {
+        cell: InPlaceOnceCell::new(U32_INIT),
+    };
+    c.cell.get_or_mutate(u32_square);
+    let bytes = postcard::to_slice(&c, &mut buf).unwrap();
+    assert_eq!(bytes, &[1, 0x84, 0x09]);
+    let c: Compact = postcard::from_bytes(bytes).unwrap();
+    assert_eq!(c.cell.get(), Some(&U32_MUTATED));
+
+    assert!(postcard::from_bytes::<Compact>(&[2, 34]).is_err());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use in_place_once_cell::RacyInPlaceOnceLock;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that the first mutation is published and later ones are skipped.
+fn basic() {
+    let lock = RacyInPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.get_or_mutate(u32_square), U32_MUTATED);
+    assert_eq!(lock.get(), Some(&U32_MUTATED));
+    assert_eq!(lock.get_or_mutate(|_| unreachable!()), U32_MUTATED);
+    assert_eq!(lock.into_inner(), U32_MUTATED);
+}
+
+#[test]
+/// Test that a failed mutation publishes nothing and leaves the initial value for the next one.
+fn try_mutate_error() {
+    let lock = RacyInPlaceOnceLock::new(U32_INIT);
+    let res = lock.get_or_try_mutate(|val| {
+        *val = 0;
+        Err(())
+    });
+    assert_eq!(res, Err(()));
+    assert_eq!(lock.get(), None);
+    assert_eq!(
+        lock.get_or_try_mutate(|val| {
+            u32_square(val);
+            Ok::<_, ()>(())
+        }),
+        Ok(U32_MUTATED)
+    );
+}
+
+#[test]
+/// Test that racing threads never wait and all see the same value.
+fn race() {
+    const THREADS: usize = 8;
+
+    let lock = RacyInPlaceOnceLock::new(U32_INIT);
+    let runs = AtomicUsize::new(0);
+    let barrier = Barrier::new(THREADS);
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                barrier.wait();
+                let val = lock.get_or_mutate(|val| {
+                    runs.fetch_add(1, Ordering::Relaxed);
+                    u32_square(val);
+                });
+                assert_eq!(val, U32_MUTATED);
+            });
+        }
+    });
+
+    assert!((1..=THREADS).contains(&runs.into_inner()));
+    assert_eq!(lock.get(), Some(&U32_MUTATED));
+}
+
+#[test]
+fn debug_impl() {
+    let lock = RacyInPlaceOnceLock::new(U32_INIT);
+    assert_eq!(format!("{lock:?}"), "RacyInPlaceOnceLock(34 <untouched>)");
+    lock.get_or_mutate(u32_square);
+    assert_eq!(format!("{lock:?}"), "RacyInPlaceOnceLock(1156)");
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::AtomicU8;
+use std::thread;
+
+use in_place_once_cell::raw::{RawInPlaceOnce, RawState};
+
+use common::{U32_INIT, U32_MUTATED};
+
+/// A minimal container built on the raw protocol: states and values stored apart.
+struct Table {
+    states: [AtomicU8; 4],
+    values: [UnsafeCell<u32>; 4],
+}
+
+unsafe impl Sync for Table {}
+
+impl Table {
+    fn new() -> Self {
+        Self {
+            states: Default::default(),
+            values: [const { UnsafeCell::new(U32_INIT) }; 4],
+        }
+    }
+
+    fn raw(&self, i: usize) -> RawInPlaceOnce<'_, u32> {
+        unsafe { RawInPlaceOnce::new(&self.states[i], self.values[i].get()) }
+    }
+}
+
+#[test]
+/// Test the full state machine: begin, abort, begin again, complete.
+fn protocol() {
+    let table = Table::new();
+    let raw = table.raw(0);
+    assert_eq!(raw.state(), RawState::Untouched);
+    assert_eq!(raw.get(), None);
+
+    let mut mutation = raw.try_begin_mutation().unwrap();
+    assert_eq!(raw.state(), RawState::Mutating);
+    assert_eq!(raw.try_begin_mutation().unwrap_err(), RawState::Mutating);
+    *mutation.value() += 1;
+    raw.abort_mutation(mutation);
+    assert_eq!(raw.state(), RawState::Untouched);
+
+    let mut mutation = raw.try_begin_mutation().unwrap();
+    *mutation.value() = U32_INIT * U32_INIT;
+    assert_eq!(raw.complete_mutation(mutation), &U32_MUTATED);
+    assert!(raw.is_mutated());
+    assert_eq!(raw.try_begin_mutation().unwrap_err(), RawState::Mutated);
+    assert_eq!(table.raw(0).get(), Some(&U32_MUTATED));
+    assert_eq!(table.raw(1).get(), None);
+}
+
+#[test]
+/// Dropping a mutation, e.g. while unwinding, aborts it.
+fn drop_aborts() {
+    let table = Table::new();
+    drop(table.raw(2).try_begin_mutation().unwrap());
+    assert_eq!(table.raw(2).state(), RawState::Untouched);
+}
+
+#[test]
+/// Exactly one racing thread gets to mutate each slot.
+fn race() {
+    let table = Table::new();
+    let wins: usize = thread::scope(|s| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                s.spawn(|| {
+                    (0..4)
+                        .filter(|&i| {
+                            let raw = table.raw(i);
+                            let Ok(mut mutation) = raw.try_begin_mutation() else {
+                                return false;
+                            };
+                            *mutation.value() *= U32_INIT;
+                            raw.complete_mutation(mutation);
+                            true
+                        })
+                        .count()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    });
+
+    assert_eq!(wins, 4);
+    assert!((0..4).all(|i| table.raw(i).get() == Some(&U32_MUTATED)));
+}
+
+#[test]
+#[should_panic = "mutation of another `RawInPlaceOnce`"]
+fn complete_foreign_mutation() {
+    let table = Table::new();
+    let mutation = table.raw(0).try_begin_mutation().unwrap();
+    table.raw(1).complete_mutation(mutation);
+}
+
+#[test]
+/// The payload pointer handed out is the one passed in, usable to write the payload directly.
+fn payload_ptr() {
+    let table = Table::new();
+    let raw = table.raw(0);
+    assert_eq!(raw.as_ptr(), table.values[0].get());
+
+    let mut mutation = raw.try_begin_mutation().unwrap();
+    unsafe { mutation.as_mut_ptr().write(U32_MUTATED) };
+    assert_eq!(raw.complete_mutation(mutation), &U32_MUTATED);
+}
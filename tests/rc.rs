@@ -0,0 +1,36 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use in_place_once_cell::RcOnce;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// A child shared by two parents is mutated once, and both see it.
+fn shared_child() {
+    let child = RcOnce::new(U32_INIT);
+    let parents = [child.clone(), child.clone()];
+    assert_eq!(parents[0].get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(parents[1].get_or_mutate(|v| *v += 1), &U32_MUTATED);
+    assert_eq!(child.get(), Some(&U32_MUTATED));
+    assert_eq!(RcOnce::strong_count(&child), 3);
+}
+
+#[test]
+/// The cell can only be borrowed mutably or taken back by its only owner.
+fn unique_owner() {
+    let mut rc = RcOnce::new(U32_INIT);
+    let clone = rc.clone();
+    assert!(RcOnce::ptr_eq(&rc, &clone));
+    assert!(RcOnce::get_mut(&mut rc).is_none());
+
+    let clone = RcOnce::try_unwrap(clone).unwrap_err();
+    drop(clone);
+    let cell = RcOnce::get_mut(&mut rc).unwrap();
+    *cell.get_mut_or_mutate(u32_square) += 1;
+    assert_eq!(rc.get(), Some(&(U32_MUTATED + 1)));
+
+    let cell = RcOnce::into_inner(rc).unwrap();
+    assert_eq!(cell.into_inner(), U32_MUTATED + 1);
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "std")]
+
+use in_place_once_cell::{InPlaceOnceCell, InPlaceOnceLock};
+use std::io::{self, Read};
+
+/// A reader that yields `data` a few bytes at a time, interrupted before every read, then fails
+/// with `error` if one is set.
+struct Flaky {
+    data: &'static [u8],
+    interrupt: bool,
+    error: Option<io::ErrorKind>,
+}
+
+impl Flaky {
+    fn new(data: &'static [u8], error: Option<io::ErrorKind>) -> Self {
+        Self {
+            data,
+            interrupt: true,
+            error,
+        }
+    }
+}
+
+impl Read for Flaky {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if !self.interrupt {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        if self.data.is_empty() {
+            return self.error.map_or(Ok(0), |kind| Err(kind.into()));
+        }
+
+        let n = self.data.len().min(buf.len()).min(3);
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+/// The reader is appended to the placeholder through partial and interrupted reads, once.
+fn cell_from_reader() {
+    let cell = InPlaceOnceCell::new(b"v1:".to_vec());
+    let val = cell
+        .get_or_mutate_from_reader(Flaky::new(b"hello world", None))
+        .unwrap();
+    assert_eq!(val, b"v1:hello world");
+    let val = cell.get_or_mutate_from_reader(&b"ignored"[..]).unwrap();
+    assert_eq!(val, b"v1:hello world");
+}
+
+#[test]
+/// A failing reader leaves the placeholder as it was and the cell unmutated.
+fn cell_from_reader_err() {
+    let cell = InPlaceOnceCell::new(b"v1:".to_vec());
+    let err = cell
+        .get_or_mutate_from_reader(Flaky::new(b"hello", Some(io::ErrorKind::UnexpectedEof)))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.into_inner(), b"v1:");
+}
+
+#[test]
+/// The lock is read into once.
+fn lock_from_reader() {
+    let lock = InPlaceOnceLock::new(Vec::with_capacity(4));
+    assert_eq!(
+        lock.get_or_mutate_from_reader(&b"asset"[..]).unwrap(),
+        b"asset"
+    );
+    assert_eq!(
+        lock.get_or_mutate_from_reader(&b"ignored"[..]).unwrap(),
+        b"asset"
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+/// The async lock is read into once from a `futures` `AsyncRead`.
+fn async_lock_from_reader() {
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use in_place_once_cell::AsyncInPlaceOnceLock;
+
+    let lock = AsyncInPlaceOnceLock::new(b"v1:".to_vec());
+    let data = vec![7; 20_000];
+    block_on(async {
+        let val = lock
+            .get_or_mutate_from_async_reader(Cursor::new(&data))
+            .await
+            .unwrap();
+        assert_eq!(val.len(), 20_003);
+        assert_eq!(&val[..3], b"v1:");
+        let val = lock
+            .get_or_mutate_from_async_reader(Cursor::new(b"ignored"))
+            .await
+            .unwrap();
+        assert_eq!(val.len(), 20_003);
+    });
+}
+
+#[cfg(feature = "async")]
+#[test]
+/// A failing async reader leaves the placeholder as it was.
+fn async_lock_from_reader_err() {
+    use futures::executor::block_on;
+    use futures::io::AllowStdIo;
+    use in_place_once_cell::AsyncInPlaceOnceLock;
+
+    let lock = AsyncInPlaceOnceLock::new(b"v1:".to_vec());
+    let reader = AllowStdIo::new(Flaky::new(b"hello", Some(io::ErrorKind::BrokenPipe)));
+    let err = block_on(lock.get_or_mutate_from_async_reader(reader)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.into_inner(), b"v1:");
+}
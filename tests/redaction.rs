@@ -0,0 +1,27 @@
+//! The redaction hook is global, so it is tested in its own binary.
+
+use in_place_once_cell::{InPlaceOnceCell, clear_redaction_hook, set_redaction_hook};
+
+struct ApiKey(&'static str);
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ApiKey").field(&self.0).finish()
+    }
+}
+
+#[test]
+/// Values of redacted types are hidden in both forms, and other values are not.
+fn redaction() {
+    let key = InPlaceOnceCell::new(ApiKey(""));
+    key.get_or_mutate(|k| k.0 = "secret");
+    let other = InPlaceOnceCell::new(34);
+
+    set_redaction_hook(|ty| ty.ends_with("ApiKey"));
+    assert_eq!(format!("{key:?}"), "InPlaceOnceCell(<redacted>)");
+    assert!(format!("{key:#?}").contains("value: <redacted>"));
+    assert_eq!(format!("{other:?}"), "InPlaceOnceCell(34 <untouched>)");
+
+    clear_redaction_hook();
+    assert_eq!(format!("{key:?}"), "InPlaceOnceCell(ApiKey(\"secret\"))");
+}
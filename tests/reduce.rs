@@ -0,0 +1,48 @@
+#![cfg(feature = "std")]
+
+use std::thread;
+
+use in_place_once_cell::ReduceOnceLock;
+
+#[test]
+/// The value freezes once every party contributed, and turns later contributions away.
+fn contribute() {
+    let lock = ReduceOnceLock::new(8, Vec::new());
+    thread::scope(|s| {
+        let waiter = s.spawn(|| lock.wait().len());
+        for i in 0..8 {
+            let lock = &lock;
+            s.spawn(move || assert!(lock.contribute(|v| v.push(i))));
+        }
+        assert_eq!(waiter.join().unwrap(), 8);
+    });
+
+    assert!(lock.is_frozen());
+    assert_eq!(lock.remaining(), 0);
+    assert!(!lock.contribute(|_| unreachable!()));
+    let mut val = lock.into_inner();
+    val.sort_unstable();
+    assert_eq!(val, (0..8).collect::<Vec<_>>());
+}
+
+#[test]
+/// Finalizing freezes the value before every party contributed.
+fn finalize() {
+    let lock = ReduceOnceLock::new(3, 0u32);
+    assert!(lock.contribute(|v| *v += 34));
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.remaining(), 2);
+    assert_eq!(format!("{lock:?}"), "ReduceOnceLock(<untouched>)");
+
+    assert_eq!(lock.finalize(), &34);
+    assert!(!lock.contribute(|v| *v += 1));
+    assert_eq!(lock.get(), Some(&34));
+    assert_eq!(lock.wait(), &34);
+}
+
+#[test]
+/// A lock with no parties starts out frozen.
+fn no_parties() {
+    let lock = ReduceOnceLock::new(0, 34u32);
+    assert_eq!(lock.get(), Some(&34));
+}
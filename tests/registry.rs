@@ -0,0 +1,35 @@
+#![cfg(feature = "registry")]
+
+use in_place_once_cell::registry::dump_states;
+use in_place_once_cell::{LockState, in_place_lazy_static};
+
+in_place_lazy_static! {
+    static ref TOUCHED: u32 = 34; mutate |v| *v *= *v;
+    static ref UNTOUCHED: u32 = 34; mutate |v| *v *= *v;
+}
+
+#[test]
+/// Every declared static is reported, whether it ran or not.
+fn dump() {
+    assert_eq!(*TOUCHED, 1156);
+
+    let states: Vec<_> = dump_states()
+        .into_iter()
+        .filter(|s| s.module == module_path!())
+        .map(|s| (s.name, s.state))
+        .collect();
+    assert_eq!(
+        states,
+        [
+            ("TOUCHED", LockState::Mutated),
+            ("UNTOUCHED", LockState::Untouched),
+        ]
+    );
+
+    let line = dump_states()
+        .into_iter()
+        .find(|s| s.name == "TOUCHED")
+        .unwrap()
+        .to_string();
+    assert_eq!(line, "registry::TOUCHED: Mutated");
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::mem;
+
+use in_place_once_cell::ReprCOnceCell;
+
+use common::{U32_INIT, U32_MUTATED};
+
+#[test]
+fn basic() {
+    let c = ReprCOnceCell::new(U32_INIT);
+    assert!(c.get().is_none());
+
+    assert_eq!(c.get_or_mutate(|v| *v *= *v), &U32_MUTATED);
+    assert_eq!(c.get(), Some(&U32_MUTATED));
+    assert_eq!(c.get_or_mutate(|v| *v += 1), &U32_MUTATED);
+}
+
+#[test]
+/// The state byte comes first, followed by the value, as documented.
+fn layout() {
+    assert_eq!(ReprCOnceCell::<u32>::STATE_OFFSET, 0);
+    assert_eq!(ReprCOnceCell::<u32>::VALUE_OFFSET, 4);
+    assert_eq!(ReprCOnceCell::<u16>::VALUE_OFFSET, 2);
+    assert_eq!(mem::size_of::<ReprCOnceCell<u32>>(), 8);
+    assert_eq!(mem::size_of::<ReprCOnceCell<[u8; 3]>>(), 4);
+}
+
+#[test]
+/// Foreign code reading through a raw pointer sees the documented state and value.
+fn raw_view() {
+    let c = ReprCOnceCell::new(U32_INIT);
+    let ptr = c.as_ptr().cast::<u8>();
+    let read = || unsafe {
+        (
+            ptr.add(ReprCOnceCell::<u32>::STATE_OFFSET).read(),
+            ptr.add(ReprCOnceCell::<u32>::VALUE_OFFSET)
+                .cast::<u32>()
+                .read(),
+        )
+    };
+
+    assert_eq!(read(), (ReprCOnceCell::<u32>::UNTOUCHED, U32_INIT));
+    c.get_or_mutate(|v| *v *= *v);
+    assert_eq!(read(), (ReprCOnceCell::<u32>::MUTATED, U32_MUTATED));
+}
+
+#[test]
+/// Foreign code can lay out a cell in memory it owns, through pointers that never pass through a
+/// reference.
+fn raw_fields() {
+    let mut mem = mem::MaybeUninit::<ReprCOnceCell<u32>>::uninit();
+    let ptr = mem.as_mut_ptr();
+    unsafe {
+        ReprCOnceCell::state_ptr(ptr).write(ReprCOnceCell::<u32>::UNTOUCHED);
+        ReprCOnceCell::value_ptr(ptr).write(U32_INIT);
+    }
+
+    let c = unsafe { mem.assume_init_ref() };
+    assert_eq!(c.get_or_mutate(|v| *v *= *v), &U32_MUTATED);
+    assert_eq!(
+        unsafe { ReprCOnceCell::state_ptr(c.as_ptr()).read() },
+        ReprCOnceCell::<u32>::MUTATED
+    );
+}
+
+#[test]
+fn try_mutate_error() {
+    let c = ReprCOnceCell::new(U32_INIT);
+    assert_eq!(c.get_or_try_mutate(|_| Err(())), Err(()));
+    assert_eq!(c.get(), None);
+}
+
+#[test]
+/// The state byte is `MUTATING` while the mutator runs, and a mutator that mutates its own cell
+/// panics, leaving the cell untouched.
+fn reentrant() {
+    let c = ReprCOnceCell::new(U32_INIT);
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        c.get_or_mutate(|_| {
+            assert_eq!(
+                unsafe { ReprCOnceCell::state_ptr(c.as_ptr()).read() },
+                ReprCOnceCell::<u32>::MUTATING
+            );
+            c.get_or_mutate(|v| *v += 1);
+        });
+    }));
+    assert_eq!(
+        res.unwrap_err().downcast_ref::<&str>(),
+        Some(&"reentrant mutation of a `ReprCOnceCell`")
+    );
+    assert_eq!(
+        unsafe { ReprCOnceCell::state_ptr(c.as_ptr()).read() },
+        ReprCOnceCell::<u32>::UNTOUCHED
+    );
+    assert_eq!(c.get_or_mutate(|v| *v *= *v), &U32_MUTATED);
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use in_place_once_cell::RetainingOnceCell;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that the initial value outlives the mutation.
+fn initial() {
+    let c = RetainingOnceCell::new(U32_INIT);
+    assert_eq!(c.initial(), &U32_INIT);
+    assert_eq!(c.diff(), None);
+
+    assert_eq!(c.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(c.initial(), &U32_INIT);
+    assert_eq!(c.diff(), Some((&U32_INIT, &U32_MUTATED)));
+    assert_eq!(
+        format!("{c:?}"),
+        "RetainingOnceCell { initial: 34, cell: InPlaceOnceCell(1156) }"
+    );
+
+    let (initial, c) = c.into_parts();
+    assert_eq!((initial, c.into_inner()), (U32_INIT, U32_MUTATED));
+}
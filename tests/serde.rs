@@ -0,0 +1,156 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use in_place_once_cell::unchecked::UncheckedOnceCell;
+use in_place_once_cell::{InPlaceOnceCell, InPlaceOnceLock};
+use serde::Deserialize;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that cells round-trip through their tagged representation.
+fn cell_round_trip() {
+    let c = InPlaceOnceCell::new(U32_INIT);
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"untouched":34}"#);
+    let c: InPlaceOnceCell<u32> = serde_json::from_str(r#"{"untouched":34}"#).unwrap();
+    assert_eq!(c.get(), None);
+
+    c.get_or_mutate(u32_square);
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"mutated":1156}"#);
+    let c: InPlaceOnceCell<u32> = serde_json::from_str(r#"{"mutated":1156}"#).unwrap();
+    assert_eq!(c.get(), Some(&U32_MUTATED));
+    assert_eq!(c.get_or_mutate(|_| unreachable!()), &U32_MUTATED);
+}
+
+#[test]
+/// Test that locks round-trip through their tagged representation.
+fn lock_round_trip() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(serde_json::to_string(&lock).unwrap(), r#"{"untouched":34}"#);
+    // Serializing leaves the lock free to be mutated.
+    lock.get_or_mutate(u32_square);
+    assert_eq!(serde_json::to_string(&lock).unwrap(), r#"{"mutated":1156}"#);
+
+    let lock: InPlaceOnceLock<u32> = serde_json::from_str(r#"{"mutated":1156}"#).unwrap();
+    assert_eq!(lock.get(), Some(&U32_MUTATED));
+}
+
+#[test]
+/// Test that unchecked cells serialize as their value alone.
+fn unchecked_round_trip() {
+    let c = UncheckedOnceCell::new(U32_INIT);
+    assert_eq!(serde_json::to_string(&c).unwrap(), "34");
+    let c: UncheckedOnceCell<u32> = serde_json::from_str("1156").unwrap();
+    assert_eq!(c.into_inner(), U32_MUTATED);
+}
+
+#[test]
+/// Test that deserializing in place reuses the placeholder's buffer.
+fn cell_deserialize_in_place() {
+    let placeholder = Vec::<u32>::with_capacity(16);
+    let buf = placeholder.as_ptr();
+    let mut c = InPlaceOnceCell::new(placeholder);
+
+    let mut de = serde_json::Deserializer::from_str(r#"{"mutated":[34,1156]}"#);
+    Deserialize::deserialize_in_place(&mut de, &mut c).unwrap();
+    assert_eq!(c.get().unwrap(), &[U32_INIT, U32_MUTATED]);
+    assert_eq!(c.get().unwrap().as_ptr(), buf);
+
+    let mut de = serde_json::Deserializer::from_str(r#"{"untouched":[34]}"#);
+    Deserialize::deserialize_in_place(&mut de, &mut c).unwrap();
+    assert_eq!(c.get(), None);
+    assert_eq!(c.into_inner(), [U32_INIT]);
+
+    let mut c = UncheckedOnceCell::new(vec![U32_INIT]);
+    let mut de = serde_json::Deserializer::from_str("[1156]");
+    Deserialize::deserialize_in_place(&mut de, &mut c).unwrap();
+    assert_eq!(c.into_inner(), [U32_MUTATED]);
+}
+
+#[test]
+fn unknown_tag() {
+    assert!(serde_json::from_str::<InPlaceOnceCell<u32>>(r#"{"mutating":34}"#).is_err());
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+/// Test that the schema describes the tagged representation.
+fn schema() {
+    let schema = schemars::schema_for!(InPlaceOnceLock<u32>);
+    let variants = schema.get("oneOf").unwrap().as_array().unwrap();
+    let tags: Vec<_> = variants
+        .iter()
+        .map(|variant| variant["required"][0].as_str().unwrap())
+        .collect();
+    assert_eq!(tags, ["untouched", "mutated"]);
+    assert_eq!(variants[1]["properties"]["mutated"]["type"], "integer");
+}
+
+#[test]
+/// Test that the compact representation tags the value with a single number.
+fn cell_compact() {
+    #[derive(serde::Serialize, Deserialize)]
+    struct Compact {
+        #[serde(with = "in_place_once_cell::serde::compact")]
+        cell: InPlaceOnceCell<u32>,
+    }
+
+    let c = Compact {
+        cell: InPlaceOnceCell::new(U32_INIT),
+    };
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"cell":[0,34]}"#);
+    c.cell.get_or_mutate(u32_square);
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"cell":[1,1156]}"#);
+
+    let c: Compact = serde_json::from_str(r#"{"cell":[1,1156]}"#).unwrap();
+    assert_eq!(c.cell.get(), Some(&U32_MUTATED));
+    assert!(serde_json::from_str::<Compact>(r#"{"cell":[2,34]}"#).is_err());
+}
+
+#[test]
+/// Test that `as_option` maps untouched cells to `None` and mutated ones to `Some`.
+fn cell_as_option() {
+    #[derive(serde::Serialize, Deserialize)]
+    struct AsOption {
+        #[serde(default, with = "in_place_once_cell::serde::as_option")]
+        cell: InPlaceOnceCell<u32>,
+    }
+
+    let c = AsOption {
+        cell: InPlaceOnceCell::new(U32_INIT),
+    };
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"cell":null}"#);
+    c.cell.get_or_mutate(u32_square);
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"cell":1156}"#);
+
+    let c: AsOption = serde_json::from_str(r#"{"cell":1156}"#).unwrap();
+    assert_eq!(c.cell.get(), Some(&U32_MUTATED));
+    for json in [r#"{"cell":null}"#, "{}"] {
+        let c: AsOption = serde_json::from_str(json).unwrap();
+        assert_eq!(c.cell.get(), None);
+        assert_eq!(c.cell.into_inner(), 0);
+    }
+}
+
+#[test]
+/// Test that `as_value_or_default` maps cells to their bare value, or the default.
+fn cell_as_value_or_default() {
+    #[derive(serde::Serialize, Deserialize)]
+    struct AsValue {
+        #[serde(default, with = "in_place_once_cell::serde::as_value_or_default")]
+        cell: InPlaceOnceCell<u32>,
+    }
+
+    let c = AsValue {
+        cell: InPlaceOnceCell::new(U32_INIT),
+    };
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"cell":0}"#);
+    c.cell.get_or_mutate(u32_square);
+    assert_eq!(serde_json::to_string(&c).unwrap(), r#"{"cell":1156}"#);
+
+    let c: AsValue = serde_json::from_str(r#"{"cell":1156}"#).unwrap();
+    assert_eq!(c.cell.get(), Some(&U32_MUTATED));
+    let c: AsValue = serde_json::from_str("{}").unwrap();
+    assert_eq!(c.cell.get(), None);
+}
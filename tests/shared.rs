@@ -0,0 +1,122 @@
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+use in_place_once_cell::SharedInPlaceOnceLock;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+fn basic() {
+    let lock = SharedInPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(lock.get_or_mutate(|v| *v += 1), &U32_MUTATED);
+}
+
+#[test]
+/// Only one of many racing threads mutates the value.
+fn race() {
+    let calls = AtomicU32::new(0);
+    let lock = SharedInPlaceOnceLock::new(U32_INIT);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                let val = lock.get_or_mutate(|v| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    u32_square(v);
+                });
+                assert_eq!(val, &U32_MUTATED);
+            });
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+/// A failed or panicking mutator leaves the lock untouched for the next one.
+fn failed_mutation() {
+    let lock = SharedInPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.get_or_try_mutate(|_| Err(())), Err(()));
+    let res = panic::catch_unwind(AssertUnwindSafe(|| lock.get_or_mutate(|_| panic!())));
+    assert!(res.is_err());
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+}
+
+#[test]
+/// Zeroed memory is a valid untouched lock.
+fn zeroed() {
+    // Mutable, since the lock writes through the pointer.
+    let mut mem = [0u64; 2];
+    let lock = unsafe { SharedInPlaceOnceLock::<u32>::from_ptr(mem.as_mut_ptr().cast()) };
+    assert_eq!(lock.get(), None);
+    assert_eq!(lock.get_or_mutate(|v| *v = U32_INIT), &U32_INIT);
+    assert!(!unsafe { lock.reset_abandoned() });
+}
+
+#[test]
+/// A lock can be laid out field by field in memory that does not hold one yet.
+fn raw_fields() {
+    let mut mem = std::mem::MaybeUninit::<SharedInPlaceOnceLock<u32>>::uninit();
+    let ptr = mem.as_mut_ptr();
+    unsafe {
+        SharedInPlaceOnceLock::state_ptr(ptr).write(AtomicU32::new(0));
+        SharedInPlaceOnceLock::value_ptr(ptr).write(U32_INIT);
+    }
+
+    let lock = unsafe { SharedInPlaceOnceLock::from_ptr(ptr) };
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+}
+
+#[cfg(unix)]
+#[test]
+#[cfg_attr(miri, ignore = "Miri does not support `fork`")]
+/// A forked child and its parent share one lock through a shared mapping, and only one of them
+/// mutates it.
+fn cross_process() {
+    use std::ptr;
+
+    #[repr(C)]
+    struct Region {
+        lock: SharedInPlaceOnceLock<u32>,
+        calls: AtomicU32,
+    }
+
+    unsafe {
+        let mem = libc::mmap(
+            ptr::null_mut(),
+            size_of::<Region>(),
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        assert_ne!(mem, libc::MAP_FAILED);
+        let region = &*mem.cast::<Region>();
+        let mutate = || {
+            region.lock.get_or_mutate(|v| {
+                region.calls.fetch_add(1, Ordering::Relaxed);
+                *v = U32_INIT;
+                u32_square(v);
+            })
+        };
+
+        let pid = libc::fork();
+        assert!(pid >= 0);
+        if pid == 0 {
+            let ok = *mutate() == U32_MUTATED;
+            libc::_exit(if ok { 0 } else { 1 });
+        }
+
+        assert_eq!(mutate(), &U32_MUTATED);
+        let mut status = 0;
+        assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+        assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+        assert_eq!(region.calls.load(Ordering::Relaxed), 1);
+
+        libc::munmap(mem, size_of::<Region>());
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(all(unix, feature = "std"))]
+
+mod common;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use in_place_once_cell::InPlaceOnceLock;
+
+use common::{U32_INIT, U32_MUTATED};
+
+/// Counts every allocation, to check that reading never allocates.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+static LOCK: InPlaceOnceLock<u32> = InPlaceOnceLock::new(U32_INIT);
+/// What the handler read, or `u32::MAX` if the lock was not mutated.
+static READ: AtomicU32 = AtomicU32::new(0);
+/// How many allocations the handler saw happen while it ran.
+static HANDLER_ALLOCATIONS: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+extern "C" fn handler(_: libc::c_int) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let val = LOCK.get_signal_safe().copied().unwrap_or(u32::MAX);
+    HANDLER_ALLOCATIONS.store(
+        ALLOCATIONS.load(Ordering::Relaxed) - before,
+        Ordering::Relaxed,
+    );
+    READ.store(val, Ordering::Relaxed);
+}
+
+// The only test in this file, so nothing else allocates while it counts.
+#[test]
+#[cfg_attr(miri, ignore = "miri does not support signal handlers")]
+/// Test that reading the lock from a signal handler works, and does not allocate.
+fn read_from_handler() {
+    unsafe {
+        let handler = handler as extern "C" fn(libc::c_int);
+        assert_ne!(
+            libc::signal(libc::SIGUSR1, handler as libc::sighandler_t),
+            libc::SIG_ERR
+        );
+
+        libc::raise(libc::SIGUSR1);
+        assert_eq!(READ.load(Ordering::Relaxed), u32::MAX);
+
+        LOCK.get_or_mutate(|v| *v *= *v);
+        libc::raise(libc::SIGUSR1);
+        assert_eq!(READ.load(Ordering::Relaxed), U32_MUTATED);
+        assert_eq!(HANDLER_ALLOCATIONS.load(Ordering::Relaxed), 0);
+
+        libc::signal(libc::SIGUSR1, libc::SIG_DFL);
+    }
+}
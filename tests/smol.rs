@@ -0,0 +1,36 @@
+#![cfg(feature = "smol")]
+
+mod common;
+
+use futures::executor::block_on;
+use in_place_once_cell::AsyncInPlaceOnceLock;
+use std::panic::{self, AssertUnwindSafe};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test offloading a synchronous mutator to the blocking pool.
+fn unblock_get_or_mutate() {
+    static LOCK: AsyncInPlaceOnceLock<u32> = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    block_on(async {
+        assert_eq!(LOCK.unblock_get_or_mutate(u32_square).await, &U32_MUTATED);
+        assert_eq!(LOCK.get_or_mutate(async |v| *v += 1).await, &U32_MUTATED);
+    });
+}
+
+#[test]
+/// A panicking blocking mutator propagates the panic and leaves the cell unmutated.
+fn unblock_get_or_mutate_panic() {
+    static LOCK: AsyncInPlaceOnceLock<u32> = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        block_on(LOCK.unblock_get_or_mutate(|_| panic!("oops")))
+    }));
+    assert!(res.is_err());
+    assert_eq!(LOCK.get(), None);
+    assert_eq!(
+        block_on(LOCK.unblock_get_or_mutate(u32_square)),
+        &U32_MUTATED
+    );
+}
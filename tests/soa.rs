@@ -0,0 +1,200 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use in_place_once_cell::{BoxedSoaInPlaceOnceCells, SoaInPlaceOnceCells};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+fn basic() {
+    let cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 4]);
+    assert_eq!(cells.len(), 4);
+    assert_eq!(cells.get(1), None);
+    assert_eq!(cells.get_or_mutate(1, u32_square), &U32_MUTATED);
+    assert_eq!(cells.get_or_mutate(1, |_| unreachable!()), &U32_MUTATED);
+    assert_eq!(cells.get(1), Some(&U32_MUTATED));
+    assert!(!cells.is_mutated(0));
+    assert_eq!(cells.get(4), None);
+    assert_eq!(
+        cells.into_inner(),
+        [U32_INIT, U32_MUTATED, U32_INIT, U32_INIT]
+    );
+}
+
+#[test]
+/// Test that indexing reads and writes mutated cells.
+fn index() {
+    let mut cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 2]);
+    cells.get_or_mutate(0, u32_square);
+    assert_eq!(cells[0], U32_MUTATED);
+    cells[0] += 1;
+    assert_eq!(cells.mutated(0), &(U32_MUTATED + 1));
+}
+
+#[test]
+#[should_panic = "the cell at index 1 is not mutated"]
+fn index_untouched() {
+    let cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 2]);
+    let _ = cells[1];
+}
+
+#[test]
+#[should_panic = "index out of bounds"]
+fn index_out_of_bounds() {
+    let cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 2]);
+    let _ = cells[2];
+}
+
+#[test]
+/// Test that a failed mutation leaves the cell untouched.
+fn try_mutate_error() {
+    let cells = SoaInPlaceOnceCells::from_fn(2, |_| U32_INIT);
+    assert_eq!(cells.get_or_try_mutate(0, |_| Err(())), Err(()));
+    assert!(!cells.is_mutated(0));
+    assert_eq!(cells.count_mutated(), 0);
+}
+
+#[test]
+/// Test that iteration yields every mutated cell in order, across bitset words.
+fn iter_mutated() {
+    let cells = SoaInPlaceOnceCells::from_fn(300, |i| i);
+    let indices = [0, 5, 63, 64, 65, 200, 299];
+    for i in indices.into_iter().rev() {
+        cells.get_or_mutate(i, |val| *val *= 2);
+    }
+
+    let mutated: Vec<_> = cells.iter_mutated().collect();
+    let expected: Vec<_> = indices.iter().map(|&i| (i, i * 2)).collect();
+    assert_eq!(
+        mutated
+            .into_iter()
+            .map(|(i, &v)| (i, v))
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(cells.count_mutated(), indices.len());
+    assert_eq!(
+        SoaInPlaceOnceCells::<u32>::new(vec![])
+            .iter_mutated()
+            .next(),
+        None
+    );
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn out_of_bounds() {
+    let cells = SoaInPlaceOnceCells::new(vec![U32_INIT]);
+    cells.get_or_mutate(1, u32_square);
+}
+
+#[test]
+fn debug_impl() {
+    let cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 3]);
+    cells.get_or_mutate(2, u32_square);
+    assert_eq!(format!("{cells:?}"), "{2: 1156}");
+}
+
+#[test]
+/// Test that disjoint chunks are mutated on their own threads, keeping their indices.
+fn chunks_mut() {
+    let bits = usize::BITS as usize;
+    let mut cells = SoaInPlaceOnceCells::from_fn(bits * 3 + 1, |i| i as u32);
+    std::thread::scope(|s| {
+        for mut chunk in cells.chunks_mut(bits * 2) {
+            s.spawn(move || {
+                for index in chunk.range().step_by(2) {
+                    let val = chunk.get_mut_or_mutate(index, u32_square);
+                    assert_eq!(*val as usize, index * index);
+                }
+                let last = chunk.range().end - 1;
+                assert_eq!(
+                    chunk.get_mut_or_try_mutate(last, |_| Err(())).is_err(),
+                    last % 2 == 1
+                );
+            });
+        }
+    });
+
+    assert_eq!(cells.count_mutated(), bits * 3 / 2 + 1);
+    for (index, val) in cells.iter_mutated() {
+        assert_eq!(index % 2, 0);
+        assert_eq!(*val as usize, index * index);
+    }
+}
+
+#[test]
+#[should_panic = "multiple of `usize::BITS`"]
+fn chunks_mut_unaligned() {
+    let mut cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 4]);
+    let _ = cells.chunks_mut(3);
+}
+
+#[test]
+/// Test that a mutator mutating its own cell panics, leaving the cell untouched, while it may
+/// mutate other cells.
+fn reentrant() {
+    let cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 2]);
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cells.get_or_mutate(0, |_| {
+            cells.get_or_mutate(0, u32_square);
+        });
+    }));
+    let payload = res.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"reentrant mutation of a `SoaInPlaceOnceCells` cell")
+    );
+    assert_eq!(cells.get(0), None);
+
+    let val = cells.get_or_mutate(0, |v| *v = *cells.get_or_mutate(1, u32_square) + 1);
+    assert_eq!(val, &(U32_MUTATED + 1));
+}
+
+#[test]
+/// Test that the values only unwrap once every cell is mutated.
+fn try_unwrap_all() {
+    let cells = SoaInPlaceOnceCells::new(vec![U32_INIT; 2]);
+    cells.get_or_mutate(0, u32_square);
+    let cells = cells.try_unwrap_all().unwrap_err();
+    cells.get_or_mutate(1, u32_square);
+    assert_eq!(cells.try_unwrap_all().ok(), Some(vec![U32_MUTATED; 2]));
+}
+
+#[test]
+/// Test that boxed cells clone the placeholder into a box only when they are mutated.
+fn boxed() {
+    let cells = BoxedSoaInPlaceOnceCells::new(100, [U32_INIT; 64]);
+    assert_eq!(cells.len(), 100);
+    assert_eq!(cells.get(3), None);
+    assert_eq!(cells.get_or_try_mutate(3, |_| Err(())), Err(()));
+    assert!(!cells.is_mutated(3));
+
+    let val = cells.get_or_mutate(3, |v| u32_square(&mut v[0]));
+    assert_eq!(val[0], U32_MUTATED);
+    assert_eq!(val[1], U32_INIT);
+    assert_eq!(cells.get_or_mutate(3, |_| unreachable!())[0], U32_MUTATED);
+    assert_eq!(cells.placeholder(), &[U32_INIT; 64]);
+    assert_eq!(cells.count_mutated(), 1);
+    assert_eq!(
+        cells.iter_mutated().map(|(i, _)| i).collect::<Vec<_>>(),
+        [3]
+    );
+
+    let boxes = cells.into_boxes();
+    assert!(boxes[2].is_none());
+    assert_eq!(boxes[3].as_ref().unwrap()[0], U32_MUTATED);
+}
+
+#[test]
+/// Test that a mutator mutating its own cell keeps the inner mutation.
+fn boxed_reentrant() {
+    let cells = BoxedSoaInPlaceOnceCells::new(2, U32_INIT);
+    let val = cells.get_or_mutate(0, |v| {
+        cells.get_or_mutate(0, u32_square);
+        *v = 0;
+    });
+    assert_eq!(val, &U32_MUTATED);
+    assert_eq!(format!("{cells:?}"), "{0: 1156}");
+}
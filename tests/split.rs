@@ -0,0 +1,35 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::thread;
+
+use in_place_once_cell::InPlaceOnceLock;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Readers see the value that the one writer mutates.
+fn split() {
+    let (writer, reader) = InPlaceOnceLock::new(U32_INIT).split();
+    assert_eq!(reader.get(), None);
+
+    let readers = [reader.clone(), writer.reader()];
+    thread::spawn(move || writer.get_or_mutate(u32_square).to_owned())
+        .join()
+        .unwrap();
+    assert_eq!(reader.get(), Some(&U32_MUTATED));
+    for reader in readers {
+        assert_eq!(reader.get(), Some(&U32_MUTATED));
+    }
+}
+
+#[test]
+/// A failed mutation leaves the writer free to try again.
+fn writer_retry() {
+    let (writer, reader) = InPlaceOnceLock::new(U32_INIT).split();
+    assert_eq!(writer.get_or_try_mutate(|_| Err(())), Err(()));
+    assert_eq!(writer.get_or_mutate(u32_square), &U32_MUTATED);
+    assert_eq!(writer.get(), Some(&U32_MUTATED));
+    assert_eq!(format!("{reader:?}"), "ReadHandle(InPlaceOnceLock(1156))");
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "stats")]
+
+mod common;
+
+use std::sync::Barrier;
+use std::thread;
+use std::time::Duration;
+
+use in_place_once_cell::{InPlaceOnceLock, LockStats};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+fn uncontended() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.stats(), LockStats::default());
+
+    lock.get_or_mutate(u32_square);
+    lock.get_or_mutate(u32_square);
+    let stats = lock.stats();
+    assert_eq!(stats.contended, 0);
+    assert_eq!(stats.wait_time, Duration::ZERO);
+    assert!(stats.mutation_time.is_some());
+}
+
+#[test]
+/// Callers that race a slow mutator are counted, along with the time they waited.
+fn contended() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let barrier = Barrier::new(4);
+    thread::scope(|s| {
+        s.spawn(|| {
+            lock.get_or_mutate(|v| {
+                barrier.wait();
+                thread::sleep(Duration::from_millis(50));
+                u32_square(v);
+            });
+        });
+        for _ in 0..3 {
+            s.spawn(|| {
+                barrier.wait();
+                assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+            });
+        }
+    });
+
+    let stats = lock.stats();
+    assert!((1..=3).contains(&stats.contended));
+    assert!(stats.wait_time > Duration::ZERO);
+    assert!(stats.mutation_time.unwrap() >= Duration::from_millis(50));
+
+    lock.reset_stats();
+    assert_eq!(lock.stats(), LockStats::default());
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::thread;
+
+use in_place_once_cell::{TakeOnceCell, singleton};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+fn take() {
+    let mut cell = TakeOnceCell::new(U32_INIT);
+    assert!(!cell.is_taken());
+    u32_square(cell.take().unwrap());
+    assert!(cell.is_taken());
+    assert!(cell.take().is_none());
+    assert_eq!(*cell.get_mut(), U32_MUTATED);
+    assert_eq!(format!("{cell:?}"), "TakeOnceCell { taken: true, .. }");
+    assert_eq!(cell.into_inner(), U32_MUTATED);
+}
+
+#[test]
+/// Only one of many racing threads takes the value.
+fn race() {
+    static CELL: TakeOnceCell<u32> = TakeOnceCell::new(U32_INIT);
+    let taken = thread::scope(|s| {
+        let threads: Vec<_> = (0..8).map(|_| s.spawn(|| CELL.take().is_some())).collect();
+        threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .filter(|&taken| taken)
+            .count()
+    });
+    assert_eq!(taken, 1);
+}
+
+#[test]
+/// Each expansion of `singleton!` declares its own static, taken once.
+fn singleton() {
+    fn buffer() -> Option<&'static mut [u32; 256]> {
+        singleton!(: [u32; 256] = [U32_INIT; 256])
+    }
+
+    let buf = buffer().unwrap();
+    u32_square(&mut buf[0]);
+    assert_eq!(buf[..2], [U32_MUTATED, U32_INIT]);
+    assert!(buffer().is_none());
+
+    let named: &'static mut u32 = singleton!(#[allow(unused)] NAMED: u32 = U32_INIT).unwrap();
+    assert_eq!(*named, U32_INIT);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use in_place_once_cell::{InPlaceOnceTCell, InPlaceOnceTCellOwner};
+use std::thread;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// Test that cells are mutated and read through their owner, from any thread.
+fn mutate_through_owner() {
+    struct Marker;
+    let mut owner = InPlaceOnceTCellOwner::<Marker>::new();
+    let cells: [InPlaceOnceTCell<Marker, u32>; 2] = [InPlaceOnceTCell::new(U32_INIT), 0.into()];
+
+    assert_eq!(owner.get(&cells[0]), None);
+    assert_eq!(owner.get_or_mutate(&cells[0], u32_square), &U32_MUTATED);
+    assert_eq!(owner.get_or_try_mutate(&cells[1], |_| Err(())), Err(()));
+    assert_eq!(
+        owner.get_or_mutate(&cells[0], |_| unreachable!()),
+        &U32_MUTATED
+    );
+
+    let owner = &owner;
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                assert_eq!(owner.get(&cells[0]), Some(&U32_MUTATED));
+                assert_eq!(owner.get(&cells[1]), None);
+            });
+        }
+    });
+}
+
+#[test]
+/// Test that only one owner per marker type exists at a time.
+fn one_owner() {
+    struct Marker;
+    let owner = InPlaceOnceTCellOwner::<Marker>::new();
+    assert!(InPlaceOnceTCellOwner::<Marker>::try_new().is_none());
+    drop(owner);
+    assert!(InPlaceOnceTCellOwner::<Marker>::try_new().is_some());
+}
+
+#[test]
+#[should_panic = "already exists"]
+fn second_owner() {
+    struct Marker;
+    let _owner = InPlaceOnceTCellOwner::<Marker>::new();
+    let _ = InPlaceOnceTCellOwner::<Marker>::new();
+}
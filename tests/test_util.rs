@@ -0,0 +1,92 @@
+#![cfg(feature = "test-util")]
+
+mod common;
+
+use in_place_once_cell::InPlaceOnceLock;
+use in_place_once_cell::test_util::{self, HookPoint};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[test]
+/// The hook sees the points of an uncontended mutation in order, and only while installed.
+fn hook_points() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let lock = InPlaceOnceLock::new(U32_INIT);
+
+    let guard = test_util::set_hook({
+        let seen = Rc::clone(&seen);
+        move |point| seen.borrow_mut().push(point)
+    });
+    assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+    drop(guard);
+    InPlaceOnceLock::new(U32_INIT).get_or_mutate(u32_square);
+
+    assert_eq!(
+        *seen.borrow(),
+        [HookPoint::BeforeCas, HookPoint::BeforePublish]
+    );
+}
+
+#[test]
+/// A second mutator that parks while the first is about to publish gets the first one's value,
+/// every time.
+fn forced_interleaving() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    let (publishing_tx, publishing_rx) = mpsc::channel();
+    let (parking_tx, parking_rx) = mpsc::channel();
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            let _hook = test_util::set_hook(move |point| {
+                if point == HookPoint::BeforePublish {
+                    publishing_tx.send(()).unwrap();
+                    parking_rx.recv().unwrap();
+                }
+            });
+            assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+        });
+
+        publishing_rx.recv().unwrap();
+        let _hook = test_util::set_hook(move |point| {
+            if point == HookPoint::BeforePark {
+                parking_tx.send(()).unwrap();
+            }
+        });
+        assert_eq!(
+            lock.get_or_mutate(|_| panic!("the first mutator won")),
+            &U32_MUTATED
+        );
+    });
+}
+
+#[cfg(feature = "futures-timer")]
+#[test]
+/// Timeouts started with a fake clock installed expire when it is advanced past them.
+fn fake_clock() {
+    use futures::executor::block_on;
+    use in_place_once_cell::AsyncInPlaceOnceLock;
+    use in_place_once_cell::test_util::FakeClock;
+    use std::pin::pin;
+    use std::time::Duration;
+
+    let clock = FakeClock::new();
+    let _clock = clock.install();
+    let lock = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    block_on(async {
+        let mut wait = pin!(lock.wait_timeout(Duration::from_secs(10)));
+        assert!(futures::poll!(wait.as_mut()).is_pending());
+        clock.advance(Duration::from_secs(9));
+        assert!(futures::poll!(wait.as_mut()).is_pending());
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(
+            futures::poll!(wait.as_mut()),
+            std::task::Poll::Ready(Err(..))
+        ));
+    });
+    assert_eq!(clock.now(), Duration::from_secs(10));
+}
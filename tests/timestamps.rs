@@ -0,0 +1,30 @@
+#![cfg(feature = "timestamps")]
+
+mod common;
+
+use std::time::{Instant, SystemTime};
+
+use in_place_once_cell::InPlaceOnceLock;
+
+use common::{U32_INIT, u32_square};
+
+#[test]
+/// Test that the time is recorded once the mutator returns, and only then.
+fn mutated_when() {
+    let lock = InPlaceOnceLock::new(U32_INIT);
+    assert_eq!(lock.mutated_when(), None);
+    assert!(lock.get_or_try_mutate(|_| Err(())).is_err());
+    assert_eq!(lock.mutated_when(), None);
+
+    let before = (Instant::now(), SystemTime::now());
+    lock.get_or_mutate(u32_square);
+    let when = lock.mutated_when().unwrap();
+    assert!(when.instant >= before.0 && when.instant <= Instant::now());
+    assert!(when.system_time >= before.1);
+
+    lock.get_or_mutate(u32_square);
+    assert_eq!(lock.mutated_when(), Some(when));
+
+    let lock = unsafe { InPlaceOnceLock::from_raw_parts(U32_INIT, true) };
+    assert_eq!(lock.mutated_when(), None);
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use in_place_once_cell::{AsyncInPlaceOnceLock, InPlaceOnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+#[tokio::test]
+/// Test the `watch` bridge before and after the mutation.
+async fn to_watch() {
+    static LOCK: AsyncInPlaceOnceLock<u32> = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let mut rx = LOCK.to_watch();
+    assert_eq!(*rx.borrow(), None);
+
+    LOCK.get_or_mutate(async |v| u32_square(v)).await;
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow(), Some(U32_MUTATED));
+    assert_eq!(*LOCK.to_watch().borrow(), Some(U32_MUTATED));
+}
+
+#[tokio::test]
+/// Test offloading a synchronous mutator to the blocking pool.
+async fn blocking_get_or_mutate() {
+    static LOCK: AsyncInPlaceOnceLock<u32> = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    assert_eq!(LOCK.blocking_get_or_mutate(u32_square).await, &U32_MUTATED);
+    assert_eq!(LOCK.get_or_mutate(async |v| *v += 1).await, &U32_MUTATED);
+}
+
+#[tokio::test]
+/// A panicking blocking mutator propagates the panic and leaves the cell unmutated.
+async fn blocking_get_or_mutate_panic() {
+    static LOCK: AsyncInPlaceOnceLock<u32> = AsyncInPlaceOnceLock::new(U32_INIT);
+
+    let res = tokio::spawn(LOCK.blocking_get_or_mutate(|_| panic!("oops"))).await;
+    assert!(res.unwrap_err().is_panic());
+    assert_eq!(LOCK.get(), None);
+    assert_eq!(LOCK.blocking_get_or_mutate(u32_square).await, &U32_MUTATED);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+/// Offloading a synchronous lock's mutator to the blocking pool runs it exactly once.
+async fn sync_blocking_get_or_mutate() {
+    static LOCK: InPlaceOnceLock<u32> = InPlaceOnceLock::new(U32_INIT);
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let tasks: Vec<_> = (0..16)
+        .map(|_| {
+            tokio::spawn(async {
+                *LOCK
+                    .blocking_get_or_mutate(|v| {
+                        CALLS.fetch_add(1, Ordering::Relaxed);
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                        u32_square(v);
+                    })
+                    .await
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        assert_eq!(task.await.unwrap(), U32_MUTATED);
+    }
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+}
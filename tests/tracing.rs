@@ -0,0 +1,155 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use in_place_once_cell::InPlaceOnceLock;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use common::{U32_INIT, U32_MUTATED, u32_square};
+
+/// Records every event as `"<message> <label>"`, along with the span names.
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>,
+    spans: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    label: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "label" {
+            self.label = value.to_owned();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        self.spans
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", attrs.metadata().name(), fields.label));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let line = format!("{} {}", fields.message, fields.label);
+        self.events.lock().unwrap().push(line.trim_end().to_owned());
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn record(f: impl FnOnce()) -> (Vec<String>, Vec<String>) {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), f);
+    let events = recorder.events.lock().unwrap().clone();
+    let spans = recorder.spans.lock().unwrap().clone();
+    (events, spans)
+}
+
+#[test]
+/// A mutation is traced from start to completion, under a labeled span.
+fn mutation() {
+    let (events, spans) = record(|| {
+        let lock = InPlaceOnceLock::new(U32_INIT).with_label("config");
+        assert_eq!(lock.get_or_mutate(u32_square), &U32_MUTATED);
+        lock.get_or_mutate(u32_square);
+    });
+
+    assert_eq!(spans, ["mutate config"]);
+    assert_eq!(events, ["mutation started", "mutation completed"]);
+}
+
+#[test]
+fn failure() {
+    let (events, _) = record(|| {
+        let lock = InPlaceOnceLock::new(U32_INIT);
+        assert_eq!(lock.get_or_try_mutate(|_| Err(())), Err(()));
+    });
+
+    assert_eq!(events, ["mutation started", "mutation failed"]);
+}
+
+#[test]
+/// A panicking mutator is reported as poisoning the lock.
+fn panic() {
+    let (events, _) = record(|| {
+        let lock = InPlaceOnceLock::new(U32_INIT);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| lock.get_or_mutate(|_| panic!())));
+        assert!(res.is_err());
+    });
+
+    assert_eq!(
+        events,
+        ["mutation started", "mutator panicked, lock poisoned"]
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+/// A waiting async mutator reports contention, and a cancelled one is reported as such.
+fn async_lock() {
+    use futures::FutureExt;
+    use futures::executor::block_on;
+    use in_place_once_cell::AsyncInPlaceOnceLock;
+
+    let (events, spans) = record(|| {
+        let lock = AsyncInPlaceOnceLock::new(U32_INIT).with_label("async");
+        let mut first = Box::pin(lock.get_or_mutate(async |v| {
+            futures::pending!();
+            u32_square(v);
+        }));
+        assert!((&mut first).now_or_never().is_none());
+
+        let mut second = Box::pin(lock.get_or_mutate(async |v| u32_square(v)));
+        assert!((&mut second).now_or_never().is_none());
+        drop(first);
+        assert_eq!(block_on(second), &U32_MUTATED);
+    });
+
+    assert_eq!(spans, ["mutate async", "mutate async"]);
+    assert_eq!(
+        events,
+        [
+            "mutation started",
+            "contended with another mutator async",
+            "mutation cancelled",
+            "mutation started",
+            "mutation completed",
+        ]
+    );
+}
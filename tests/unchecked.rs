@@ -0,0 +1,24 @@
+mod common;
+
+use in_place_once_cell::unchecked::UncheckedOnceCell;
+
+use common::{U32_INIT, U32_MUTATED};
+
+#[test]
+/// Test basic functionality
+fn basic() {
+    let c = UncheckedOnceCell::new(U32_INIT);
+    // SAFETY: the cell is mutated once, and nothing else touches it meanwhile.
+    assert_eq!(unsafe { c.mutate(|v| *v *= *v) }, &U32_MUTATED);
+    // SAFETY: the cell was just mutated.
+    assert_eq!(unsafe { c.assume_mutated() }, &U32_MUTATED);
+    assert_eq!(format!("{c:?}"), "UncheckedOnceCell(1156)");
+    assert_eq!(c.into_inner(), U32_MUTATED);
+}
+
+#[test]
+/// Test that the cell costs nothing over its value.
+fn layout() {
+    assert_eq!(size_of::<UncheckedOnceCell<u32>>(), size_of::<u32>());
+    assert_eq!(size_of::<UncheckedOnceCell<[u8; 3]>>(), 3);
+}